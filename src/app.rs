@@ -1,9 +1,24 @@
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
+
 use ratatui::widgets::ListState;
+use tokio::sync::{mpsc, oneshot, Mutex};
+use tokio::task::AbortHandle;
 use tui_textarea::TextArea;
 
-use crate::db::{DatabaseConnection, QueryResult, TableInfo};
+use crate::config::Config;
+use crate::keymap::KeyMap;
+use crate::export::RowFormat;
+use crate::db::{BackendId, ColumnInfo, DatabaseConnection, LimitSyntax, QueryResult, SshTunnel, TableInfo, Transaction};
 use crate::storage::RecentConnection;
-use crate::ui::{QueryButton, TreeState, ResultsState};
+use crate::ui::{recent_rows, ColumnStats, Completion, ConnectionForm, FormField, QueryButton, RecentRow, TreeSnapshot, TreeState, ResultsState, VimMode};
+use crate::ui::export::ExportDialog;
+use crate::ui::palette::Palette;
+use crate::ui::params::ParamForm;
+use crate::ui::saved::SavedQueryList;
+use crate::ui::pagination::{PageButton, PaginationRegion};
 use crate::ui::query::ButtonRegion;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -16,6 +31,8 @@ pub enum AppState {
 pub enum ConnectionFocus {
     RecentList,
     NewInput,
+    /// The field-by-field alternative to typing a URL.
+    Form,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -26,110 +43,524 @@ pub enum Focus {
     Results,
 }
 
+#[derive(Debug, Clone)]
+pub enum Modal<'a> {
+    Help,
+    Export(Box<ExportDialog<'a>>),
+    Prompt(Box<Prompt<'a>>),
+    TypesLegend { scroll: usize },
+    Confirm(Confirm),
+    RowDetail { format: RowFormat, scroll: usize },
+    CellDetail { scroll: usize },
+    /// CREATE statement of the sidebar's table.
+    Ddl { table: String, ddl: String, scroll: usize },
+    Palette(Box<Palette<'a>>),
+    /// Values for the bind parameters of a query about to run.
+    Params(Box<ParamForm<'a>>),
+    SavedQueries(SavedQueryList),
+    ColumnStats { column: String, stats: ColumnStats },
+    /// Show or hide result columns; `selected` is a column index.
+    Columns { selected: usize },
+}
+
+/// A statement waiting for the user to confirm before it runs.
+#[derive(Debug, Clone)]
+pub struct Confirm {
+    pub title: String,
+    pub message: String,
+    pub query: String,
+    pub action: ConfirmAction,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConfirmAction {
+    RunQuery,
+    KillSession,
+    UpdateCell,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PromptAction {
+    AttachDatabase,
+    /// Name to save the editor's query under.
+    SaveQuery,
+    /// New value for a cell of the results; `key_col` is the primary key.
+    EditCell { row: usize, col: usize, key_col: usize },
+    /// Label for the recent connection with this id.
+    RenameConnection { id: i64 },
+    /// Group for the recent connection with this id; empty ungroups it.
+    ConnectionGroup { id: i64 },
+}
+
+#[derive(Debug, Clone)]
+pub struct Prompt<'a> {
+    pub action: PromptAction,
+    pub title: String,
+    pub hint: String,
+    pub input: TextArea<'a>,
+}
+
+impl Prompt<'_> {
+    pub fn new(action: PromptAction, title: impl Into<String>, hint: impl Into<String>) -> Self {
+        let mut input = TextArea::default();
+        input.set_cursor_line_style(ratatui::style::Style::default());
+        Self {
+            action,
+            title: title.into(),
+            hint: hint.into(),
+            input,
+        }
+    }
+
+    pub fn value(&self) -> String {
+        self.input.lines().join("")
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SidebarClick {
+    None,
+    Selected,
+    /// A table was double-clicked.
+    OpenTable,
+}
+
+/// Password re-entry offered after a connection fails authentication.
+#[derive(Debug, Clone)]
+pub struct PasswordRetry<'a> {
+    pub connection_string: String,
+    pub input: TextArea<'a>,
+}
+
+impl PasswordRetry<'_> {
+    pub fn new(connection_string: String) -> Self {
+        let mut input = TextArea::default();
+        input.set_cursor_line_style(ratatui::style::Style::default());
+        input.set_mask_char('•');
+        Self {
+            connection_string,
+            input,
+        }
+    }
+
+    pub fn password(&self) -> String {
+        self.input.lines().join("")
+    }
+}
+
+/// LIMIT/OFFSET paging over a query generated for a table, or over an
+/// ad-hoc query without its own LIMIT.
+#[derive(Debug, Clone)]
+pub struct Pagination {
+    pub table: Option<TableInfo>,
+    pub base_query: String,
+    pub syntax: LimitSyntax,
+    pub page_size: usize,
+    pub page: usize,
+    pub total_rows: Option<u64>,
+}
+
+impl Pagination {
+    pub fn new(table: TableInfo, page_size: usize, syntax: LimitSyntax) -> Self {
+        Self {
+            base_query: format!("SELECT * FROM {}.{}", table.schema, table.name),
+            table: Some(table),
+            syntax,
+            page_size: page_size.max(1),
+            page: 0,
+            total_rows: None,
+        }
+    }
+
+    pub fn for_query(query: &str, page_size: usize, syntax: LimitSyntax) -> Option<Self> {
+        if !crate::db::is_pageable(query) {
+            return None;
+        }
+        Some(Self {
+            base_query: crate::db::trim_statement_end(query).trim_start().to_string(),
+            table: None,
+            syntax,
+            page_size: page_size.max(1),
+            page: 0,
+            total_rows: None,
+        })
+    }
+
+    /// Number of rows before the current page.
+    pub fn offset(&self) -> usize {
+        self.page * self.page_size
+    }
+
+    pub fn query(&self) -> String {
+        self.syntax.apply(&self.base_query, self.page_size, self.offset())
+    }
+
+    pub fn total_pages(&self) -> Option<usize> {
+        self.total_rows
+            .map(|total| (total as usize).div_ceil(self.page_size).max(1))
+    }
+
+    /// Infers the total once a short (final) page has been fetched.
+    pub fn record_page_len(&mut self, rows: usize) {
+        if rows < self.page_size {
+            self.total_rows = Some((self.offset() + rows) as u64);
+        }
+    }
+}
+
+/// What the main loop does with a query's result once it arrives.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum QueryKind {
+    Editor,
+    Sessions,
+    /// A reviewed cell UPDATE; the page is reloaded after it.
+    CellUpdate,
+}
+
+/// Sent back by the task that ran a query.
+pub struct QueryOutcome {
+    pub query: String,
+    pub kind: QueryKind,
+    pub result: anyhow::Result<QueryResult>,
+    pub timed_out: bool,
+    /// The connection was lost and could not be re-established.
+    pub disconnected: bool,
+}
+
+/// Sidebar contents fetched on a background task.
+pub enum SidebarLoad {
+    Tables {
+        schema: String,
+        result: anyhow::Result<Vec<TableInfo>>,
+    },
+    Columns {
+        schema: String,
+        table: String,
+        result: anyhow::Result<Vec<ColumnInfo>>,
+    },
+    /// The schemas listed again, with what was expanded before.
+    Reload {
+        snapshot: TreeSnapshot,
+        result: anyhow::Result<SidebarReload>,
+    },
+}
+
+pub struct SidebarReload {
+    pub schemas: Vec<String>,
+    pub tables: Vec<(String, Vec<TableInfo>)>,
+    pub columns: Vec<(String, String, Vec<ColumnInfo>)>,
+}
+
+/// Sorts numerically when every non-NULL cell of the column is a number,
+/// otherwise as text. NULLs sort last when ascending.
+fn sort_rows(rows: &mut [Vec<Option<String>>], col: usize, ascending: bool) {
+    fn cell(row: &[Option<String>], col: usize) -> Option<&str> {
+        row.get(col).and_then(Option::as_deref)
+    }
+    let numeric = rows
+        .iter()
+        .filter_map(|row| cell(row, col))
+        .all(|c| c.trim().parse::<f64>().is_ok());
+    rows.sort_by(|a, b| {
+        let ordering = match (cell(a, col), cell(b, col)) {
+            (None, None) => std::cmp::Ordering::Equal,
+            (None, Some(_)) => std::cmp::Ordering::Greater,
+            (Some(_), None) => std::cmp::Ordering::Less,
+            (Some(a), Some(b)) if numeric => {
+                let parse = |c: &str| c.trim().parse::<f64>().unwrap_or(f64::NAN);
+                parse(a).total_cmp(&parse(b))
+            }
+            (Some(a), Some(b)) => a.cmp(b),
+        };
+        if ascending { ordering } else { ordering.reverse() }
+    });
+}
+
+const SPINNER: [&str; 10] = ["⠋", "⠙", "⠹", "⠸", "⠼", "⠴", "⠦", "⠧", "⠇", "⠏"];
+
+const STATUS_TTL: Duration = Duration::from_secs(4);
+/// Two clicks on the same sidebar node within this window open it.
+const DOUBLE_CLICK: Duration = Duration::from_millis(400);
+const DEFAULT_SIDEBAR_PCT: u16 = 22;
+pub const MIN_SIDEBAR_PCT: u16 = 10;
+pub const MAX_SIDEBAR_PCT: u16 = 60;
+
+#[derive(Debug, Clone)]
+pub struct StatusMessage {
+    pub text: String,
+    pub is_error: bool,
+    pub created: Instant,
+}
+
 pub struct App<'a> {
+    pub config: Config,
+    pub keymap: KeyMap,
     pub state: AppState,
     pub focus: Focus,
+    pub modal: Option<Modal<'a>>,
+    pub status_message: Option<StatusMessage>,
     pub selected_button: QueryButton,
     pub hovered_button: QueryButton,
     pub connection_input: TextArea<'a>,
     pub connection_error: Option<String>,
+    pub password_retry: Option<PasswordRetry<'a>>,
+    /// Prompt open over the connection dialog, such as renaming a recent
+    /// connection; the browser's prompts are modals instead.
+    pub connection_prompt: Option<Prompt<'a>>,
     pub connection: Option<DatabaseConnection>,
+    pub connection_string: Option<String>,
+    /// Credential-free label of the connection, for the status bar.
+    pub connection_name: String,
+    /// Fetched once on connect; None when the server wouldn't say.
+    pub server_version: Option<String>,
+    /// Kept alive for as long as the connection that goes through it.
+    pub tunnel: Option<SshTunnel>,
+    /// Refuse anything but SELECT/EXPLAIN/SHOW/WITH.
+    pub read_only: bool,
+    /// Open transaction that editor queries run in instead of the pool.
+    /// Shared with the running query's task, so it is locked while one runs.
+    pub transaction: Option<Arc<Mutex<Transaction>>>,
+    /// Tables of the schemas loaded so far.
     pub tables: Vec<TableInfo>,
+    /// Row counts per schema, fetched in the background.
+    pub row_counts_tx: mpsc::UnboundedSender<(String, Vec<(String, u64)>)>,
+    pub row_counts_rx: mpsc::UnboundedReceiver<(String, Vec<(String, u64)>)>,
     pub tree_state: TreeState,
+    /// The sidebar fetch in flight; one at a time, like the tree's loading
+    /// markers.
+    sidebar_rx: Option<oneshot::Receiver<SidebarLoad>>,
+    sidebar_task: Option<AbortHandle>,
     pub query_input: TextArea<'a>,
+    /// Suggestions shown under the editor's cursor.
+    pub completion: Option<Completion>,
+    /// Editor mode while `config.vim_mode` is on.
+    pub vim_mode: VimMode,
+    /// First key of a two-key vim command such as `dd`.
+    pub vim_pending: Option<char>,
+    /// Columns fetched for completion and the sidebar, by (schema, table).
+    pub column_cache: HashMap<(String, String), Vec<ColumnInfo>>,
     pub query_result: QueryResult,
+    /// All rows in query order while the grid is filtered or sorted.
+    pub original_rows: Option<Vec<Vec<Option<String>>>>,
+    /// Set when the last query failed; shown instead of the grid.
+    pub query_error: Option<String>,
+    pub error_scroll: usize,
     pub results_state: ResultsState,
     pub should_quit: bool,
     pub button_region: Option<ButtonRegion>,
     pub recent_connections: Vec<RecentConnection>,
+    /// Selected line of the recent list, which may be a group header.
     pub recent_connections_state: ListState,
+    pub collapsed_groups: HashSet<String>,
     pub connection_focus: ConnectionFocus,
+    pub connection_form: ConnectionForm<'a>,
     pub sidebar_area: Option<ratatui::layout::Rect>,
+    /// Area split between the sidebar and the right panel.
+    pub browser_area: Option<ratatui::layout::Rect>,
+    /// Share of `browser_area` given to the sidebar, set by dragging its edge.
+    pub sidebar_pct: u16,
+    /// The sidebar's right edge is being dragged.
+    pub resizing_sidebar: bool,
+    pub show_sidebar: bool,
+    last_sidebar_click: Option<(Instant, usize)>,
+    /// The results grid currently shows the server's active sessions.
+    pub sessions_view: bool,
+    pub pagination: Option<Pagination>,
+    /// Last parameter values entered for each query, for this session.
+    pub param_values: HashMap<String, Vec<String>>,
+    pub pagination_region: Option<PaginationRegion>,
+    pub hovered_page_button: PageButton,
+    /// A query is executing on a background task.
+    pub query_running: bool,
+    pub query_rx: Option<oneshot::Receiver<QueryOutcome>>,
+    query_task: Option<AbortHandle>,
+    /// Where the running query runs, for cancelling it on the server.
+    running_backend: BackendId,
+    query_started: Instant,
+    /// Set by the query task while it re-establishes a lost connection.
+    pub reconnecting: Arc<AtomicBool>,
 }
 
 impl<'a> App<'a> {
-    pub fn new() -> Self {
+    pub fn new(config: Config, keymap: KeyMap) -> Self {
         let mut connection_input = TextArea::default();
         connection_input.set_cursor_line_style(ratatui::style::Style::default());
+        // Offered, not connected to: Enter in the input still decides.
+        if let Ok(url) = std::env::var("DATABASE_URL")
+            && !url.trim().is_empty()
+        {
+            connection_input.insert_str(url.trim());
+        }
 
         let mut query_input = TextArea::default();
         query_input.set_cursor_line_style(ratatui::style::Style::default());
 
+        let (row_counts_tx, row_counts_rx) = mpsc::unbounded_channel();
+
         Self {
+            config,
+            keymap,
             state: AppState::Connection,
             focus: Focus::Sidebar,
+            modal: None,
+            status_message: None,
             selected_button: QueryButton::None,
             hovered_button: QueryButton::None,
             connection_input,
             connection_error: None,
+            password_retry: None,
+            connection_prompt: None,
             connection: None,
+            connection_string: None,
+            connection_name: String::new(),
+            server_version: None,
+            tunnel: None,
+            read_only: false,
+            transaction: None,
             tables: vec![],
+            row_counts_tx,
+            row_counts_rx,
             tree_state: TreeState::default(),
+            sidebar_rx: None,
+            sidebar_task: None,
             query_input,
+            completion: None,
+            vim_mode: VimMode::default(),
+            vim_pending: None,
+            column_cache: HashMap::new(),
             query_result: QueryResult::empty(),
+            original_rows: None,
+            query_error: None,
+            error_scroll: 0,
             results_state: ResultsState::new(),
             should_quit: false,
             button_region: None,
             recent_connections: vec![],
             recent_connections_state: ListState::default(),
+            collapsed_groups: HashSet::new(),
             connection_focus: ConnectionFocus::RecentList,
+            connection_form: ConnectionForm::new(),
             sidebar_area: None,
+            browser_area: None,
+            sidebar_pct: DEFAULT_SIDEBAR_PCT,
+            resizing_sidebar: false,
+            show_sidebar: true,
+            last_sidebar_click: None,
+            sessions_view: false,
+            pagination: None,
+            param_values: HashMap::new(),
+            pagination_region: None,
+            hovered_page_button: PageButton::None,
+            query_running: false,
+            query_rx: None,
+            query_task: None,
+            running_backend: BackendId::default(),
+            query_started: Instant::now(),
+            reconnecting: Arc::new(AtomicBool::new(false)),
         }
     }
 
-    pub fn set_tables(&mut self, tables: Vec<TableInfo>) {
-        self.tree_state = TreeState::from_tables(&tables);
-        self.tables = tables;
+    /// Whether the current connection matches a configured production pattern.
+    pub fn is_production(&self) -> bool {
+        self.connection_string
+            .as_deref()
+            .is_some_and(|c| self.config.is_production(c))
+    }
+
+    pub fn set_schemas(&mut self, schemas: &[String]) {
+        self.abort_sidebar_load();
+        self.tree_state = TreeState::from_schemas(schemas);
+        self.tables.clear();
+        self.column_cache.clear();
+    }
+
+    pub fn set_row_counts(&mut self, schema: &str, counts: &[(String, u64)]) {
+        for table in self.tables.iter_mut().filter(|t| t.schema == schema) {
+            if let Some((_, count)) = counts.iter().find(|(name, _)| *name == table.name) {
+                table.row_count = Some(*count);
+            }
+        }
+        self.tree_state.set_row_counts(schema, counts);
     }
 
     pub fn set_recent_connections(&mut self, connections: Vec<RecentConnection>) {
         self.recent_connections = connections;
         if !self.recent_connections.is_empty() {
-            self.recent_connections_state.select(Some(0));
+            let first = self.recent_rows().iter().position(|row| matches!(row, RecentRow::Connection(_)));
+            self.recent_connections_state.select(Some(first.unwrap_or(0)));
             self.connection_focus = ConnectionFocus::RecentList;
         } else {
             self.connection_focus = ConnectionFocus::NewInput;
         }
     }
 
+    pub fn recent_rows(&self) -> Vec<RecentRow> {
+        recent_rows(&self.recent_connections, &self.collapsed_groups)
+    }
+
+    /// Selects the line of the connection with this id, if it is shown.
+    pub fn select_recent_connection(&mut self, id: i64) {
+        let position = self.recent_rows().iter().position(|row| {
+            matches!(row, RecentRow::Connection(i) if self.recent_connections[*i].id == id)
+        });
+        if position.is_some() {
+            self.recent_connections_state.select(position);
+        }
+    }
+
+    /// Collapses or expands the selected group; false when a connection
+    /// is selected instead.
+    pub fn toggle_recent_group(&mut self) -> bool {
+        let rows = self.recent_rows();
+        let Some(RecentRow::Group { name, .. }) = self.recent_connections_state.selected().and_then(|i| rows.get(i))
+        else {
+            return false;
+        };
+        if !self.collapsed_groups.remove(name) {
+            self.collapsed_groups.insert(name.clone());
+        }
+        true
+    }
+
     pub fn select_next_recent(&mut self) {
-        if self.recent_connections.is_empty() {
+        let len = self.recent_rows().len();
+        if len == 0 {
             return;
         }
         let i = match self.recent_connections_state.selected() {
-            Some(i) => (i + 1) % self.recent_connections.len(),
+            Some(i) => (i + 1) % len,
             None => 0,
         };
         self.recent_connections_state.select(Some(i));
     }
 
     pub fn select_prev_recent(&mut self) {
-        if self.recent_connections.is_empty() {
+        let len = self.recent_rows().len();
+        if len == 0 {
             return;
         }
         let i = match self.recent_connections_state.selected() {
-            Some(i) => {
-                if i == 0 {
-                    self.recent_connections.len() - 1
-                } else {
-                    i - 1
-                }
-            }
-            None => 0,
+            Some(0) | None => len - 1,
+            Some(i) => i - 1,
         };
         self.recent_connections_state.select(Some(i));
     }
 
     pub fn get_selected_recent_connection(&self) -> Option<&RecentConnection> {
-        self.recent_connections_state
-            .selected()
-            .and_then(|i| self.recent_connections.get(i))
+        match self.recent_rows().get(self.recent_connections_state.selected()?)? {
+            RecentRow::Connection(i) => self.recent_connections.get(*i),
+            RecentRow::Group { .. } => None,
+        }
     }
 
     pub fn toggle_connection_focus(&mut self) {
         self.connection_focus = match self.connection_focus {
             ConnectionFocus::RecentList => ConnectionFocus::NewInput,
             ConnectionFocus::NewInput => {
+                self.connection_form.field = FormField::Engine;
+                ConnectionFocus::Form
+            }
+            ConnectionFocus::Form => {
                 if !self.recent_connections.is_empty() {
                     if self.recent_connections_state.selected().is_none() {
                         self.recent_connections_state.select(Some(0));
@@ -143,11 +574,13 @@ impl<'a> App<'a> {
     }
 
     pub fn cycle_focus(&mut self) {
+        self.completion = None;
         self.focus = match self.focus {
             Focus::Sidebar => Focus::Query,
             Focus::Query => Focus::QueryButtons,
             Focus::QueryButtons => Focus::Results,
-            Focus::Results => Focus::Sidebar,
+            Focus::Results if self.show_sidebar => Focus::Sidebar,
+            Focus::Results => Focus::Query,
         };
         if self.focus == Focus::QueryButtons {
             self.selected_button = QueryButton::Run;
@@ -156,21 +589,36 @@ impl<'a> App<'a> {
         }
     }
 
+    pub fn toggle_sidebar(&mut self) {
+        self.show_sidebar = !self.show_sidebar;
+        if !self.show_sidebar {
+            self.sidebar_area = None;
+            self.tree_state.searching = false;
+            if self.focus == Focus::Sidebar {
+                self.focus = Focus::Query;
+            }
+        }
+    }
+
     pub fn cycle_button(&mut self) {
         self.selected_button = match self.selected_button {
             QueryButton::None => QueryButton::Run,
-            QueryButton::Run => QueryButton::Clear,
+            QueryButton::Run => QueryButton::Format,
+            QueryButton::Format => QueryButton::Clear,
             QueryButton::Clear => QueryButton::Copy,
-            QueryButton::Copy => QueryButton::Run,
+            QueryButton::Copy => QueryButton::Export,
+            QueryButton::Export => QueryButton::Run,
         };
     }
 
     pub fn cycle_button_reverse(&mut self) {
         self.selected_button = match self.selected_button {
-            QueryButton::None => QueryButton::Copy,
-            QueryButton::Run => QueryButton::Copy,
-            QueryButton::Clear => QueryButton::Run,
+            QueryButton::None => QueryButton::Export,
+            QueryButton::Run => QueryButton::Export,
+            QueryButton::Format => QueryButton::Run,
+            QueryButton::Clear => QueryButton::Format,
             QueryButton::Copy => QueryButton::Clear,
+            QueryButton::Export => QueryButton::Copy,
         };
     }
 
@@ -179,25 +627,395 @@ impl<'a> App<'a> {
         self.query_input.set_cursor_line_style(ratatui::style::Style::default());
     }
 
+    /// The identifier being typed before the editor's cursor, and the name
+    /// before the `.` in front of it, if any.
+    pub fn completion_context(&self) -> (Option<String>, String) {
+        let (row, col) = self.query_input.cursor();
+        let line = self.query_input.lines().get(row).map_or("", String::as_str);
+        let before: Vec<char> = line.chars().take(col).collect();
+        let is_ident = |c: &char| c.is_alphanumeric() || *c == '_';
+
+        let start = before.len() - before.iter().rev().take_while(|c| is_ident(c)).count();
+        let prefix: String = before[start..].iter().collect();
+        if start == 0 || before[start - 1] != '.' {
+            return (None, prefix);
+        }
+        let qualifier_start = before[..start - 1]
+            .iter()
+            .rposition(|c| !is_ident(c) && !matches!(c, '"' | '`'))
+            .map_or(0, |i| i + 1);
+        let qualifier: String = before[qualifier_start..start - 1]
+            .iter()
+            .filter(|c| !matches!(c, '"' | '`'))
+            .collect();
+        (Some(qualifier).filter(|q| !q.is_empty()), prefix)
+    }
+
+    /// The (schema, table) a name before `.` stands for: an alias or a
+    /// table named in the query, or a table from the sidebar.
+    pub fn resolve_table(&self, name: &str) -> Option<(String, String)> {
+        let query = self.get_query_text();
+        let reference = crate::db::table_references(&query).into_iter().find(|r| {
+            r.alias.as_deref().is_some_and(|a| a.eq_ignore_ascii_case(name)) || r.table.eq_ignore_ascii_case(name)
+        });
+        let (schema, table) = match reference {
+            Some(r) => (r.schema, r.table),
+            None => (None, name.to_string()),
+        };
+        self.tables
+            .iter()
+            .find(|t| t.name.eq_ignore_ascii_case(&table) && schema.as_ref().is_none_or(|s| t.schema.eq_ignore_ascii_case(s)))
+            .map(|t| (t.schema.clone(), t.name.clone()))
+    }
+
+    /// Tables of the query whose columns are offered without a qualifier.
+    pub fn query_tables(&self) -> Vec<(String, String)> {
+        crate::db::table_references(&self.get_query_text())
+            .iter()
+            .filter_map(|r| self.resolve_table(r.alias.as_deref().unwrap_or(&r.table)))
+            .collect()
+    }
+
+    /// Names starting with `prefix`: tables of a schema, or columns of a
+    /// table, after `qualifier.`; otherwise schemas, tables and the columns
+    /// of the tables in the query.
+    pub fn completion_items(&self, qualifier: Option<&str>, prefix: &str) -> Vec<String> {
+        let mut items: Vec<String> = Vec::new();
+        let columns = |schema: &str, table: &str| {
+            self.column_cache
+                .get(&(schema.to_string(), table.to_string()))
+                .into_iter()
+                .flatten()
+                .map(|c| c.name.clone())
+        };
+        match qualifier {
+            Some(qualifier) => {
+                if let Some(schema) = self.tree_state.schema_names().find(|s| s.eq_ignore_ascii_case(qualifier)) {
+                    items.extend(self.tables.iter().filter(|t| t.schema == schema).map(|t| t.name.clone()));
+                }
+                if let Some((schema, table)) = self.resolve_table(qualifier) {
+                    items.extend(columns(&schema, &table));
+                }
+            }
+            None => {
+                for (schema, table) in self.query_tables() {
+                    items.extend(columns(&schema, &table));
+                }
+                items.extend(self.tables.iter().map(|t| t.name.clone()));
+                items.extend(self.tree_state.schema_names().map(str::to_string));
+            }
+        }
+        let prefix = prefix.to_lowercase();
+        let mut seen = HashSet::new();
+        items.retain(|item| item.to_lowercase().starts_with(&prefix) && seen.insert(item.clone()));
+        items
+    }
+
+    /// Replaces the word before the cursor with the selected suggestion.
+    pub fn accept_completion(&mut self) {
+        let Some(completion) = self.completion.take() else {
+            return;
+        };
+        let Some(item) = completion.selected_item() else {
+            return;
+        };
+        let plain = item.starts_with(|c: char| c.is_ascii_lowercase() || c == '_')
+            && item.chars().all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_')
+            && !crate::db::SQL_KEYWORDS.contains(&item.to_ascii_uppercase().as_str());
+        let text = match &self.connection {
+            Some(conn) if !plain => conn.quote_identifier(item),
+            _ => item.to_string(),
+        };
+        for _ in 0..completion.prefix_len {
+            self.query_input.delete_char();
+        }
+        self.query_input.insert_str(text);
+    }
+
+    /// Reformats the editor's query; undo brings the original back.
+    pub fn format_query(&mut self) {
+        let query = self.get_query_text();
+        if query.trim().is_empty() {
+            return;
+        }
+        self.query_input.select_all();
+        self.query_input.insert_str(crate::db::format_sql(&query));
+    }
+
+    pub fn set_query_text(&mut self, text: &str) {
+        self.query_input = TextArea::from(text.lines());
+        self.query_input.set_cursor_line_style(ratatui::style::Style::default());
+    }
+
     pub fn get_query_text(&self) -> String {
         self.query_input.lines().join("\n")
     }
 
     pub fn set_query_result(&mut self, result: QueryResult) {
+        // Another page or a re-run keeps the columns the user hid.
+        let hidden = std::mem::take(&mut self.results_state.hidden_cols);
         self.results_state.reset();
+        if result.columns == self.query_result.columns {
+            self.results_state.hidden_cols = hidden;
+        }
         self.query_result = result;
+        self.original_rows = None;
+        self.query_error = None;
+    }
+
+    /// Rebuilds the grid from the original rows: only those with a cell
+    /// containing the search text (ignoring case), in the chosen sort order.
+    pub fn refresh_rows(&mut self) {
+        let state = &self.results_state;
+        if state.search.is_empty() && state.sort_col.is_none() {
+            if let Some(rows) = self.original_rows.take() {
+                self.query_result.rows = rows;
+            }
+        } else {
+            let all = self
+                .original_rows
+                .get_or_insert_with(|| std::mem::take(&mut self.query_result.rows));
+            let needle = state.search.to_lowercase();
+            let mut rows: Vec<Vec<Option<String>>> = all
+                .iter()
+                .filter(|row| {
+                    row.iter()
+                        .any(|cell| cell.as_deref().unwrap_or("NULL").to_lowercase().contains(&needle))
+                })
+                .cloned()
+                .collect();
+            if let Some(col) = state.sort_col {
+                sort_rows(&mut rows, col, state.sort_asc);
+            }
+            self.query_result.rows = rows;
+        }
+        self.results_state.selected_row = 0;
+        self.results_state.scroll_offset = 0;
+    }
+
+    pub fn clear_results_filter(&mut self) {
+        self.results_state.search.clear();
+        self.results_state.searching = false;
+        self.refresh_rows();
+    }
+
+    /// Header clicks cycle a column through ascending, descending and unsorted.
+    pub fn cycle_sort(&mut self, col: usize) {
+        let state = &mut self.results_state;
+        match state.sort_col {
+            Some(c) if c == col && state.sort_asc => state.sort_asc = false,
+            Some(c) if c == col => state.sort_col = None,
+            _ => {
+                state.sort_col = Some(col);
+                state.sort_asc = true;
+            }
+        }
+        self.refresh_rows();
+    }
+
+    /// Replaces the results grid with the error panel.
+    pub fn set_query_error(&mut self, error: String) {
+        self.set_query_result(QueryResult::empty());
+        self.query_error = Some(error);
+        self.error_scroll = 0;
+    }
+
+    /// Scrolls the error panel by `lines`, keeping its last line in view.
+    pub fn scroll_query_error(&mut self, lines: isize) {
+        let count = self.query_error.as_ref().map_or(0, |e| e.lines().count());
+        self.error_scroll = self.error_scroll.saturating_add_signed(lines).min(count.saturating_sub(1));
+    }
+
+    /// Hides the error panel, going back to the empty results.
+    pub fn dismiss_query_error(&mut self) {
+        self.query_error = None;
+        self.error_scroll = 0;
+    }
+
+    pub fn limit_syntax(&self) -> LimitSyntax {
+        self.connection.as_ref().map_or(LimitSyntax::Limit, DatabaseConnection::limit_syntax)
+    }
+
+    /// Whether an open SQLite transaction holds the pool's only connection,
+    /// so anything else run on the pool would wait for it to end.
+    pub fn pool_held_by_transaction(&self) -> bool {
+        self.transaction.is_some() && matches!(self.connection, Some(DatabaseConnection::Sqlite(_)))
+    }
+
+    /// Returns to the connection screen after the connection was lost for
+    /// good, showing `error` there.
+    pub fn disconnect(&mut self, error: String) {
+        self.abort_sidebar_load();
+        self.connection = None;
+        self.transaction = None;
+        self.tunnel = None;
+        self.connection_string = None;
+        self.server_version = None;
+        self.connection_error = Some(error);
+        self.state = AppState::Connection;
+    }
+
+    pub fn start_query(&mut self, backend: BackendId, task: AbortHandle, rx: oneshot::Receiver<QueryOutcome>) {
+        self.query_running = true;
+        self.query_rx = Some(rx);
+        self.query_task = Some(task);
+        self.running_backend = backend;
+        self.query_started = Instant::now();
+    }
+
+    /// Replaces any sidebar fetch in flight with this one.
+    pub fn start_sidebar_load(&mut self, task: AbortHandle, rx: oneshot::Receiver<SidebarLoad>) {
+        self.abort_sidebar_load();
+        self.sidebar_rx = Some(rx);
+        self.sidebar_task = Some(task);
+    }
+
+    pub fn sidebar_loading(&self) -> bool {
+        self.sidebar_rx.is_some()
     }
 
-    pub fn handle_sidebar_click(&mut self, x: u16, y: u16) -> bool {
-        if let Some(area) = self.sidebar_area {
-            if x >= area.x && x < area.x + area.width && y >= area.y && y < area.y + area.height {
-                let relative_y = y.saturating_sub(area.y + 1) as usize;
-                let visible_idx = self.tree_state.scroll_offset + relative_y;
-                self.tree_state.select_by_click(visible_idx);
-                self.focus = Focus::Sidebar;
-                return true;
+    fn abort_sidebar_load(&mut self) {
+        if let Some(task) = self.sidebar_task.take() {
+            task.abort();
+        }
+        self.sidebar_rx = None;
+    }
+
+    /// Takes what the sidebar fetch returned if it has finished.
+    pub fn poll_sidebar(&mut self) -> Option<SidebarLoad> {
+        let rx = self.sidebar_rx.as_mut()?;
+        let load = match rx.try_recv() {
+            Ok(load) => Some(load),
+            Err(oneshot::error::TryRecvError::Empty) => return None,
+            Err(oneshot::error::TryRecvError::Closed) => {
+                self.tree_state.cancel_loading();
+                self.tree_state.cancel_loading_columns();
+                None
             }
+        };
+        self.sidebar_rx = None;
+        self.sidebar_task = None;
+        load
+    }
+
+    /// Abandons the running query and returns where it runs, if one was
+    /// running.
+    pub fn cancel_query(&mut self) -> Option<BackendId> {
+        if !self.query_running {
+            return None;
+        }
+        if let Some(task) = self.query_task.take() {
+            task.abort();
+        }
+        self.query_running = false;
+        self.query_rx = None;
+        Some(std::mem::take(&mut self.running_backend))
+    }
+
+    /// Takes the outcome of the running query if it has finished.
+    pub fn poll_query(&mut self) -> Option<anyhow::Result<QueryOutcome>> {
+        let rx = self.query_rx.as_mut()?;
+        let outcome = match rx.try_recv() {
+            Ok(outcome) => Ok(outcome),
+            Err(oneshot::error::TryRecvError::Empty) => return None,
+            Err(oneshot::error::TryRecvError::Closed) => Err(anyhow::anyhow!("query task ended without a result")),
+        };
+        self.query_running = false;
+        self.query_rx = None;
+        self.query_task = None;
+        Some(outcome)
+    }
+
+    /// Spinner and elapsed time shown in the title while a query runs.
+    pub fn running_status(&self) -> Option<StatusMessage> {
+        if !self.query_running {
+            return None;
+        }
+        let elapsed = self.query_started.elapsed();
+        let frame = SPINNER[(elapsed.as_millis() / 80) as usize % SPINNER.len()];
+        let activity = if self.reconnecting.load(Ordering::Relaxed) { "Reconnecting" } else { "Running" };
+        Some(StatusMessage {
+            text: format!("{} {}… {:.1}s", frame, activity, elapsed.as_secs_f32()),
+            is_error: false,
+            created: Instant::now(),
+        })
+    }
+
+    pub fn set_status(&mut self, text: impl Into<String>) {
+        self.status_message = Some(StatusMessage {
+            text: text.into(),
+            is_error: false,
+            created: Instant::now(),
+        });
+    }
+
+    pub fn set_error_status(&mut self, text: impl Into<String>) {
+        self.status_message = Some(StatusMessage {
+            text: text.into(),
+            is_error: true,
+            created: Instant::now(),
+        });
+    }
+
+    pub fn current_status(&self) -> Option<&StatusMessage> {
+        self.status_message
+            .as_ref()
+            .filter(|s| s.created.elapsed() < STATUS_TTL)
+    }
+
+    /// Whether `(x, y)` is on the sidebar's right border.
+    pub fn on_sidebar_edge(&self, x: u16, y: u16) -> bool {
+        self.sidebar_area
+            .is_some_and(|area| x + 1 == area.right() && y >= area.y && y < area.bottom())
+    }
+
+    /// Moves the sidebar's right edge to column `x`.
+    pub fn resize_sidebar(&mut self, x: u16) {
+        let Some(area) = self.browser_area.filter(|a| a.width > 0) else {
+            return;
+        };
+        let pct = (x.saturating_sub(area.x) as u32 + 1) * 100 / area.width as u32;
+        self.sidebar_pct = (pct as u16).clamp(MIN_SIDEBAR_PCT, MAX_SIDEBAR_PCT);
+    }
+
+    pub fn handle_sidebar_click(&mut self, x: u16, y: u16) -> SidebarClick {
+        let Some(area) = self.sidebar_area else {
+            return SidebarClick::None;
+        };
+        if !(x >= area.x && x < area.x + area.width && y >= area.y && y < area.y + area.height) {
+            return SidebarClick::None;
+        }
+
+        let relative_y = y.saturating_sub(area.y + 1) as usize;
+        let visible_idx = self.tree_state.scroll_offset + relative_y;
+        let hit = self.tree_state.select_by_click(visible_idx);
+        self.focus = Focus::Sidebar;
+        if !hit {
+            return SidebarClick::Selected;
+        }
+
+        let now = Instant::now();
+        let double = self
+            .last_sidebar_click
+            .is_some_and(|(at, idx)| idx == self.tree_state.selected && now.duration_since(at) < DOUBLE_CLICK);
+        self.last_sidebar_click = (!double).then_some((now, self.tree_state.selected));
+
+        // The " ▶ " glyph occupies the first three columns inside the border;
+        // a table's sits two columns further in.
+        let on_glyph = x > area.x && x <= area.x + 3;
+        let on_table_glyph = x > area.x + 2 && x <= area.x + 5;
+        if self.tree_state.is_selected_schema() {
+            if on_glyph || double {
+                self.tree_state.toggle_selected();
+            }
+            SidebarClick::Selected
+        } else if self.tree_state.is_selected_table() && on_table_glyph {
+            self.tree_state.toggle_selected();
+            SidebarClick::Selected
+        } else if double {
+            SidebarClick::OpenTable
+        } else {
+            SidebarClick::Selected
         }
-        false
     }
 }
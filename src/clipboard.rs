@@ -0,0 +1,62 @@
+use std::io::Write;
+use std::process::{Command, Stdio};
+use std::sync::Mutex;
+
+use anyhow::{Context, Result};
+
+/// Kept alive for the whole session: on X11 the copied text is only
+/// available while the clipboard owner exists.
+static CLIPBOARD: Mutex<Option<arboard::Clipboard>> = Mutex::new(None);
+
+/// Copies `text` to the system clipboard, falling back to the platform's
+/// clipboard command when arboard cannot be initialized.
+pub fn copy(text: &str) -> Result<()> {
+    let mut clipboard = CLIPBOARD.lock().unwrap_or_else(|e| e.into_inner());
+    if clipboard.is_none() {
+        *clipboard = arboard::Clipboard::new().ok();
+    }
+    let arboard_error = match clipboard.as_mut() {
+        Some(cb) => match cb.set_text(text) {
+            Ok(()) => return Ok(()),
+            Err(e) => e.to_string(),
+        },
+        None => "no clipboard available".to_string(),
+    };
+
+    copy_with_command(text).with_context(|| format!("clipboard unavailable ({arboard_error})"))
+}
+
+fn copy_with_command(text: &str) -> Result<()> {
+    let candidates: &[(&str, &[&str])] = if cfg!(target_os = "macos") {
+        &[("pbcopy", &[])]
+    } else if cfg!(windows) {
+        &[("clip", &[])]
+    } else {
+        &[
+            ("wl-copy", &[]),
+            ("xclip", &["-selection", "clipboard"]),
+            ("xsel", &["--clipboard", "--input"]),
+        ]
+    };
+
+    for (program, args) in candidates {
+        let Ok(mut child) = Command::new(program)
+            .args(*args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()
+        else {
+            continue;
+        };
+        if let Some(stdin) = child.stdin.as_mut() {
+            stdin.write_all(text.as_bytes())?;
+        }
+        // Close stdin so the command sees EOF before we wait on it.
+        drop(child.stdin.take());
+        if child.wait()?.success() {
+            return Ok(());
+        }
+    }
+    anyhow::bail!("no clipboard command found")
+}
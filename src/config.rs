@@ -0,0 +1,201 @@
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::time::Duration;
+
+use ratatui::style::Color;
+use serde::{Deserialize, Deserializer};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TruncateMode {
+    #[default]
+    End,
+    Middle,
+}
+
+/// Vertical spacing of result rows.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Density {
+    /// One line per row.
+    #[default]
+    Compact,
+    /// A blank line between rows.
+    Comfortable,
+}
+
+impl Density {
+    pub fn toggled(self) -> Self {
+        match self {
+            Density::Compact => Density::Comfortable,
+            Density::Comfortable => Density::Compact,
+        }
+    }
+
+    /// Screen lines taken by one result row.
+    pub fn row_lines(self) -> usize {
+        match self {
+            Density::Compact => 1,
+            Density::Comfortable => 2,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RuleOp {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    Contains,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct FormatRule {
+    /// Column the rule applies to; any column when omitted.
+    #[serde(default)]
+    pub column: Option<String>,
+    pub op: RuleOp,
+    pub value: String,
+    #[serde(deserialize_with = "deserialize_color")]
+    pub color: Color,
+}
+
+impl FormatRule {
+    pub fn matches(&self, column: &str, cell: &str) -> bool {
+        if let Some(c) = &self.column
+            && !c.eq_ignore_ascii_case(column)
+        {
+            return false;
+        }
+
+        if self.op == RuleOp::Contains {
+            return cell.to_lowercase().contains(&self.value.to_lowercase());
+        }
+
+        // Compare numerically when both sides are numbers, otherwise as text.
+        let ordering = match (cell.trim().parse::<f64>(), self.value.trim().parse::<f64>()) {
+            (Ok(a), Ok(b)) => a.partial_cmp(&b),
+            _ => Some(cell.cmp(self.value.as_str())),
+        };
+        let Some(ordering) = ordering else {
+            return false;
+        };
+
+        match self.op {
+            RuleOp::Eq => ordering.is_eq(),
+            RuleOp::Ne => ordering.is_ne(),
+            RuleOp::Lt => ordering.is_lt(),
+            RuleOp::Le => ordering.is_le(),
+            RuleOp::Gt => ordering.is_gt(),
+            RuleOp::Ge => ordering.is_ge(),
+            RuleOp::Contains => unreachable!(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    pub truncate: TruncateMode,
+    pub middle_truncate_columns: Vec<String>,
+    /// Built-in highlighting for negative numbers and booleans.
+    pub conditional_formatting: bool,
+    pub format_rules: Vec<FormatRule>,
+    /// Run the generated SELECT when a table is opened from the sidebar,
+    /// instead of only placing it in the editor.
+    pub auto_run_on_select: bool,
+    /// Client-side limit on waiting for query results; 0 disables it.
+    pub read_timeout_secs: u64,
+    /// Connection strings containing any of these (case-insensitive) are
+    /// treated as production: a banner is shown and writes need confirming.
+    pub production_patterns: Vec<String>,
+    /// Rows per page when browsing a table.
+    pub page_size: usize,
+    pub density: Density,
+    /// Show `schema.table` rather than just `table` in the results title.
+    pub qualified_table_names: bool,
+    /// Run the query restored from the last session on reconnect, instead
+    /// of only placing it in the editor.
+    pub auto_run_restored_query: bool,
+    /// Render at most this many result columns; 0 renders all of them.
+    pub max_columns: usize,
+    /// Vim-style normal and insert modes in the SQL editor.
+    pub vim_mode: bool,
+    /// Keep connection passwords in the OS keyring instead of `crux.db`.
+    pub keyring: bool,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            truncate: TruncateMode::default(),
+            middle_truncate_columns: vec![],
+            conditional_formatting: true,
+            format_rules: vec![],
+            auto_run_on_select: true,
+            read_timeout_secs: 300,
+            production_patterns: vec![],
+            page_size: 100,
+            density: Density::default(),
+            qualified_table_names: true,
+            auto_run_restored_query: false,
+            max_columns: 50,
+            vim_mode: false,
+            keyring: false,
+        }
+    }
+}
+
+impl Config {
+    pub fn load() -> Self {
+        config_dir()
+            .map(|dir| dir.join("config.toml"))
+            .and_then(|path| std::fs::read_to_string(path).ok())
+            .and_then(|contents| toml::from_str(&contents).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn read_timeout(&self) -> Option<Duration> {
+        (self.read_timeout_secs > 0).then(|| Duration::from_secs(self.read_timeout_secs))
+    }
+
+    pub fn is_production(&self, connection_string: &str) -> bool {
+        let connection_string = connection_string.to_lowercase();
+        self.production_patterns
+            .iter()
+            .any(|p| !p.is_empty() && connection_string.contains(&p.to_lowercase()))
+    }
+
+    pub fn truncate_mode_for(&self, column: &str) -> TruncateMode {
+        if self
+            .middle_truncate_columns
+            .iter()
+            .any(|c| c.eq_ignore_ascii_case(column))
+        {
+            TruncateMode::Middle
+        } else {
+            self.truncate
+        }
+    }
+}
+
+fn deserialize_color<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Color, D::Error> {
+    let value = String::deserialize(deserializer)?;
+    Color::from_str(&value).map_err(|_| serde::de::Error::custom(format!("invalid color: {value}")))
+}
+
+/// Expands a leading `~/` to the home directory.
+pub fn expand_home(path: &str) -> PathBuf {
+    match (path.strip_prefix("~/"), dirs::home_dir()) {
+        (Some(rest), Some(home)) => home.join(rest),
+        _ => PathBuf::from(path),
+    }
+}
+
+pub fn config_dir() -> Option<PathBuf> {
+    dirs::home_dir().map(|home| home.join(".crux"))
+}
@@ -1,21 +1,102 @@
-use anyhow::Result;
-use sqlx::{Column, Row, TypeInfo, ValueRef};
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::str::FromStr;
+use std::time::Duration;
 
-use super::{QueryResult, TableInfo};
+use anyhow::{Context, Result};
+use futures_util::{Stream, TryStreamExt};
+use sqlx::mysql::MySqlQueryResult;
+use sqlx::postgres::PgQueryResult;
+use sqlx::sqlite::SqliteQueryResult;
+use sqlx::postgres::{PgTypeInfo, PgTypeKind};
+use sqlx::{Column, Execute, Executor, Row, Statement, TypeInfo, ValueRef};
 
+use super::mssql::{self, MssqlPool, MssqlTransaction};
+use super::{
+    first_keyword, returns_rows, split_sql_statements, ColumnInfo, LimitSyntax, ParamStyle, ParamValue, QueryResult, RowSink,
+    TableInfo, TableKind,
+};
+
+/// Applied to every connection in the pool, on top of the URL's options.
+#[derive(Debug, Clone)]
+pub struct ConnectSettings {
+    /// How long to wait for the server before giving up on connecting.
+    pub connect_timeout: Duration,
+    /// Server-side limit on each statement (PostgreSQL and MySQL only;
+    /// SQL Server has no such setting).
+    pub statement_timeout: Option<Duration>,
+    /// CA certificate for verifying the server's TLS certificate, unless
+    /// the URL names one (`sslrootcert` / `ssl-ca`).
+    pub ssl_ca: Option<PathBuf>,
+}
+
+impl Default for ConnectSettings {
+    fn default() -> Self {
+        Self {
+            connect_timeout: Duration::from_secs(10),
+            statement_timeout: None,
+            ssl_ca: None,
+        }
+    }
+}
+
+/// Cheap to clone: each variant is a reference-counted pool.
+#[derive(Clone)]
 pub enum DatabaseConnection {
     Postgres(sqlx::PgPool),
     MySql(sqlx::MySqlPool),
     Sqlite(sqlx::SqlitePool),
+    /// Through tiberius, as sqlx has no SQL Server driver.
+    SqlServer(MssqlPool),
 }
 
 impl DatabaseConnection {
-    pub async fn connect(connection_string: &str) -> Result<Self> {
+    /// TLS follows the URL's `sslmode` (PostgreSQL), `ssl-mode` (MySQL) or
+    /// `encrypt` (SQL Server).
+    pub async fn connect(connection_string: &str, settings: &ConnectSettings) -> Result<Self> {
+        let statement_ms = settings.statement_timeout.map(|t| t.as_millis());
+        let timeout = settings.connect_timeout;
         if connection_string.starts_with("postgres://") || connection_string.starts_with("postgresql://") {
-            let pool = sqlx::PgPool::connect(connection_string).await?;
+            let mut options = sqlx::postgres::PgConnectOptions::from_str(connection_string)?;
+            if let Some(ca) = &settings.ssl_ca
+                && !connection_string.contains("sslrootcert=")
+            {
+                options = options.ssl_root_cert(ca);
+            }
+            let pool = sqlx::postgres::PgPoolOptions::new()
+                .acquire_timeout(timeout)
+                .after_connect(move |conn, _| {
+                    Box::pin(async move {
+                        if let Some(ms) = statement_ms {
+                            sqlx::query(&format!("SET statement_timeout = {ms}")).execute(conn).await?;
+                        }
+                        Ok(())
+                    })
+                })
+                .connect_with(options)
+                .await
+                .map_err(|e| connect_error(e, timeout))?;
             Ok(Self::Postgres(pool))
         } else if connection_string.starts_with("mysql://") {
-            let pool = sqlx::MySqlPool::connect(connection_string).await?;
+            let mut options = sqlx::mysql::MySqlConnectOptions::from_str(connection_string)?;
+            if let Some(ca) = &settings.ssl_ca
+                && !connection_string.contains("ssl-ca=")
+            {
+                options = options.ssl_ca(ca);
+            }
+            let pool = sqlx::mysql::MySqlPoolOptions::new()
+                .acquire_timeout(timeout)
+                .after_connect(move |conn, _| {
+                    Box::pin(async move {
+                        if let Some(ms) = statement_ms {
+                            sqlx::query(&format!("SET SESSION max_execution_time = {ms}")).execute(conn).await?;
+                        }
+                        Ok(())
+                    })
+                })
+                .connect_with(options)
+                .await
+                .map_err(|e| connect_error(e, timeout))?;
             Ok(Self::MySql(pool))
         } else if connection_string.starts_with("sqlite://") || connection_string.ends_with(".db") {
             let conn_str = if connection_string.starts_with("sqlite://") {
@@ -23,169 +104,1256 @@ impl DatabaseConnection {
             } else {
                 format!("sqlite://{}", connection_string)
             };
-            let pool = sqlx::SqlitePool::connect(&conn_str).await?;
+            // A single long-lived connection keeps ATTACHed databases visible
+            // to every query, since ATTACH is scoped to one connection.
+            let pool = sqlx::sqlite::SqlitePoolOptions::new()
+                .max_connections(1)
+                .idle_timeout(None)
+                .max_lifetime(None)
+                .acquire_timeout(timeout)
+                .connect(&conn_str)
+                .await
+                .map_err(|e| connect_error(e, timeout))?;
             Ok(Self::Sqlite(pool))
+        } else if connection_string.starts_with("mssql://") || connection_string.starts_with("sqlserver://") {
+            Ok(Self::SqlServer(MssqlPool::connect(connection_string, settings).await?))
         } else {
             Err(anyhow::anyhow!("Unsupported database type"))
         }
     }
 
-    pub async fn get_tables(&self) -> Result<Vec<TableInfo>> {
+    pub fn engine(&self) -> &'static str {
+        match self {
+            Self::Postgres(_) => "PostgreSQL",
+            Self::MySql(_) => "MySQL",
+            Self::Sqlite(_) => "SQLite",
+            Self::SqlServer(_) => "SQL Server",
+        }
+    }
+
+    pub fn limit_syntax(&self) -> LimitSyntax {
+        match self {
+            Self::SqlServer(_) => LimitSyntax::OffsetFetch,
+            Self::Postgres(_) | Self::MySql(_) | Self::Sqlite(_) => LimitSyntax::Limit,
+        }
+    }
+
+    /// `is_connection_lost`, or a pool timeout on a server: the pool keeps
+    /// retrying while the server refuses connections. SQLite's single
+    /// connection times out when it is only busy.
+    pub fn is_connection_lost(&self, err: &anyhow::Error) -> bool {
+        is_connection_lost(err)
+            || (!matches!(self, Self::Sqlite(_))
+                && matches!(err.downcast_ref::<sqlx::Error>(), Some(sqlx::Error::PoolTimedOut)))
+    }
+
+    pub fn param_style(&self) -> ParamStyle {
+        match self {
+            Self::Postgres(_) => ParamStyle::Dollar,
+            Self::MySql(_) | Self::Sqlite(_) => ParamStyle::Question,
+            Self::SqlServer(_) => ParamStyle::AtP,
+        }
+    }
+
+    /// Version reported by the server, or by the SQLite library.
+    pub async fn server_version(&self) -> Result<String> {
+        let version = match self {
+            Self::Postgres(pool) => {
+                sqlx::query_scalar("SELECT current_setting('server_version')")
+                    .fetch_one(pool)
+                    .await?
+            }
+            Self::MySql(pool) => sqlx::query_scalar("SELECT version()").fetch_one(pool).await?,
+            Self::Sqlite(pool) => sqlx::query_scalar("SELECT sqlite_version()").fetch_one(pool).await?,
+            Self::SqlServer(pool) => pool
+                .fetch("SELECT CAST(SERVERPROPERTY('ProductVersion') AS nvarchar(128))", &[])
+                .await?
+                .into_iter()
+                .flatten()
+                .next()
+                .flatten()
+                .unwrap_or_default(),
+        };
+        Ok(version)
+    }
+
+    pub fn quote_identifier(&self, name: &str) -> String {
+        match self {
+            Self::MySql(_) => format!("`{}`", name.replace('`', "``")),
+            Self::SqlServer(_) => format!("[{}]", name.replace(']', "]]")),
+            Self::Postgres(_) | Self::Sqlite(_) => format!("\"{}\"", name.replace('"', "\"\"")),
+        }
+    }
+
+    /// Lists the other sessions on the server; the first column is the
+    /// session id accepted by `kill_session_query`.
+    pub fn sessions_query(&self) -> Option<&'static str> {
+        match self {
+            Self::Postgres(_) => Some(
+                "SELECT pid, usename AS user, datname AS database, client_addr::text AS client, state,
+                        (now() - query_start)::text AS duration, wait_event_type, query
+                 FROM pg_stat_activity
+                 WHERE pid <> pg_backend_pid()
+                 ORDER BY query_start NULLS LAST",
+            ),
+            Self::MySql(_) => Some("SHOW FULL PROCESSLIST"),
+            Self::SqlServer(_) => Some(
+                "SELECT s.session_id, s.login_name AS [user], DB_NAME(s.database_id) AS [database],
+                        s.host_name AS client, s.status, r.total_elapsed_time AS duration_ms,
+                        r.wait_type, t.text AS query
+                 FROM sys.dm_exec_sessions s
+                 LEFT JOIN sys.dm_exec_requests r ON r.session_id = s.session_id
+                 OUTER APPLY sys.dm_exec_sql_text(r.sql_handle) t
+                 WHERE s.is_user_process = 1 AND s.session_id <> @@SPID
+                 ORDER BY r.start_time DESC",
+            ),
+            Self::Sqlite(_) => None,
+        }
+    }
+
+    pub fn kill_session_query(&self, id: &str) -> Option<String> {
+        let id: i64 = id.trim().parse().ok()?;
+        match self {
+            Self::Postgres(_) => Some(format!("SELECT pg_terminate_backend({})", id)),
+            Self::MySql(_) | Self::SqlServer(_) => Some(format!("KILL {}", id)),
+            Self::Sqlite(_) => None,
+        }
+    }
+
+    /// Names of the schemas holding tables or views, so huge catalogs can load
+    /// tables per schema on demand.
+    pub async fn get_schemas(&self) -> Result<Vec<String>> {
         match self {
             Self::Postgres(pool) => {
                 let rows = sqlx::query(
-                    "SELECT table_schema, table_name FROM information_schema.tables 
-                     WHERE table_type = 'BASE TABLE'
+                    "SELECT DISTINCT table_schema FROM information_schema.tables
+                     WHERE table_type IN ('BASE TABLE', 'VIEW')
                        AND table_schema NOT IN ('pg_catalog', 'information_schema', 'pg_toast')
                        AND table_schema NOT LIKE 'pg_temp%'
                        AND table_schema NOT LIKE 'pg_toast_temp%'
-                     ORDER BY table_schema, table_name"
+                     ORDER BY table_schema"
                 )
                 .fetch_all(pool)
                 .await?;
 
-                Ok(rows
-                    .iter()
-                    .map(|row| TableInfo {
-                        schema: row.get("table_schema"),
-                        name: row.get("table_name"),
-                    })
-                    .collect())
+                Ok(rows.iter().map(|row| row.get("table_schema")).collect())
             }
             Self::MySql(pool) => {
                 let rows = sqlx::query(
-                    "SELECT table_schema, table_name FROM information_schema.tables 
-                     WHERE table_type = 'BASE TABLE'
+                    "SELECT DISTINCT table_schema AS table_schema FROM information_schema.tables
+                     WHERE table_type IN ('BASE TABLE', 'VIEW')
                        AND table_schema NOT IN ('mysql', 'information_schema', 'performance_schema', 'sys')
-                     ORDER BY table_schema, table_name"
+                     ORDER BY table_schema"
                 )
                 .fetch_all(pool)
                 .await?;
 
-                Ok(rows
+                Ok(rows.iter().map(|row| row.get("table_schema")).collect())
+            }
+            Self::Sqlite(pool) => {
+                // Each attached database is exposed as its own schema
+                Ok(sqlx::query("PRAGMA database_list")
+                    .fetch_all(pool)
+                    .await?
                     .iter()
-                    .map(|row| TableInfo {
-                        schema: row.get("TABLE_SCHEMA"),
-                        name: row.get("TABLE_NAME"),
-                    })
+                    .map(|row| row.get::<String, _>("name"))
+                    .filter(|name| name != "temp")
                     .collect())
             }
+            Self::SqlServer(pool) => Ok(pool
+                .fetch(
+                    "SELECT DISTINCT TABLE_SCHEMA FROM INFORMATION_SCHEMA.TABLES
+                     WHERE TABLE_TYPE IN ('BASE TABLE', 'VIEW')
+                     ORDER BY TABLE_SCHEMA",
+                    &[],
+                )
+                .await?
+                .into_iter()
+                .filter_map(|row| row.into_iter().next().flatten())
+                .collect()),
+        }
+    }
+
+    /// Tables and views of a schema, tables first.
+    pub async fn get_tables_in_schema(&self, schema: &str) -> Result<Vec<TableInfo>> {
+        let names: Vec<(String, String)> = match self {
+            Self::Postgres(pool) => {
+                sqlx::query_as(
+                    "SELECT table_name::text, table_type::text FROM information_schema.tables
+                     WHERE table_type IN ('BASE TABLE', 'VIEW') AND table_schema = $1
+                     ORDER BY table_name"
+                )
+                .bind(schema)
+                .fetch_all(pool)
+                .await?
+            }
+            Self::MySql(pool) => {
+                sqlx::query_as(
+                    "SELECT table_name AS table_name, CAST(table_type AS CHAR) AS table_type
+                     FROM information_schema.tables
+                     WHERE table_type IN ('BASE TABLE', 'VIEW') AND table_schema = ?
+                     ORDER BY table_name"
+                )
+                .bind(schema)
+                .fetch_all(pool)
+                .await?
+            }
             Self::Sqlite(pool) => {
                 // Only show user tables, exclude sqlite internal and common auto-created tables
-                let rows = sqlx::query(
-                    "SELECT name FROM sqlite_master 
-                     WHERE type = 'table' 
+                sqlx::query_as(&format!(
+                    "SELECT name, type FROM {}.sqlite_master
+                     WHERE type IN ('table', 'view')
                        AND name NOT LIKE 'sqlite_%'
                        AND name NOT LIKE '_litestream%'
-                     ORDER BY name"
+                     ORDER BY name",
+                    quote_sqlite_identifier(schema)
+                ))
+                .fetch_all(pool)
+                .await?
+            }
+            Self::SqlServer(pool) => text_pairs(
+                pool.fetch(
+                    "SELECT TABLE_NAME, TABLE_TYPE FROM INFORMATION_SCHEMA.TABLES
+                     WHERE TABLE_TYPE IN ('BASE TABLE', 'VIEW') AND TABLE_SCHEMA = @P1
+                     ORDER BY TABLE_NAME",
+                    &[schema],
+                )
+                .await?,
+            ),
+        };
+
+        let mut tables: Vec<TableInfo> = names
+            .into_iter()
+            .map(|(name, kind)| TableInfo {
+                schema: schema.to_string(),
+                name,
+                kind: if kind.eq_ignore_ascii_case("view") { TableKind::View } else { TableKind::Table },
+                row_count: None,
+            })
+            .collect();
+        tables.sort_by_key(|t| t.kind);
+        Ok(tables)
+    }
+
+    /// Row count of every table in a schema. Postgres, MySQL and SQL Server
+    /// report what their catalogs hold so this stays fast on big tables;
+    /// SQLite counts.
+    pub async fn get_row_counts(&self, schema: &str) -> Result<Vec<(String, u64)>> {
+        let counts: Vec<(String, i64)> = match self {
+            Self::Postgres(pool) => {
+                sqlx::query_as(
+                    "SELECT c.relname::text, c.reltuples::bigint
+                     FROM pg_class c JOIN pg_namespace n ON n.oid = c.relnamespace
+                     WHERE n.nspname = $1 AND c.relkind IN ('r', 'p') AND c.reltuples >= 0"
                 )
+                .bind(schema)
                 .fetch_all(pool)
-                .await?;
+                .await?
+            }
+            Self::MySql(pool) => {
+                sqlx::query_as(
+                    "SELECT table_name AS table_name, CAST(table_rows AS SIGNED) AS table_rows
+                     FROM information_schema.tables
+                     WHERE table_type = 'BASE TABLE' AND table_schema = ? AND table_rows IS NOT NULL"
+                )
+                .bind(schema)
+                .fetch_all(pool)
+                .await?
+            }
+            Self::Sqlite(pool) => {
+                let mut counts = Vec::new();
+                for table in self.get_tables_in_schema(schema).await? {
+                    let count: i64 = sqlx::query_scalar(&format!(
+                        "SELECT COUNT(*) FROM {}.{}",
+                        quote_sqlite_identifier(schema),
+                        quote_sqlite_identifier(&table.name)
+                    ))
+                    .fetch_one(pool)
+                    .await?;
+                    counts.push((table.name, count));
+                }
+                counts
+            }
+            Self::SqlServer(pool) => text_pairs(
+                pool.fetch(
+                    "SELECT t.name, CAST(SUM(p.rows) AS nvarchar(20))
+                     FROM sys.tables t
+                     JOIN sys.schemas s ON s.schema_id = t.schema_id
+                     JOIN sys.partitions p ON p.object_id = t.object_id AND p.index_id IN (0, 1)
+                     WHERE s.name = @P1
+                     GROUP BY t.name",
+                    &[schema],
+                )
+                .await?,
+            )
+            .into_iter()
+            .filter_map(|(name, count)| Some((name, count.parse().ok()?)))
+            .collect(),
+        };
+        Ok(counts.into_iter().map(|(name, count)| (name, count.max(0) as u64)).collect())
+    }
 
-                Ok(rows
-                    .iter()
-                    .map(|row| TableInfo {
-                        schema: "main".to_string(),
-                        name: row.get("name"),
-                    })
-                    .collect())
+    /// Column names and SQL types of a table, in definition order.
+    pub async fn get_columns(&self, schema: &str, table: &str) -> Result<Vec<ColumnInfo>> {
+        let columns: Vec<(String, String)> = match self {
+            Self::Postgres(pool) => {
+                sqlx::query_as(
+                    "SELECT column_name::text,
+                            CASE WHEN data_type = 'USER-DEFINED' THEN udt_name::text ELSE data_type::text END
+                     FROM information_schema.columns
+                     WHERE table_schema = $1 AND table_name = $2
+                     ORDER BY ordinal_position"
+                )
+                .bind(schema)
+                .bind(table)
+                .fetch_all(pool)
+                .await?
             }
-        }
+            Self::MySql(pool) => {
+                sqlx::query_as(
+                    "SELECT column_name AS column_name, column_type AS column_type
+                     FROM information_schema.columns
+                     WHERE table_schema = ? AND table_name = ?
+                     ORDER BY ordinal_position"
+                )
+                .bind(schema)
+                .bind(table)
+                .fetch_all(pool)
+                .await?
+            }
+            Self::Sqlite(pool) => {
+                sqlx::query(&format!(
+                    "PRAGMA {}.table_info({})",
+                    quote_sqlite_identifier(schema),
+                    quote_sqlite_identifier(table)
+                ))
+                .fetch_all(pool)
+                .await?
+                .iter()
+                .map(|row| (row.get("name"), row.get("type")))
+                .collect()
+            }
+            Self::SqlServer(pool) => text_pairs(
+                pool.fetch(
+                    "SELECT COLUMN_NAME, DATA_TYPE FROM INFORMATION_SCHEMA.COLUMNS
+                     WHERE TABLE_SCHEMA = @P1 AND TABLE_NAME = @P2
+                     ORDER BY ORDINAL_POSITION",
+                    &[schema, table],
+                )
+                .await?,
+            ),
+        };
+
+        Ok(columns
+            .into_iter()
+            .map(|(name, data_type)| ColumnInfo { name, data_type })
+            .collect())
     }
 
-    pub async fn execute_query(&self, query: &str) -> Result<QueryResult> {
+    /// Primary key columns of a table in key order; empty when it has none.
+    pub async fn get_primary_key(&self, schema: &str, table: &str) -> Result<Vec<String>> {
+        let columns = match self {
+            Self::Postgres(pool) => {
+                sqlx::query_scalar(
+                    "SELECT kcu.column_name::text
+                     FROM information_schema.table_constraints tc
+                     JOIN information_schema.key_column_usage kcu
+                       ON kcu.constraint_schema = tc.constraint_schema
+                      AND kcu.constraint_name = tc.constraint_name
+                     WHERE tc.constraint_type = 'PRIMARY KEY'
+                       AND tc.table_schema = $1 AND tc.table_name = $2
+                     ORDER BY kcu.ordinal_position"
+                )
+                .bind(schema)
+                .bind(table)
+                .fetch_all(pool)
+                .await?
+            }
+            Self::MySql(pool) => {
+                sqlx::query_scalar(
+                    "SELECT column_name AS column_name
+                     FROM information_schema.key_column_usage
+                     WHERE constraint_name = 'PRIMARY'
+                       AND table_schema = ? AND table_name = ?
+                     ORDER BY ordinal_position"
+                )
+                .bind(schema)
+                .bind(table)
+                .fetch_all(pool)
+                .await?
+            }
+            Self::Sqlite(pool) => {
+                let mut key: Vec<(i64, String)> = sqlx::query(&format!(
+                    "PRAGMA {}.table_info({})",
+                    quote_sqlite_identifier(schema),
+                    quote_sqlite_identifier(table)
+                ))
+                .fetch_all(pool)
+                .await?
+                .iter()
+                .map(|row| (row.get("pk"), row.get("name")))
+                .filter(|(position, _)| *position > 0)
+                .collect();
+                key.sort();
+                key.into_iter().map(|(_, name)| name).collect()
+            }
+            Self::SqlServer(pool) => pool
+                .fetch(
+                    "SELECT kcu.COLUMN_NAME
+                     FROM INFORMATION_SCHEMA.TABLE_CONSTRAINTS tc
+                     JOIN INFORMATION_SCHEMA.KEY_COLUMN_USAGE kcu
+                       ON kcu.CONSTRAINT_SCHEMA = tc.CONSTRAINT_SCHEMA
+                      AND kcu.CONSTRAINT_NAME = tc.CONSTRAINT_NAME
+                     WHERE tc.CONSTRAINT_TYPE = 'PRIMARY KEY'
+                       AND tc.TABLE_SCHEMA = @P1 AND tc.TABLE_NAME = @P2
+                     ORDER BY kcu.ORDINAL_POSITION",
+                    &[schema, table],
+                )
+                .await?
+                .into_iter()
+                .filter_map(|row| row.into_iter().next().flatten())
+                .collect(),
+        };
+        Ok(columns)
+    }
+
+    /// The CREATE statement of a table or view. MySQL and SQLite hand back
+    /// the one they keep; for Postgres and SQL Server it is put together
+    /// from the column catalog and the primary key, so indexes, foreign
+    /// keys and checks are left out.
+    pub async fn get_table_ddl(&self, schema: &str, table: &str) -> Result<String> {
+        let name = format!("{}.{}", self.quote_identifier(schema), self.quote_identifier(table));
         match self {
             Self::Postgres(pool) => {
-                let rows = sqlx::query(query).fetch_all(pool).await?;
-                if rows.is_empty() {
-                    return Ok(QueryResult::empty());
+                let view: Option<String> = sqlx::query_scalar(
+                    "SELECT view_definition::text FROM information_schema.views
+                     WHERE table_schema = $1 AND table_name = $2",
+                )
+                .bind(schema)
+                .bind(table)
+                .fetch_optional(pool)
+                .await?
+                .flatten();
+                if let Some(definition) = view {
+                    return Ok(format!("CREATE VIEW {name} AS\n{}", definition.trim_start()));
                 }
-
-                let columns: Vec<String> = rows[0]
-                    .columns()
-                    .iter()
-                    .map(|c| c.name().to_string())
-                    .collect();
-
-                let data: Vec<Vec<String>> = rows
-                    .iter()
-                    .map(|row| {
-                        (0..columns.len())
-                            .map(|idx| extract_pg_value(row, idx))
-                            .collect()
+                let columns = sqlx::query(
+                    "SELECT column_name::text, data_type::text, udt_name::text,
+                            character_maximum_length::int AS length, numeric_precision::int AS precision,
+                            numeric_scale::int AS scale, is_nullable = 'YES' AS nullable, column_default::text
+                     FROM information_schema.columns
+                     WHERE table_schema = $1 AND table_name = $2
+                     ORDER BY ordinal_position",
+                )
+                .bind(schema)
+                .bind(table)
+                .fetch_all(pool)
+                .await?
+                .iter()
+                .map(|row| {
+                    let data_type: String = row.get("data_type");
+                    let udt: String = row.get("udt_name");
+                    DdlColumn {
+                        data_type: match data_type.as_str() {
+                            "USER-DEFINED" => udt,
+                            "ARRAY" => format!("{}[]", udt.trim_start_matches('_')),
+                            "numeric" => sized_type(&data_type, row.get("precision"), row.get("scale")),
+                            _ => sized_type(&data_type, row.get("length"), None),
+                        },
+                        name: row.get("column_name"),
+                        nullable: row.get("nullable"),
+                        default: row.get("column_default"),
+                    }
+                })
+                .collect();
+                self.create_table_ddl(&name, columns, schema, table).await
+            }
+            Self::MySql(pool) => {
+                let row = sqlx::query(&format!("SHOW CREATE TABLE {name}")).fetch_one(pool).await?;
+                // Second column: "Create Table", or "Create View" for a view.
+                let ddl = row
+                    .try_get::<String, _>(1)
+                    .or_else(|_| row.try_get::<Vec<u8>, _>(1).map(|v| lossy_text(&v)))?;
+                Ok(format!("{ddl};"))
+            }
+            Self::Sqlite(pool) => {
+                // The table's own statement first, then its indexes and triggers.
+                let statements: Vec<String> = sqlx::query_scalar(&format!(
+                    "SELECT sql FROM {}.sqlite_master
+                     WHERE tbl_name = ? AND sql IS NOT NULL
+                     ORDER BY type NOT IN ('table', 'view'), name",
+                    quote_sqlite_identifier(schema)
+                ))
+                .bind(table)
+                .fetch_all(pool)
+                .await?;
+                if statements.is_empty() {
+                    anyhow::bail!("No CREATE statement stored for {name}");
+                }
+                Ok(statements.iter().map(|sql| format!("{sql};")).collect::<Vec<_>>().join("\n\n"))
+            }
+            Self::SqlServer(pool) => {
+                let view = pool
+                    .fetch(
+                        "SELECT VIEW_DEFINITION FROM INFORMATION_SCHEMA.VIEWS
+                         WHERE TABLE_SCHEMA = @P1 AND TABLE_NAME = @P2",
+                        &[schema, table],
+                    )
+                    .await?;
+                // Already the full CREATE VIEW as it was written.
+                if let Some(definition) = view.into_iter().flatten().next().flatten() {
+                    return Ok(definition.trim().to_string());
+                }
+                let rows = pool
+                    .fetch(
+                        "SELECT COLUMN_NAME, DATA_TYPE, CHARACTER_MAXIMUM_LENGTH, NUMERIC_PRECISION,
+                                NUMERIC_SCALE, IS_NULLABLE, COLUMN_DEFAULT
+                         FROM INFORMATION_SCHEMA.COLUMNS
+                         WHERE TABLE_SCHEMA = @P1 AND TABLE_NAME = @P2
+                         ORDER BY ORDINAL_POSITION",
+                        &[schema, table],
+                    )
+                    .await?;
+                let number = |value: &Option<String>| value.as_deref().and_then(|v| v.parse::<i32>().ok());
+                let columns = rows
+                    .into_iter()
+                    .filter_map(|row| {
+                        let [name, data_type, length, precision, scale, nullable, default] =
+                            <[Option<String>; 7]>::try_from(row).ok()?;
+                        let data_type = data_type?;
+                        Some(DdlColumn {
+                            data_type: match data_type.as_str() {
+                                "decimal" | "numeric" => sized_type(&data_type, number(&precision), number(&scale)),
+                                _ if number(&length) == Some(-1) => format!("{data_type}(max)"),
+                                "char" | "varchar" | "nchar" | "nvarchar" | "binary" | "varbinary" => {
+                                    sized_type(&data_type, number(&length), None)
+                                }
+                                _ => data_type,
+                            },
+                            name: name?,
+                            nullable: nullable.as_deref() == Some("YES"),
+                            default,
+                        })
                     })
                     .collect();
+                self.create_table_ddl(&name, columns, schema, table).await
+            }
+        }
+    }
 
-                Ok(QueryResult {
-                    columns,
-                    rows: data,
-                    affected_rows: rows.len() as u64,
-                })
+    async fn create_table_ddl(&self, name: &str, columns: Vec<DdlColumn>, schema: &str, table: &str) -> Result<String> {
+        if columns.is_empty() {
+            anyhow::bail!("{name} has no columns, or does not exist");
+        }
+        let mut lines: Vec<String> = columns
+            .iter()
+            .map(|column| {
+                let mut line = format!("    {} {}", self.quote_identifier(&column.name), column.data_type);
+                if !column.nullable {
+                    line.push_str(" NOT NULL");
+                }
+                if let Some(default) = &column.default {
+                    line.push_str(&format!(" DEFAULT {default}"));
+                }
+                line
+            })
+            .collect();
+        let key = self.get_primary_key(schema, table).await?;
+        if !key.is_empty() {
+            let key: Vec<String> = key.iter().map(|c| self.quote_identifier(c)).collect();
+            lines.push(format!("    PRIMARY KEY ({})", key.join(", ")));
+        }
+        Ok(format!("CREATE TABLE {name} (\n{}\n);", lines.join(",\n")))
+    }
+
+    pub async fn attach_database(&self, path: &str, alias: &str) -> Result<()> {
+        match self {
+            Self::Sqlite(pool) => {
+                sqlx::query(&format!("ATTACH DATABASE ? AS {}", quote_sqlite_identifier(alias)))
+                    .bind(path)
+                    .execute(pool)
+                    .await?;
+                Ok(())
+            }
+            _ => Err(anyhow::anyhow!("ATTACH DATABASE is only supported for SQLite connections")),
+        }
+    }
+
+    /// Number of rows `query` would return.
+    pub async fn count_rows(&self, query: &str) -> Result<u64> {
+        let count_query = format!("SELECT COUNT(*) FROM ({}) AS crux_count", query);
+        let count: i64 = match self {
+            Self::Postgres(pool) => sqlx::query_scalar(&count_query).fetch_one(pool).await?,
+            Self::MySql(pool) => sqlx::query_scalar(&count_query).fetch_one(pool).await?,
+            Self::Sqlite(pool) => sqlx::query_scalar(&count_query).fetch_one(pool).await?,
+            Self::SqlServer(pool) => pool
+                .fetch(&count_query, &[])
+                .await?
+                .into_iter()
+                .flatten()
+                .next()
+                .flatten()
+                .and_then(|count| count.parse().ok())
+                .context("COUNT(*) returned no rows")?,
+        };
+        Ok(count.max(0) as u64)
+    }
+
+    /// Asks the server to stop the query on the connection with this
+    /// `BackendId` after the client has abandoned it. SQLite has nothing to cancel, and SQL
+    /// Server stops when the abandoned query's connection is dropped.
+    pub async fn cancel_query(&self, backend: u64) -> Result<()> {
+        match self {
+            Self::Postgres(pool) => {
+                sqlx::query("SELECT pg_cancel_backend($1)").bind(backend as i32).execute(pool).await?;
             }
             Self::MySql(pool) => {
-                let rows = sqlx::query(query).fetch_all(pool).await?;
-                if rows.is_empty() {
-                    return Ok(QueryResult::empty());
-                }
+                sqlx::query(&format!("KILL QUERY {backend}")).execute(pool).await?;
+            }
+            Self::Sqlite(_) | Self::SqlServer(_) => {}
+        }
+        Ok(())
+    }
 
-                let columns: Vec<String> = rows[0]
-                    .columns()
-                    .iter()
-                    .map(|c| c.name().to_string())
-                    .collect();
+    /// Runs `query` and hands each row to `sink` as it arrives, without
+    /// materializing the whole result set. Returns the number of rows.
+    pub async fn stream_query(&self, query: &str, sink: &mut dyn RowSink) -> Result<u64> {
+        match self {
+            Self::Postgres(pool) => stream_rows(sqlx::query(query).fetch(pool), extract_pg_value, sink).await,
+            Self::MySql(pool) => stream_rows(sqlx::query(query).fetch(pool), extract_mysql_value, sink).await,
+            Self::Sqlite(pool) => stream_rows(sqlx::query(query).fetch(pool), extract_sqlite_value, sink).await,
+            Self::SqlServer(pool) => pool.stream_query(query, sink).await,
+        }
+    }
 
-                let data: Vec<Vec<String>> = rows
-                    .iter()
-                    .map(|row| {
-                        (0..columns.len())
-                            .map(|idx| extract_mysql_value(row, idx))
-                            .collect()
-                    })
-                    .collect();
+    /// Runs a script from the editor, or one statement with its bind
+    /// parameters (None binds NULL). It runs on a single connection whose
+    /// server-side id is put in `backend` first, for `cancel_query`.
+    pub async fn execute_tracked(
+        &self,
+        sql: &str,
+        params: &[Option<String>],
+        backend: &BackendId,
+    ) -> Result<QueryResult> {
+        match self {
+            Self::Postgres(pool) => Session::Postgres(&mut *pool.acquire().await?).run(sql, params, backend).await,
+            Self::MySql(pool) => Session::MySql(&mut *pool.acquire().await?).run(sql, params, backend).await,
+            Self::Sqlite(pool) => Session::Sqlite(&mut *pool.acquire().await?).run(sql, params, backend).await,
+            Self::SqlServer(pool) => Session::SqlServerPool(pool).run(sql, params, backend).await,
+        }
+    }
 
-                Ok(QueryResult {
-                    columns,
-                    rows: data,
-                    affected_rows: rows.len() as u64,
-                })
+    pub async fn execute_query(&self, query: &str) -> Result<QueryResult> {
+        match self {
+            Self::Postgres(pool) => {
+                run_statement(pool, sqlx::query(query), extract_pg_value, PgQueryResult::rows_affected).await
+            }
+            Self::MySql(pool) => {
+                run_statement(pool, sqlx::query(query), extract_mysql_value, MySqlQueryResult::rows_affected).await
             }
             Self::Sqlite(pool) => {
-                let rows = sqlx::query(query).fetch_all(pool).await?;
-                if rows.is_empty() {
-                    return Ok(QueryResult::empty());
+                run_statement(pool, sqlx::query(query), extract_sqlite_value, SqliteQueryResult::rows_affected).await
+            }
+            Self::SqlServer(pool) => pool.execute_query(query).await,
+        }
+    }
+
+    pub async fn begin(&self) -> Result<Transaction> {
+        Ok(match self {
+            Self::Postgres(pool) => Transaction::Postgres(pool.begin().await?),
+            Self::MySql(pool) => Transaction::MySql(pool.begin().await?),
+            Self::Sqlite(pool) => Transaction::Sqlite(pool.begin().await?),
+            Self::SqlServer(pool) => Transaction::SqlServer(Box::new(MssqlTransaction::begin(pool).await?)),
+        })
+    }
+}
+
+/// An open transaction on one pooled connection. Dropping it without
+/// committing rolls it back.
+pub enum Transaction {
+    Postgres(sqlx::Transaction<'static, sqlx::Postgres>),
+    MySql(sqlx::Transaction<'static, sqlx::MySql>),
+    Sqlite(sqlx::Transaction<'static, sqlx::Sqlite>),
+    SqlServer(Box<MssqlTransaction>),
+}
+
+impl Transaction {
+    /// `DatabaseConnection::execute_tracked` inside the transaction.
+    pub async fn execute_tracked(
+        &mut self,
+        sql: &str,
+        params: &[Option<String>],
+        backend: &BackendId,
+    ) -> Result<QueryResult> {
+        match self {
+            Self::Postgres(tx) => Session::Postgres(tx).run(sql, params, backend).await,
+            Self::MySql(tx) => Session::MySql(tx).run(sql, params, backend).await,
+            Self::Sqlite(tx) => Session::Sqlite(tx).run(sql, params, backend).await,
+            Self::SqlServer(tx) => Session::SqlServer(tx).run(sql, params, backend).await,
+        }
+    }
+
+    pub async fn commit(self) -> Result<()> {
+        match self {
+            Self::Postgres(tx) => tx.commit().await?,
+            Self::MySql(tx) => tx.commit().await?,
+            Self::Sqlite(tx) => tx.commit().await?,
+            Self::SqlServer(tx) => tx.commit().await?,
+        }
+        Ok(())
+    }
+
+    pub async fn rollback(self) -> Result<()> {
+        match self {
+            Self::Postgres(tx) => tx.rollback().await?,
+            Self::MySql(tx) => tx.rollback().await?,
+            Self::Sqlite(tx) => tx.rollback().await?,
+            Self::SqlServer(tx) => tx.rollback().await?,
+        }
+        Ok(())
+    }
+}
+
+/// Server-side id of the connection a query runs on, so `cancel_query`
+/// stops that query and no other. Shared with the task running it.
+#[derive(Debug, Clone, Default)]
+pub struct BackendId(Arc<Mutex<Option<u64>>>);
+
+impl BackendId {
+    fn set(&self, id: Option<u64>) {
+        if let Ok(mut slot) = self.0.lock() {
+            *slot = id;
+        }
+    }
+
+    pub fn get(&self) -> Option<u64> {
+        self.0.lock().ok().and_then(|slot| *slot)
+    }
+}
+
+/// One connection, from the pool or a transaction, so that a whole script
+/// runs on the backend it was tracked by. SQL Server isn't tracked: its
+/// query stops when the abandoned connection is dropped.
+enum Session<'c> {
+    Postgres(&'c mut sqlx::PgConnection),
+    MySql(&'c mut sqlx::MySqlConnection),
+    Sqlite(&'c mut sqlx::SqliteConnection),
+    SqlServerPool(&'c MssqlPool),
+    SqlServer(&'c mut MssqlTransaction),
+}
+
+impl Session<'_> {
+    async fn run(&mut self, sql: &str, params: &[Option<String>], backend: &BackendId) -> Result<QueryResult> {
+        backend.set(self.backend_id().await?);
+        if !params.is_empty() {
+            return self.execute_with_params(sql, params).await;
+        }
+        // Each statement in turn, stopping at the first error: the last
+        // result set, or a summary of affected rows when none returned rows.
+        let statements = split_sql_statements(sql);
+        if statements.len() <= 1 {
+            return self.execute_query(sql).await;
+        }
+        let mut script = ScriptResult::new(statements.len());
+        for (i, statement) in statements.iter().enumerate() {
+            script.add(i, self.execute_query(statement).await)?;
+        }
+        Ok(script.finish())
+    }
+
+    async fn backend_id(&mut self) -> Result<Option<u64>> {
+        Ok(match self {
+            Self::Postgres(conn) => {
+                let pid: i32 = sqlx::query_scalar("SELECT pg_backend_pid()").fetch_one(&mut **conn).await?;
+                Some(pid as u64)
+            }
+            Self::MySql(conn) => Some(sqlx::query_scalar("SELECT CONNECTION_ID()").fetch_one(&mut **conn).await?),
+            Self::Sqlite(_) | Self::SqlServerPool(_) | Self::SqlServer(_) => None,
+        })
+    }
+
+    async fn execute_query(&mut self, query: &str) -> Result<QueryResult> {
+        match self {
+            Self::Postgres(conn) => {
+                let statement = sqlx::query(query);
+                run_statement(&mut **conn, statement, extract_pg_value, PgQueryResult::rows_affected).await
+            }
+            Self::MySql(conn) => {
+                let statement = sqlx::query(query);
+                run_statement(&mut **conn, statement, extract_mysql_value, MySqlQueryResult::rows_affected).await
+            }
+            Self::Sqlite(conn) => {
+                let statement = sqlx::query(query);
+                run_statement(&mut **conn, statement, extract_sqlite_value, SqliteQueryResult::rows_affected).await
+            }
+            Self::SqlServerPool(pool) => pool.execute_query(query).await,
+            Self::SqlServer(tx) => tx.execute_query(query).await,
+        }
+    }
+
+    async fn execute_with_params(&mut self, query: &str, params: &[Option<String>]) -> Result<QueryResult> {
+        match self {
+            Self::Postgres(conn) => {
+                let statement = bind_pg(query, &pg_param_types(&mut **conn, query).await?, params)?;
+                run_statement(&mut **conn, statement, extract_pg_value, PgQueryResult::rows_affected).await
+            }
+            Self::MySql(conn) => {
+                let statement = bind_inferred(query, params);
+                run_statement(&mut **conn, statement, extract_mysql_value, MySqlQueryResult::rows_affected).await
+            }
+            Self::Sqlite(conn) => {
+                let statement = bind_inferred(query, params);
+                run_statement(&mut **conn, statement, extract_sqlite_value, SqliteQueryResult::rows_affected).await
+            }
+            Self::SqlServerPool(pool) => pool.execute_with_params(query, params).await,
+            Self::SqlServer(tx) => tx.execute_with_params(query, params).await,
+        }
+    }
+}
+
+/// Collects the results of a multi-statement script: the last result set,
+/// or the summed affected rows when no statement returned rows.
+struct ScriptResult {
+    total: usize,
+    last_rows: Option<QueryResult>,
+    affected_rows: u64,
+}
+
+impl ScriptResult {
+    fn new(total: usize) -> Self {
+        Self {
+            total,
+            last_rows: None,
+            affected_rows: 0,
+        }
+    }
+
+    fn add(&mut self, index: usize, result: Result<QueryResult>) -> Result<()> {
+        let result = result.with_context(|| format!("Statement {} of {} failed", index + 1, self.total))?;
+        if result.is_command {
+            self.affected_rows += result.affected_rows;
+        } else {
+            self.last_rows = Some(result);
+        }
+        Ok(())
+    }
+
+    fn finish(self) -> QueryResult {
+        self.last_rows.unwrap_or(QueryResult {
+            affected_rows: self.affected_rows,
+            is_command: true,
+            statements: self.total,
+            ..QueryResult::empty()
+        })
+    }
+}
+
+/// Runs one statement: its result set, or the number of affected rows for
+/// statements that don't return rows.
+async fn run_statement<'c, 'q, DB, E>(
+    executor: E,
+    statement: sqlx::query::Query<'q, DB, DB::Arguments<'q>>,
+    extract: fn(&DB::Row, usize) -> Option<String>,
+    rows_affected: fn(&DB::QueryResult) -> u64,
+) -> Result<QueryResult>
+where
+    DB: sqlx::Database,
+    E: sqlx::Executor<'c, Database = DB>,
+    DB::Arguments<'q>: sqlx::IntoArguments<'q, DB>,
+    usize: sqlx::ColumnIndex<DB::Row>,
+{
+    // Procedures may or may not return rows; keep their first result set.
+    let is_call = first_keyword(statement.sql()) == "CALL";
+    if !returns_rows(statement.sql()) && !is_call {
+        let result = statement.execute(executor).await?;
+        return Ok(QueryResult {
+            affected_rows: rows_affected(&result),
+            is_command: true,
+            ..QueryResult::empty()
+        });
+    }
+
+    let mut affected = 0;
+    let rows = if is_call {
+        let mut rows = Vec::new();
+        let mut first_set_done = false;
+        let mut results = executor.fetch_many(statement);
+        while let Some(step) = results.try_next().await? {
+            match step {
+                sqlx::Either::Left(result) => {
+                    affected += rows_affected(&result);
+                    first_set_done = !rows.is_empty();
                 }
+                sqlx::Either::Right(row) if !first_set_done => rows.push(row),
+                sqlx::Either::Right(_) => {}
+            }
+        }
+        rows
+    } else {
+        statement.fetch_all(executor).await?
+    };
+    let Some(first) = rows.first() else {
+        return Ok(if is_call {
+            QueryResult {
+                affected_rows: affected,
+                is_command: true,
+                ..QueryResult::empty()
+            }
+        } else {
+            QueryResult::empty()
+        });
+    };
+    let columns: Vec<String> = first.columns().iter().map(|c| c.name().to_string()).collect();
+    let column_types: Vec<String> = first
+        .columns()
+        .iter()
+        .map(|c| match c.type_info().name() {
+            // SQLite has no declared type for expressions; use the first value's.
+            "NULL" => rows
+                .iter()
+                .filter_map(|row| row.try_get_raw(c.ordinal()).ok())
+                .find(|value| !value.is_null())
+                .map_or_else(|| "NULL".to_string(), |value| value.type_info().name().to_string()),
+            name => name.to_string(),
+        })
+        .collect();
+    let data: Vec<Vec<Option<String>>> = rows
+        .iter()
+        .map(|row| (0..columns.len()).map(|idx| extract(row, idx)).collect())
+        .collect();
 
-                let columns: Vec<String> = rows[0]
-                    .columns()
-                    .iter()
-                    .map(|c| c.name().to_string())
-                    .collect();
+    Ok(QueryResult {
+        columns,
+        column_types,
+        rows: data,
+        affected_rows: rows.len() as u64,
+        ..QueryResult::empty()
+    })
+}
 
-                let data: Vec<Vec<String>> = rows
-                    .iter()
-                    .map(|row| {
-                        (0..columns.len())
-                            .map(|idx| extract_sqlite_value(row, idx))
-                            .collect()
-                    })
-                    .collect();
+async fn stream_rows<R: Row>(
+    mut rows: impl Stream<Item = Result<R, sqlx::Error>> + Unpin,
+    extract: fn(&R, usize) -> Option<String>,
+    sink: &mut dyn RowSink,
+) -> Result<u64> {
+    let mut count = 0u64;
+    while let Some(row) = rows.try_next().await? {
+        if count == 0 {
+            let columns: Vec<String> = row.columns().iter().map(|c| c.name().to_string()).collect();
+            sink.columns(&columns)?;
+        }
+        let values: Vec<Option<String>> = (0..row.len()).map(|idx| extract(&row, idx)).collect();
+        sink.row(&values)?;
+        count += 1;
+    }
+    Ok(count)
+}
+
+/// True when a connect error was caused by bad credentials (SQLSTATE
+/// 28P01 invalid_password or 28000, which MySQL uses for 1045; SQL Server
+/// error 18456).
+pub fn is_auth_failure(err: &anyhow::Error) -> bool {
+    match err.downcast_ref::<sqlx::Error>() {
+        Some(sqlx::Error::Database(db_err)) => {
+            matches!(db_err.code().as_deref(), Some("28P01") | Some("28000"))
+        }
+        _ => mssql::is_auth_failure(err),
+    }
+}
+
+/// True when a query failed because the connection itself went away;
+/// the server rolls back any transaction that was open on it.
+pub fn is_connection_lost(err: &anyhow::Error) -> bool {
+    match err.downcast_ref::<sqlx::Error>() {
+        Some(
+            sqlx::Error::Io(_)
+            | sqlx::Error::Tls(_)
+            | sqlx::Error::Protocol(_)
+            | sqlx::Error::PoolClosed
+            | sqlx::Error::WorkerCrashed,
+        ) => true,
+        // Postgres: the server is shutting down or restarting.
+        Some(sqlx::Error::Database(db_err)) => {
+            matches!(db_err.code().as_deref(), Some("57P01") | Some("57P02") | Some("57P03"))
+        }
+        _ => mssql::is_connection_lost(err),
+    }
+}
+
+/// Full text of a query error, with the Postgres DETAIL/HINT/WHERE
+/// sections and the SQLSTATE (or SQL Server error number) on lines of
+/// their own.
+pub fn describe_error(err: &anyhow::Error) -> String {
+    let Some(sqlx::Error::Database(db_err)) = err.downcast_ref::<sqlx::Error>() else {
+        return mssql::describe_error(err).unwrap_or_else(|| format!("{err:#}"));
+    };
+
+    let mut text = db_err.message().to_string();
+    if let Some(pg) = db_err.try_downcast_ref::<sqlx::postgres::PgDatabaseError>() {
+        text = format!("{}: {}", format!("{:?}", pg.severity()).to_uppercase(), pg.message());
+        if let Some(detail) = pg.detail() {
+            text.push_str(&format!("\nDETAIL: {detail}"));
+        }
+        if let Some(hint) = pg.hint() {
+            text.push_str(&format!("\nHINT: {hint}"));
+        }
+        if let Some(r#where) = pg.r#where() {
+            text.push_str(&format!("\nWHERE: {}", r#where));
+        }
+        if let Some(sqlx::postgres::PgErrorPosition::Original(position)) = pg.position() {
+            text.push_str(&format!("\nPOSITION: {position}"));
+        }
+    }
+    if let Some(code) = db_err.code() {
+        text.push_str(&format!("\nCODE: {code}"));
+    }
+    with_context::<sqlx::Error>(err, text)
+}
+
+/// Puts context added on top of a driver error, such as which statement
+/// of a script failed, before its description.
+pub(super) fn with_context<E: std::error::Error + 'static>(err: &anyhow::Error, text: String) -> String {
+    let context: Vec<String> = err.chain().take_while(|e| !e.is::<E>()).map(|e| e.to_string()).collect();
+    if context.is_empty() {
+        text
+    } else {
+        format!("{}\n{}", context.join(": "), text)
+    }
+}
+
+/// A pool timeout while connecting means the server never answered.
+/// Refusals of unencrypted connections say how to turn TLS on, and are not
+/// reported as authentication failures.
+fn connect_error(err: sqlx::Error, timeout: Duration) -> anyhow::Error {
+    match err {
+        sqlx::Error::PoolTimedOut => anyhow::anyhow!("Timed out after {}s waiting for the server", timeout.as_secs()),
+        sqlx::Error::Database(db_err) if requires_tls(db_err.as_ref()) => anyhow::anyhow!(
+            "{}. The server requires TLS: add sslmode=require (PostgreSQL) or ssl-mode=REQUIRED (MySQL) to the URL",
+            db_err.message()
+        ),
+        err => err.into(),
+    }
+}
+
+fn requires_tls(err: &dyn sqlx::error::DatabaseError) -> bool {
+    // Postgres: no pg_hba.conf entry for a non-SSL client; MySQL 3159:
+    // require_secure_transport is on.
+    (err.code().as_deref() == Some("28000") && err.message().contains("SSL off"))
+        || err
+            .try_downcast_ref::<sqlx::mysql::MySqlDatabaseError>()
+            .is_some_and(|e| e.number() == 3159)
+}
+
+/// Replaces the password in a connection URL, keeping everything else.
+pub fn with_password(connection_string: &str, password: &str) -> Result<String> {
+    let mut url = url::Url::parse(connection_string)?;
+    url.set_password(Some(password))
+        .map_err(|_| anyhow::anyhow!("Connection string cannot carry a password"))?;
+    Ok(url.to_string())
+}
+
+/// The connection string with any password replaced by `***`, safe to show.
+/// Strings that aren't URLs (e.g. a bare SQLite path) are returned as is.
+pub fn mask_password(connection_string: &str) -> String {
+    match url::Url::parse(connection_string) {
+        Ok(mut url) if url.password().is_some() => {
+            let _ = url.set_password(Some("***"));
+            url.to_string()
+        }
+        _ => connection_string.to_string(),
+    }
+}
+
+/// Splits a connection URL into the URL without its password and the
+/// password as written in it; `None` when there is no password.
+pub fn split_password(connection_string: &str) -> Option<(String, String)> {
+    let mut url = url::Url::parse(connection_string).ok()?;
+    let password = url.password()?.to_string();
+    url.set_password(None).ok()?;
+    Some((url.to_string(), password))
+}
+
+/// Text that is not valid UTF-8 (e.g. latin1 stored in a text column) is
+/// decoded lossily; the U+FFFD replacement characters mark the cell.
+fn lossy_text(bytes: &[u8]) -> String {
+    String::from_utf8_lossy(bytes).into_owned()
+}
+
+/// A column of a CREATE TABLE put together from the catalog.
+struct DdlColumn {
+    name: String,
+    data_type: String,
+    nullable: bool,
+    default: Option<String>,
+}
+
+/// `varchar(20)`, `numeric(10,2)`; the bare type when it has no size.
+fn sized_type(data_type: &str, size: Option<i32>, scale: Option<i32>) -> String {
+    match (size, scale) {
+        (Some(size), Some(scale)) => format!("{data_type}({size},{scale})"),
+        (Some(size), None) => format!("{data_type}({size})"),
+        (None, _) => data_type.to_string(),
+    }
+}
+
+/// Two-column catalog rows as text, skipping rows with a NULL.
+fn text_pairs(rows: Vec<Vec<Option<String>>>) -> Vec<(String, String)> {
+    rows.into_iter()
+        .filter_map(|row| match row.as_slice() {
+            [Some(a), Some(b), ..] => Some((a.clone(), b.clone())),
+            _ => None,
+        })
+        .collect()
+}
+
+fn quote_sqlite_identifier(name: &str) -> String {
+    format!("\"{}\"", name.replace('"', "\"\""))
+}
+
+type PgQuery<'q> = sqlx::query::Query<'q, sqlx::Postgres, sqlx::postgres::PgArguments>;
+
+/// The types Postgres infers for a statement's parameters.
+async fn pg_param_types<'c>(
+    executor: impl Executor<'c, Database = sqlx::Postgres>,
+    query: &str,
+) -> Result<Vec<PgTypeInfo>> {
+    let statement = executor.prepare(query).await?;
+    Ok(match statement.parameters() {
+        Some(sqlx::Either::Left(types)) => types.to_vec(),
+        _ => Vec::new(),
+    })
+}
 
-                Ok(QueryResult {
-                    columns,
-                    rows: data,
-                    affected_rows: rows.len() as u64,
+/// Postgres doesn't convert text parameters to the type a statement
+/// expects, so each value is parsed as the type it inferred.
+fn bind_pg<'q>(query: &'q str, types: &[PgTypeInfo], params: &[Option<String>]) -> Result<PgQuery<'q>> {
+    let mut statement = sqlx::query(query);
+    for (i, value) in params.iter().enumerate() {
+        let value = value.as_deref().map(str::trim);
+        let type_name = types.get(i).map_or("TEXT", |t| t.name());
+        if let Some(type_info) = types.get(i).filter(|t| matches!(t.kind(), PgTypeKind::Enum(_))) {
+            statement = statement.bind(PgEnumLabel {
+                label: value.map(str::to_string),
+                type_info: type_info.clone(),
+            });
+            continue;
+        }
+        statement = bind_pg_value(statement, value, type_name)
+            .with_context(|| format!("${} is not a valid {}", i + 1, type_name.to_lowercase()))?;
+    }
+    Ok(statement)
+}
+
+fn bind_pg_value<'q>(statement: PgQuery<'q>, value: Option<&str>, type_name: &str) -> Result<PgQuery<'q>> {
+    use sqlx::types::chrono::{DateTime, FixedOffset, NaiveDate, NaiveTime};
+    use sqlx::types::{BigDecimal, JsonValue, Uuid};
+
+    fn parse<T>(value: Option<&str>) -> Result<Option<T>>
+    where
+        T: FromStr,
+        T::Err: std::error::Error + Send + Sync + 'static,
+    {
+        Ok(value.map(str::parse).transpose()?)
+    }
+
+    Ok(match type_name {
+        "BOOL" => statement.bind(
+            value
+                .map(|v| match v.to_lowercase().as_str() {
+                    "t" | "true" | "y" | "yes" | "on" | "1" => Ok(true),
+                    "f" | "false" | "n" | "no" | "off" | "0" => Ok(false),
+                    _ => Err(anyhow::anyhow!("expected true or false")),
                 })
-            }
+                .transpose()?,
+        ),
+        "INT2" => statement.bind(parse::<i16>(value)?),
+        "INT4" => statement.bind(parse::<i32>(value)?),
+        "INT8" => statement.bind(parse::<i64>(value)?),
+        "FLOAT4" => statement.bind(parse::<f32>(value)?),
+        "FLOAT8" => statement.bind(parse::<f64>(value)?),
+        "NUMERIC" => statement.bind(parse::<BigDecimal>(value)?),
+        "UUID" => statement.bind(parse::<Uuid>(value)?),
+        "DATE" => statement.bind(parse::<NaiveDate>(value)?),
+        "TIME" => statement.bind(parse::<NaiveTime>(value)?),
+        "TIMESTAMP" => statement.bind(value.map(parse_timestamp).transpose()?),
+        "TIMESTAMPTZ" => statement.bind(parse::<DateTime<FixedOffset>>(value)?),
+        "JSON" | "JSONB" => statement.bind(value.map(serde_json::from_str::<JsonValue>).transpose()?),
+        "BYTEA" => statement.bind(
+            value
+                .map(|v| hex::decode(v.trim_start_matches("\\x").trim_start_matches("0x")))
+                .transpose()?,
+        ),
+        _ => statement.bind(value.map(str::to_string)),
+    })
+}
+
+/// `2024-01-31 10:00:00`, also with a `T`, without seconds, or a bare date.
+fn parse_timestamp(value: &str) -> Result<sqlx::types::chrono::NaiveDateTime> {
+    use sqlx::types::chrono::{NaiveDate, NaiveDateTime};
+
+    let value = value.replacen('T', " ", 1);
+    ["%Y-%m-%d %H:%M:%S%.f", "%Y-%m-%d %H:%M"]
+        .iter()
+        .find_map(|format| NaiveDateTime::parse_from_str(&value, format).ok())
+        .or_else(|| NaiveDate::parse_from_str(&value, "%Y-%m-%d").ok().and_then(|d| d.and_hms_opt(0, 0, 0)))
+        .context("expected YYYY-MM-DD HH:MM:SS")
+}
+
+/// A value for an enum parameter. Postgres won't compare an enum with
+/// text, but an enum's binary form is just its label.
+struct PgEnumLabel {
+    label: Option<String>,
+    type_info: PgTypeInfo,
+}
+
+impl sqlx::Type<sqlx::Postgres> for PgEnumLabel {
+    fn type_info() -> PgTypeInfo {
+        <String as sqlx::Type<sqlx::Postgres>>::type_info()
+    }
+}
+
+impl sqlx::Encode<'_, sqlx::Postgres> for PgEnumLabel {
+    fn encode_by_ref(
+        &self,
+        buf: &mut sqlx::postgres::PgArgumentBuffer,
+    ) -> Result<sqlx::encode::IsNull, sqlx::error::BoxDynError> {
+        match &self.label {
+            Some(label) => <&str as sqlx::Encode<sqlx::Postgres>>::encode_by_ref(&label.as_str(), buf),
+            None => Ok(sqlx::encode::IsNull::Yes),
         }
     }
+
+    fn produces(&self) -> Option<PgTypeInfo> {
+        Some(self.type_info.clone())
+    }
+}
+
+/// Binds parameters on engines that convert them to the column's type
+/// themselves; values are sent as numbers when they look like one.
+fn bind_inferred<'q, DB>(query: &'q str, params: &'q [Option<String>]) -> sqlx::query::Query<'q, DB, DB::Arguments<'q>>
+where
+    DB: sqlx::Database,
+    i64: sqlx::Encode<'q, DB> + sqlx::Type<DB>,
+    f64: sqlx::Encode<'q, DB> + sqlx::Type<DB>,
+    &'q str: sqlx::Encode<'q, DB> + sqlx::Type<DB>,
+    Option<&'q str>: sqlx::Encode<'q, DB> + sqlx::Type<DB>,
+{
+    params.iter().fold(sqlx::query(query), |statement, value| {
+        match ParamValue::infer(value.as_deref()) {
+            ParamValue::Null => statement.bind(None::<&str>),
+            ParamValue::Integer(n) => statement.bind(n),
+            ParamValue::Float(f) => statement.bind(f),
+            ParamValue::Text(text) => statement.bind(text),
+        }
+    })
 }
 
-fn extract_pg_value(row: &sqlx::postgres::PgRow, idx: usize) -> String {
+fn extract_pg_value(row: &sqlx::postgres::PgRow, idx: usize) -> Option<String> {
     let value_ref = row.try_get_raw(idx).ok();
     
     if let Some(vr) = value_ref {
         if vr.is_null() {
-            return "NULL".to_string();
+            return None;
         }
         
         let type_info = vr.type_info().clone();
@@ -194,88 +1362,129 @@ fn extract_pg_value(row: &sqlx::postgres::PgRow, idx: usize) -> String {
         match type_name {
             "BOOL" => {
                 if let Ok(v) = row.try_get::<bool, _>(idx) {
-                    return v.to_string();
+                    return Some(v.to_string());
                 }
             }
             "INT2" | "SMALLINT" | "SMALLSERIAL" => {
                 if let Ok(v) = row.try_get::<i16, _>(idx) {
-                    return v.to_string();
+                    return Some(v.to_string());
                 }
             }
             "INT4" | "INT" | "INTEGER" | "SERIAL" => {
                 if let Ok(v) = row.try_get::<i32, _>(idx) {
-                    return v.to_string();
+                    return Some(v.to_string());
                 }
             }
             "INT8" | "BIGINT" | "BIGSERIAL" => {
                 if let Ok(v) = row.try_get::<i64, _>(idx) {
-                    return v.to_string();
+                    return Some(v.to_string());
                 }
             }
             "FLOAT4" | "REAL" => {
                 if let Ok(v) = row.try_get::<f32, _>(idx) {
-                    return v.to_string();
+                    return Some(v.to_string());
                 }
             }
             "FLOAT8" | "DOUBLE PRECISION" => {
                 if let Ok(v) = row.try_get::<f64, _>(idx) {
-                    return v.to_string();
+                    return Some(v.to_string());
                 }
             }
             "NUMERIC" | "DECIMAL" => {
+                // Never go through f64: it loses precision and prints exponents.
                 if let Ok(v) = row.try_get::<sqlx::types::BigDecimal, _>(idx) {
-                    return v.to_string();
+                    return Some(v.to_plain_string());
                 }
-                if let Ok(v) = row.try_get::<f64, _>(idx) {
-                    return v.to_string();
+                if let Some(v) = pg_numeric_special(vr.as_bytes().unwrap_or_default(), vr.format()) {
+                    return Some(v.to_string());
                 }
             }
             "TEXT" | "VARCHAR" | "CHAR" | "BPCHAR" | "NAME" => {
                 if let Ok(v) = row.try_get::<String, _>(idx) {
-                    return v;
+                    return Some(v);
+                }
+                if let Ok(v) = row.try_get_unchecked::<Vec<u8>, _>(idx) {
+                    return Some(lossy_text(&v));
                 }
             }
             "UUID" => {
                 if let Ok(v) = row.try_get::<sqlx::types::Uuid, _>(idx) {
-                    return v.to_string();
+                    return Some(v.to_string());
                 }
             }
             "DATE" => {
                 if let Ok(v) = row.try_get::<sqlx::types::chrono::NaiveDate, _>(idx) {
-                    return v.to_string();
+                    return Some(v.to_string());
                 }
             }
             "TIME" | "TIMETZ" => {
                 if let Ok(v) = row.try_get::<sqlx::types::chrono::NaiveTime, _>(idx) {
-                    return v.to_string();
+                    return Some(v.to_string());
                 }
             }
             "TIMESTAMP" => {
                 if let Ok(v) = row.try_get::<sqlx::types::chrono::NaiveDateTime, _>(idx) {
-                    return v.to_string();
+                    return Some(v.to_string());
                 }
             }
             "TIMESTAMPTZ" => {
                 if let Ok(v) = row.try_get::<sqlx::types::chrono::DateTime<sqlx::types::chrono::Utc>, _>(idx) {
-                    return v.to_string();
+                    return Some(v.to_string());
                 }
             }
             "JSON" | "JSONB" => {
                 if let Ok(v) = row.try_get::<sqlx::types::JsonValue, _>(idx) {
-                    return v.to_string();
+                    return Some(v.to_string());
                 }
             }
             "BYTEA" => {
                 if let Ok(v) = row.try_get::<Vec<u8>, _>(idx) {
-                    return format!("\\x{}", hex::encode(v));
+                    return Some(format!("\\x{}", hex::encode(v)));
                 }
             }
             "INET" | "CIDR" => {
                 if let Ok(v) = row.try_get::<String, _>(idx) {
-                    return v;
+                    return Some(v);
+                }
+            }
+            "OID" => {
+                if let Ok(v) = row.try_get::<sqlx::postgres::types::Oid, _>(idx) {
+                    return Some(v.0.to_string());
+                }
+                if let Ok(v) = row.try_get_unchecked::<String, _>(idx) {
+                    return Some(v);
+                }
+            }
+            "XML" => {
+                if let Ok(v) = row.try_get_unchecked::<String, _>(idx) {
+                    return Some(v);
+                }
+            }
+            "BIT" | "VARBIT" => {
+                if let Some(v) = decode_pg_bits(vr.as_bytes().unwrap_or_default(), vr.format()) {
+                    return Some(v);
+                }
+                if let Ok(v) = row.try_get_unchecked::<String, _>(idx) {
+                    return Some(v);
+                }
+            }
+            "INTERVAL" => {
+                if let Ok(v) = row.try_get::<sqlx::postgres::types::PgInterval, _>(idx) {
+                    return Some(format_pg_interval(&v));
+                }
+            }
+            "INT4RANGE" | "INT8RANGE" | "NUMRANGE" | "DATERANGE" | "TSRANGE" | "TSTZRANGE" => {
+                if let Some(v) = extract_pg_range(row, idx, &vr, type_name) {
+                    return Some(v);
+                }
+            }
+            _ => {
+                if let Some(element) = type_name.strip_suffix("[]").or_else(|| type_name.strip_prefix('_'))
+                    && let Some(v) = extract_pg_array(row, idx, &element.to_ascii_uppercase())
+                {
+                    return Some(v);
                 }
             }
-            _ => {}
         }
     }
     
@@ -284,15 +1493,170 @@ fn extract_pg_value(row: &sqlx::postgres::PgRow, idx: usize) -> String {
         .or_else(|_| row.try_get::<i32, _>(idx).map(|v| v.to_string()))
         .or_else(|_| row.try_get::<f64, _>(idx).map(|v| v.to_string()))
         .or_else(|_| row.try_get::<bool, _>(idx).map(|v| v.to_string()))
-        .unwrap_or_else(|_| "NULL".to_string())
+        .ok()
+}
+
+/// Like Postgres prints it: `1 year 2 mons 3 days 04:05:06.5`. A field
+/// right after a negative one shows its sign, e.g. `-1 years +2 days`.
+fn format_pg_interval(v: &sqlx::postgres::types::PgInterval) -> String {
+    let mut parts = Vec::new();
+    let mut after_negative = false;
+    for (n, unit) in [(v.months / 12, "year"), (v.months % 12, "mon"), (v.days, "day")] {
+        if n != 0 {
+            let sign = if after_negative && n > 0 { "+" } else { "" };
+            parts.push(format!("{sign}{n} {unit}{}", if n == 1 { "" } else { "s" }));
+            after_negative = n < 0;
+        }
+    }
+    if v.microseconds != 0 || parts.is_empty() {
+        let sign = if v.microseconds < 0 {
+            "-"
+        } else if after_negative {
+            "+"
+        } else {
+            ""
+        };
+        let us = v.microseconds.unsigned_abs();
+        let mut time = format!(
+            "{sign}{:02}:{:02}:{:02}",
+            us / 3_600_000_000,
+            us / 60_000_000 % 60,
+            us / 1_000_000 % 60
+        );
+        if !us.is_multiple_of(1_000_000) {
+            time.push_str(format!(".{:06}", us % 1_000_000).trim_end_matches('0'));
+        }
+        parts.push(time);
+    }
+    parts.join(" ")
 }
 
-fn extract_mysql_value(row: &sqlx::mysql::MySqlRow, idx: usize) -> String {
+/// A range such as `[1,10)`; `empty` for the empty range.
+fn extract_pg_range(
+    row: &sqlx::postgres::PgRow,
+    idx: usize,
+    value: &sqlx::postgres::PgValueRef<'_>,
+    type_name: &str,
+) -> Option<String> {
+    use sqlx::postgres::types::PgRange;
+    use sqlx::types::chrono::{DateTime, NaiveDate, NaiveDateTime, Utc};
+
+    // The binary encoding starts with a flags byte; 0x01 marks an empty range.
+    if value.format() == sqlx::postgres::PgValueFormat::Binary
+        && value.as_bytes().ok()?.first().is_some_and(|flags| flags & 0x01 != 0)
+    {
+        return Some("empty".to_string());
+    }
+    let text = match type_name {
+        "INT4RANGE" => row.try_get::<PgRange<i32>, _>(idx).ok()?.to_string(),
+        "INT8RANGE" => row.try_get::<PgRange<i64>, _>(idx).ok()?.to_string(),
+        "NUMRANGE" => row.try_get::<PgRange<sqlx::types::BigDecimal>, _>(idx).ok()?.to_string(),
+        "DATERANGE" => row.try_get::<PgRange<NaiveDate>, _>(idx).ok()?.to_string(),
+        "TSRANGE" => row.try_get::<PgRange<NaiveDateTime>, _>(idx).ok()?.to_string(),
+        "TSTZRANGE" => row.try_get::<PgRange<DateTime<Utc>>, _>(idx).ok()?.to_string(),
+        _ => return None,
+    };
+    Some(text)
+}
+
+/// A one-dimensional array in Postgres' own `{1,2,NULL}` notation.
+fn extract_pg_array(row: &sqlx::postgres::PgRow, idx: usize, element: &str) -> Option<String> {
+    Some(match element {
+        "BOOL" => format_pg_array(row.try_get::<Vec<Option<bool>>, _>(idx).ok()?, |v| {
+            if v { "t" } else { "f" }.to_string()
+        }),
+        "INT2" => format_pg_array(row.try_get::<Vec<Option<i16>>, _>(idx).ok()?, |v| v.to_string()),
+        "INT4" => format_pg_array(row.try_get::<Vec<Option<i32>>, _>(idx).ok()?, |v| v.to_string()),
+        "INT8" => format_pg_array(row.try_get::<Vec<Option<i64>>, _>(idx).ok()?, |v| v.to_string()),
+        "FLOAT4" => format_pg_array(row.try_get::<Vec<Option<f32>>, _>(idx).ok()?, |v| v.to_string()),
+        "FLOAT8" => format_pg_array(row.try_get::<Vec<Option<f64>>, _>(idx).ok()?, |v| v.to_string()),
+        "NUMERIC" => format_pg_array(row.try_get::<Vec<Option<sqlx::types::BigDecimal>>, _>(idx).ok()?, |v| {
+            v.to_plain_string()
+        }),
+        "TEXT" | "VARCHAR" | "BPCHAR" | "CHAR" | "NAME" => {
+            format_pg_array(row.try_get::<Vec<Option<String>>, _>(idx).ok()?, |v| v)
+        }
+        "UUID" => format_pg_array(row.try_get::<Vec<Option<sqlx::types::Uuid>>, _>(idx).ok()?, |v| v.to_string()),
+        "DATE" => format_pg_array(row.try_get::<Vec<Option<sqlx::types::chrono::NaiveDate>>, _>(idx).ok()?, |v| {
+            v.to_string()
+        }),
+        "TIMESTAMP" => format_pg_array(
+            row.try_get::<Vec<Option<sqlx::types::chrono::NaiveDateTime>>, _>(idx).ok()?,
+            |v| v.to_string(),
+        ),
+        "TIMESTAMPTZ" => format_pg_array(
+            row.try_get::<Vec<Option<sqlx::types::chrono::DateTime<sqlx::types::chrono::Utc>>>, _>(idx).ok()?,
+            |v| v.to_string(),
+        ),
+        _ => return None,
+    })
+}
+
+/// `{a,b,NULL}`, each element given as text by `to_text` and quoted as needed.
+fn format_pg_array<T>(items: Vec<Option<T>>, to_text: impl Fn(T) -> String) -> String {
+    let items: Vec<String> = items
+        .into_iter()
+        .map(|item| item.map_or_else(|| "NULL".to_string(), |v| quote_pg_array_element(to_text(v))))
+        .collect();
+    format!("{{{}}}", items.join(","))
+}
+
+/// Double-quotes an array element the way Postgres does when it is empty,
+/// spells NULL, or holds delimiters, quotes or whitespace.
+fn quote_pg_array_element(value: String) -> String {
+    let needs_quotes = value.is_empty()
+        || value.eq_ignore_ascii_case("NULL")
+        || value.chars().any(|c| matches!(c, ',' | '{' | '}' | '"' | '\\') || c.is_whitespace());
+    if !needs_quotes {
+        return value;
+    }
+    format!("\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+/// NaN and infinities, which `BigDecimal` cannot represent.
+fn pg_numeric_special(bytes: &[u8], format: sqlx::postgres::PgValueFormat) -> Option<&'static str> {
+    if format == sqlx::postgres::PgValueFormat::Text {
+        return match bytes {
+            b"NaN" => Some("NaN"),
+            b"Infinity" => Some("Infinity"),
+            b"-Infinity" => Some("-Infinity"),
+            _ => None,
+        };
+    }
+
+    // Binary header: ndigits, weight, sign, dscale (all 16-bit).
+    match u16::from_be_bytes([*bytes.get(4)?, *bytes.get(5)?]) {
+        0xC000 => Some("NaN"),
+        0xD000 => Some("Infinity"),
+        0xF000 => Some("-Infinity"),
+        _ => None,
+    }
+}
+
+fn decode_pg_bits(bytes: &[u8], format: sqlx::postgres::PgValueFormat) -> Option<String> {
+    if format == sqlx::postgres::PgValueFormat::Text {
+        return std::str::from_utf8(bytes).ok().map(str::to_string);
+    }
+
+    // Binary format: big-endian i32 bit count followed by the packed bits.
+    let (len, data) = bytes.split_first_chunk::<4>()?;
+    let bit_len = usize::try_from(i32::from_be_bytes(*len)).ok()?;
+    if data.len() * 8 < bit_len {
+        return None;
+    }
+    Some(
+        (0..bit_len)
+            .map(|i| if data[i / 8] & (0x80 >> (i % 8)) != 0 { '1' } else { '0' })
+            .collect(),
+    )
+}
+
+fn extract_mysql_value(row: &sqlx::mysql::MySqlRow, idx: usize) -> Option<String> {
     let value_ref = row.try_get_raw(idx).ok();
     
     if let Some(vr) = value_ref {
         if vr.is_null() {
-            return "NULL".to_string();
+            return None;
         }
         
         let type_info = vr.type_info().clone();
@@ -301,72 +1665,98 @@ fn extract_mysql_value(row: &sqlx::mysql::MySqlRow, idx: usize) -> String {
         match type_name {
             "BOOLEAN" | "TINYINT(1)" => {
                 if let Ok(v) = row.try_get::<bool, _>(idx) {
-                    return v.to_string();
+                    return Some(v.to_string());
                 }
             }
-            "TINYINT" => {
+            "TINYINT" | "TINYINT UNSIGNED" => {
+                if let Ok(v) = row.try_get::<u8, _>(idx) {
+                    return Some(v.to_string());
+                }
                 if let Ok(v) = row.try_get::<i8, _>(idx) {
-                    return v.to_string();
+                    return Some(v.to_string());
                 }
             }
-            "SMALLINT" => {
+            "SMALLINT" | "SMALLINT UNSIGNED" => {
+                if let Ok(v) = row.try_get::<u16, _>(idx) {
+                    return Some(v.to_string());
+                }
                 if let Ok(v) = row.try_get::<i16, _>(idx) {
-                    return v.to_string();
+                    return Some(v.to_string());
                 }
             }
-            "INT" | "MEDIUMINT" => {
+            "INT" | "MEDIUMINT" | "INT UNSIGNED" | "MEDIUMINT UNSIGNED" => {
+                if let Ok(v) = row.try_get::<u32, _>(idx) {
+                    return Some(v.to_string());
+                }
                 if let Ok(v) = row.try_get::<i32, _>(idx) {
-                    return v.to_string();
+                    return Some(v.to_string());
                 }
             }
-            "BIGINT" => {
+            "BIGINT" | "BIGINT UNSIGNED" => {
+                if let Ok(v) = row.try_get::<u64, _>(idx) {
+                    return Some(v.to_string());
+                }
                 if let Ok(v) = row.try_get::<i64, _>(idx) {
-                    return v.to_string();
+                    return Some(v.to_string());
+                }
+            }
+            "YEAR" => {
+                if let Ok(v) = row.try_get_unchecked::<u16, _>(idx) {
+                    return Some(v.to_string());
+                }
+            }
+            "BIT" => {
+                // BIT arrives as big-endian bytes whatever the value format.
+                if let Ok(v) = row.try_get_unchecked::<Vec<u8>, _>(idx) {
+                    return Some(format_mysql_bits(&v));
                 }
             }
             "FLOAT" => {
                 if let Ok(v) = row.try_get::<f32, _>(idx) {
-                    return v.to_string();
+                    return Some(v.to_string());
                 }
             }
             "DOUBLE" => {
                 if let Ok(v) = row.try_get::<f64, _>(idx) {
-                    return v.to_string();
+                    return Some(v.to_string());
                 }
             }
             "DECIMAL" => {
                 if let Ok(v) = row.try_get::<sqlx::types::BigDecimal, _>(idx) {
-                    return v.to_string();
+                    return Some(v.to_plain_string());
                 }
             }
             "VARCHAR" | "CHAR" | "TEXT" | "TINYTEXT" | "MEDIUMTEXT" | "LONGTEXT" | "ENUM" | "SET" => {
                 if let Ok(v) = row.try_get::<String, _>(idx) {
-                    return v;
+                    return Some(v);
+                }
+                if let Ok(v) = row.try_get_unchecked::<Vec<u8>, _>(idx) {
+                    return Some(lossy_text(&v));
                 }
             }
             "DATE" => {
                 if let Ok(v) = row.try_get::<sqlx::types::chrono::NaiveDate, _>(idx) {
-                    return v.to_string();
+                    return Some(v.to_string());
                 }
             }
             "TIME" => {
                 if let Ok(v) = row.try_get::<sqlx::types::chrono::NaiveTime, _>(idx) {
-                    return v.to_string();
+                    return Some(v.to_string());
                 }
             }
             "DATETIME" | "TIMESTAMP" => {
                 if let Ok(v) = row.try_get::<sqlx::types::chrono::NaiveDateTime, _>(idx) {
-                    return v.to_string();
+                    return Some(v.to_string());
                 }
             }
             "JSON" => {
                 if let Ok(v) = row.try_get::<sqlx::types::JsonValue, _>(idx) {
-                    return v.to_string();
+                    return Some(v.to_string());
                 }
             }
             "BLOB" | "TINYBLOB" | "MEDIUMBLOB" | "LONGBLOB" | "BINARY" | "VARBINARY" => {
                 if let Ok(v) = row.try_get::<Vec<u8>, _>(idx) {
-                    return format!("0x{}", hex::encode(v));
+                    return Some(format!("0x{}", hex::encode(v)));
                 }
             }
             _ => {}
@@ -374,19 +1764,27 @@ fn extract_mysql_value(row: &sqlx::mysql::MySqlRow, idx: usize) -> String {
     }
     
     row.try_get::<String, _>(idx)
+        .or_else(|_| row.try_get::<u64, _>(idx).map(|v| v.to_string()))
         .or_else(|_| row.try_get::<i64, _>(idx).map(|v| v.to_string()))
         .or_else(|_| row.try_get::<i32, _>(idx).map(|v| v.to_string()))
         .or_else(|_| row.try_get::<f64, _>(idx).map(|v| v.to_string()))
         .or_else(|_| row.try_get::<bool, _>(idx).map(|v| v.to_string()))
-        .unwrap_or_else(|_| "NULL".to_string())
+        .ok()
 }
 
-fn extract_sqlite_value(row: &sqlx::sqlite::SqliteRow, idx: usize) -> String {
+/// Renders a BIT value the way MySQL writes it, e.g. `b'101'`.
+fn format_mysql_bits(bytes: &[u8]) -> String {
+    let bits: String = bytes.iter().map(|b| format!("{b:08b}")).collect();
+    let trimmed = bits.trim_start_matches('0');
+    format!("b'{}'", if trimmed.is_empty() { "0" } else { trimmed })
+}
+
+fn extract_sqlite_value(row: &sqlx::sqlite::SqliteRow, idx: usize) -> Option<String> {
     let value_ref = row.try_get_raw(idx).ok();
     
     if let Some(vr) = value_ref {
         if vr.is_null() {
-            return "NULL".to_string();
+            return None;
         }
         
         let type_info = vr.type_info().clone();
@@ -395,43 +1793,46 @@ fn extract_sqlite_value(row: &sqlx::sqlite::SqliteRow, idx: usize) -> String {
         match type_name {
             "INTEGER" => {
                 if let Ok(v) = row.try_get::<i64, _>(idx) {
-                    return v.to_string();
+                    return Some(v.to_string());
                 }
             }
             "REAL" => {
                 if let Ok(v) = row.try_get::<f64, _>(idx) {
-                    return v.to_string();
+                    return Some(v.to_string());
                 }
             }
             "TEXT" => {
                 if let Ok(v) = row.try_get::<String, _>(idx) {
-                    return v;
+                    return Some(v);
+                }
+                if let Ok(v) = row.try_get_unchecked::<Vec<u8>, _>(idx) {
+                    return Some(lossy_text(&v));
                 }
             }
             "BLOB" => {
                 if let Ok(v) = row.try_get::<Vec<u8>, _>(idx) {
-                    return format!("X'{}'", hex::encode(v));
+                    return Some(format!("X'{}'", hex::encode(v)));
                 }
             }
             "BOOLEAN" => {
                 if let Ok(v) = row.try_get::<bool, _>(idx) {
-                    return v.to_string();
+                    return Some(v.to_string());
                 }
             }
             "DATE" => {
                 if let Ok(v) = row.try_get::<sqlx::types::chrono::NaiveDate, _>(idx) {
-                    return v.to_string();
+                    return Some(v.to_string());
                 }
                 if let Ok(v) = row.try_get::<String, _>(idx) {
-                    return v;
+                    return Some(v);
                 }
             }
             "DATETIME" | "TIMESTAMP" => {
                 if let Ok(v) = row.try_get::<sqlx::types::chrono::NaiveDateTime, _>(idx) {
-                    return v.to_string();
+                    return Some(v.to_string());
                 }
                 if let Ok(v) = row.try_get::<String, _>(idx) {
-                    return v;
+                    return Some(v);
                 }
             }
             _ => {}
@@ -443,5 +1844,95 @@ fn extract_sqlite_value(row: &sqlx::sqlite::SqliteRow, idx: usize) -> String {
         .or_else(|_| row.try_get::<f64, _>(idx).map(|v| v.to_string()))
         .or_else(|_| row.try_get::<bool, _>(idx).map(|v| v.to_string()))
         .or_else(|_| row.try_get::<Vec<u8>, _>(idx).map(|v| format!("X'{}'", hex::encode(v))))
-        .unwrap_or_else(|_| "NULL".to_string())
+        .ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn formats_pg_arrays_with_nulls() {
+        assert_eq!(format_pg_array(vec![Some(1), Some(2), None], |v: i32| v.to_string()), "{1,2,NULL}");
+        assert_eq!(format_pg_array(Vec::<Option<i32>>::new(), |v| v.to_string()), "{}");
+    }
+
+    #[test]
+    fn quotes_pg_array_elements_that_need_it() {
+        let items = ["plain", "", "NULL", "a,b", "two words", "say \"hi\"", "back\\slash"];
+        assert_eq!(
+            format_pg_array(items.map(Some).to_vec(), str::to_string),
+            r#"{plain,"","NULL","a,b","two words","say \"hi\"","back\\slash"}"#
+        );
+    }
+
+    #[test]
+    fn decodes_pg_bits_of_any_length() {
+        use sqlx::postgres::PgValueFormat::{Binary, Text};
+        let cases: &[(&[u8], _, Option<&str>)] = &[
+            (&[0, 0, 0, 0], Binary, Some("")),
+            (&[0, 0, 0, 3, 0b1010_0000], Binary, Some("101")),
+            (&[0, 0, 0, 8, 0b1000_0001], Binary, Some("10000001")),
+            (&[0, 0, 0, 12, 0xAB, 0xC0], Binary, Some("101010111100")),
+            (&[0, 0, 0, 9, 0xFF], Binary, None),
+            (&[0, 0], Binary, None),
+            (b"0110", Text, Some("0110")),
+        ];
+        for (bytes, format, expected) in cases {
+            assert_eq!(decode_pg_bits(bytes, *format).as_deref(), *expected, "{bytes:?}");
+        }
+    }
+
+    #[test]
+    fn formats_pg_intervals_like_postgres() {
+        use sqlx::postgres::types::PgInterval;
+        let cases = [
+            ((0, 0, 0), "00:00:00"),
+            ((14, 3, 14_706_500_000), "1 year 2 mons 3 days 04:05:06.5"),
+            ((-12, 0, 0), "-1 years"),
+            ((0, -1, 0), "-1 days"),
+            ((-14, 0, 0), "-1 years -2 mons"),
+            ((-12, 2, -10_800_000_000), "-1 years +2 days -03:00:00"),
+            ((0, -3, 14_706_500_000), "-3 days +04:05:06.5"),
+            ((0, 1, -1_000_000), "1 day -00:00:01"),
+            ((0, 0, -250_000), "-00:00:00.25"),
+        ];
+        for ((months, days, microseconds), expected) in cases {
+            let interval = PgInterval { months, days, microseconds };
+            assert_eq!(format_pg_interval(&interval), expected);
+        }
+    }
+
+    #[test]
+    fn formats_mysql_bits_without_leading_zeros() {
+        let cases: &[(&[u8], &str)] = &[
+            (&[], "b'0'"),
+            (&[0], "b'0'"),
+            (&[0b101], "b'101'"),
+            (&[0xFF], "b'11111111'"),
+            (&[0, 1], "b'1'"),
+            (&[1, 0], "b'100000000'"),
+        ];
+        for (bytes, expected) in cases {
+            assert_eq!(format_mysql_bits(bytes), *expected);
+        }
+    }
+
+    #[test]
+    fn recognizes_pg_numeric_nan_and_infinities() {
+        use sqlx::postgres::PgValueFormat::{Binary, Text};
+        let cases: &[(&[u8], _, Option<&str>)] = &[
+            (&[0, 0, 0, 0, 0xC0, 0, 0, 0], Binary, Some("NaN")),
+            (&[0, 0, 0, 0, 0xD0, 0, 0, 0], Binary, Some("Infinity")),
+            (&[0, 0, 0, 0, 0xF0, 0, 0, 0], Binary, Some("-Infinity")),
+            (&[0, 1, 0, 0, 0x40, 0, 0, 0, 0, 5], Binary, None),
+            (&[0, 0, 0, 0], Binary, None),
+            (b"NaN", Text, Some("NaN")),
+            (b"-Infinity", Text, Some("-Infinity")),
+            (b"1.5", Text, None),
+        ];
+        for (bytes, format, expected) in cases {
+            assert_eq!(pg_numeric_special(bytes, *format), *expected, "{bytes:?}");
+        }
+    }
 }
@@ -0,0 +1,492 @@
+/// Words the editor highlights and the formatter uppercases.
+pub const SQL_KEYWORDS: &[&str] = &[
+    "ADD", "ALL", "ALTER", "AND", "ANY", "AS", "ASC", "BEGIN", "BETWEEN", "BY", "CASE", "CAST", "CHECK",
+    "COLUMN", "COMMIT", "CONFLICT", "CONSTRAINT", "CREATE", "CROSS", "DATABASE", "DEFAULT", "DELETE", "DESC",
+    "DISTINCT", "DO", "DROP", "ELSE", "END", "EXCEPT", "EXISTS", "EXPLAIN", "FALSE", "FETCH", "FOREIGN", "FROM",
+    "FULL", "GROUP", "HAVING", "ILIKE", "IN", "INDEX", "INNER", "INSERT", "INTERSECT", "INTO", "IS", "JOIN",
+    "KEY", "LEFT", "LIKE", "LIMIT", "NATURAL", "NOT", "NOTHING", "NULL", "OFFSET", "ON", "OR", "ORDER",
+    "OUTER", "OVER", "PARTITION", "PRIMARY", "REFERENCES", "RETURNING", "RIGHT", "ROLLBACK", "SELECT", "SET",
+    "SHOW", "TABLE", "THEN", "TRUE", "TRUNCATE", "UNION", "UNIQUE", "UPDATE", "USING", "VALUES", "VIEW",
+    "WHEN", "WHERE", "WITH",
+];
+
+/// Keywords that start a clause on a line of its own.
+const CLAUSES: &[&str] = &[
+    "SELECT", "FROM", "WHERE", "GROUP", "ORDER", "HAVING", "LIMIT", "OFFSET", "FETCH", "UNION", "INTERSECT",
+    "EXCEPT", "JOIN", "LEFT", "RIGHT", "INNER", "FULL", "CROSS", "NATURAL", "SET", "VALUES", "RETURNING",
+    "INSERT", "UPDATE", "DELETE",
+];
+
+/// Words that may come before `JOIN` without starting a new line.
+const JOIN_MODIFIERS: &[&str] = &["LEFT", "RIGHT", "INNER", "FULL", "CROSS", "NATURAL", "OUTER"];
+
+const INDENT: &str = "    ";
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Word(String),
+    /// Strings, quoted identifiers, numbers and parameters, kept verbatim.
+    Literal(String),
+    LineComment(String),
+    BlockComment(String),
+    Open,
+    Close,
+    Comma,
+    Semicolon,
+    /// `.`, `::` and the brackets of a subscript or array type, written
+    /// without spaces around them.
+    Tight(String),
+    Operator(String),
+}
+
+/// Reformats SQL with one clause per line, the SELECT list one column
+/// per line, subqueries indented and keywords uppercased. Literals and
+/// comments are kept verbatim.
+pub fn format_sql(input: &str) -> String {
+    let mut f = Formatter::default();
+    let tokens = tokenize(input);
+    for (i, token) in tokens.iter().enumerate() {
+        f.push(token, tokens.get(i + 1));
+    }
+    f.out.trim_end().to_string()
+}
+
+/// State of one level of parentheses; the outermost level is the statement.
+#[derive(Default)]
+struct Level {
+    /// A subquery in parentheses lays out its clauses like a statement.
+    query: bool,
+    indent: usize,
+    /// Indentation of the line a subquery's `)` goes on.
+    close_indent: usize,
+    in_select_list: bool,
+    in_condition: bool,
+}
+
+struct Formatter {
+    out: String,
+    levels: Vec<Level>,
+    prev: Option<Token>,
+    /// The word before `prev`, when both are words.
+    prev_prev_word: Option<String>,
+    /// Break before the first column of a SELECT list, found at this depth.
+    break_pending: Option<usize>,
+    between: bool,
+    unary: bool,
+    /// Open `[` of subscripts and array literals; their commas don't break.
+    brackets: usize,
+}
+
+impl Default for Formatter {
+    fn default() -> Self {
+        Self {
+            out: String::new(),
+            levels: vec![Level {
+                query: true,
+                ..Level::default()
+            }],
+            prev: None,
+            prev_prev_word: None,
+            break_pending: None,
+            between: false,
+            unary: false,
+            brackets: 0,
+        }
+    }
+}
+
+impl Formatter {
+    fn level(&mut self) -> &mut Level {
+        self.levels.last_mut().expect("the statement level is never popped")
+    }
+
+    fn newline(&mut self, indent: usize) {
+        let trimmed = self.out.trim_end_matches(' ').len();
+        self.out.truncate(trimmed);
+        if !self.out.is_empty() && !self.out.ends_with('\n') {
+            self.out.push('\n');
+        }
+        self.out.push_str(&INDENT.repeat(indent));
+    }
+
+    fn space(&mut self) {
+        if !self.out.is_empty() && !self.out.ends_with([' ', '\n']) {
+            self.out.push(' ');
+        }
+    }
+
+    fn prev_word(&self) -> Option<&str> {
+        match &self.prev {
+            Some(Token::Word(word)) => Some(word),
+            _ => None,
+        }
+    }
+
+    fn push(&mut self, token: &Token, next: Option<&Token>) {
+        if let Some(depth) = self.break_pending
+            && self.levels.len() == depth
+            && !self.continues_select(token)
+        {
+            self.break_pending = None;
+            let indent = self.level().indent + 1;
+            self.newline(indent);
+        }
+
+        match token {
+            Token::Word(word) => {
+                let word = self.push_word(word, next);
+                self.unary = false;
+                self.prev_prev_word = self.prev_word().map(str::to_string);
+                self.prev = Some(Token::Word(word));
+                return;
+            }
+            Token::Literal(text) => {
+                self.separate();
+                self.out.push_str(text);
+            }
+            Token::LineComment(text) => {
+                self.space();
+                self.out.push_str(text);
+                let indent = self.level().indent;
+                self.newline(indent);
+            }
+            Token::BlockComment(text) => {
+                self.space();
+                self.out.push_str(text);
+            }
+            Token::Open => {
+                let table_name = matches!(self.prev_prev_word.as_deref(), Some("INTO" | "TABLE"));
+                let function_call = !table_name
+                    && self
+                        .prev_word()
+                        .is_some_and(|w| !SQL_KEYWORDS.contains(&w) || matches!(w, "CAST" | "ANY"));
+                if !function_call {
+                    self.separate();
+                }
+                self.out.push('(');
+                let query = matches!(next, Some(Token::Word(w)) if matches!(w.to_ascii_uppercase().as_str(), "SELECT" | "WITH"));
+                let line_indent = self.line_indent();
+                let indent = if query { line_indent + 1 } else { self.level().indent };
+                self.levels.push(Level {
+                    query,
+                    indent,
+                    close_indent: line_indent,
+                    ..Level::default()
+                });
+            }
+            Token::Close => {
+                if self.levels.len() > 1
+                    && let Some(level) = self.levels.pop()
+                    && level.query
+                {
+                    self.newline(level.close_indent);
+                }
+                self.out.push(')');
+            }
+            Token::Comma => {
+                self.out.push(',');
+                if self.level().in_select_list && self.brackets == 0 {
+                    let indent = self.level().indent + 1;
+                    self.newline(indent);
+                }
+            }
+            Token::Semicolon => {
+                self.out.push_str(";\n\n");
+                *self = Self {
+                    out: std::mem::take(&mut self.out),
+                    ..Self::default()
+                };
+                return;
+            }
+            Token::Tight(text) => {
+                match text.as_str() {
+                    "[" => self.brackets += 1,
+                    "]" => self.brackets = self.brackets.saturating_sub(1),
+                    _ => {}
+                }
+                self.out.push_str(text);
+            }
+            Token::Operator(op) => {
+                let unary = matches!(op.as_str(), "-" | "+")
+                    && !matches!(self.prev, Some(Token::Literal(_) | Token::Close))
+                    && self.prev_word().is_none_or(|w| SQL_KEYWORDS.contains(&w));
+                self.separate();
+                self.out.push_str(op);
+                self.unary = unary;
+                self.prev = Some(token.clone());
+                return;
+            }
+        }
+        self.unary = false;
+        self.prev = Some(token.clone());
+    }
+
+    /// Indentation of the line being written.
+    fn line_indent(&self) -> usize {
+        let line = self.out.rsplit('\n').next().unwrap_or("");
+        (line.len() - line.trim_start_matches(' ').len()) / INDENT.len()
+    }
+
+    /// Whether a token still belongs on the SELECT line: `DISTINCT` and
+    /// Postgres' `DISTINCT ON (...)`.
+    fn continues_select(&self, token: &Token) -> bool {
+        match token {
+            Token::Word(w) if w.eq_ignore_ascii_case("DISTINCT") => true,
+            Token::Word(w) if w.eq_ignore_ascii_case("ON") => self.prev_word() == Some("DISTINCT"),
+            Token::Open => self.prev_word() == Some("ON"),
+            Token::Close => true,
+            _ => false,
+        }
+    }
+
+    /// A space before the next token, unless it follows `(`, `.` or `::`,
+    /// or a unary sign.
+    fn separate(&mut self) {
+        if !matches!(self.prev, Some(Token::Open | Token::Tight(_))) && !self.unary {
+            self.space();
+        }
+    }
+
+    /// Writes a word, uppercased if it is a keyword, and returns it as written.
+    fn push_word(&mut self, word: &str, next: Option<&Token>) -> String {
+        let upper = word.to_ascii_uppercase();
+        let keyword = SQL_KEYWORDS.contains(&upper.as_str());
+        let word = if keyword { upper.as_str() } else { word };
+
+        let level = self.levels.last().expect("the statement level is never popped");
+        if keyword && level.query && self.starts_clause(word, next) {
+            let indent = level.indent;
+            self.newline(indent);
+            let level = self.level();
+            level.in_select_list = word == "SELECT";
+            level.in_condition = matches!(word, "WHERE" | "HAVING");
+            if word == "SELECT" {
+                self.break_pending = Some(self.levels.len());
+            }
+        } else if keyword && matches!(word, "AND" | "OR") && level.in_condition && !self.between {
+            let indent = level.indent + 1;
+            self.newline(indent);
+        } else {
+            self.separate();
+        }
+
+        if word == "BETWEEN" {
+            self.between = true;
+        } else if word == "AND" {
+            self.between = false;
+        }
+        self.out.push_str(word);
+        word.to_string()
+    }
+
+    fn starts_clause(&self, word: &str, next: Option<&Token>) -> bool {
+        if word == "ON" {
+            return matches!(next, Some(Token::Word(w)) if w.eq_ignore_ascii_case("CONFLICT"));
+        }
+        if !CLAUSES.contains(&word) {
+            return false;
+        }
+        let prev = self.prev_word();
+        match word {
+            "JOIN" => !prev.is_some_and(|w| JOIN_MODIFIERS.contains(&w)),
+            // Only LEFT JOIN and friends start a clause, not the LEFT() function.
+            "LEFT" | "RIGHT" | "INNER" | "FULL" | "CROSS" | "NATURAL" => {
+                !prev.is_some_and(|w| JOIN_MODIFIERS.contains(&w))
+                    && matches!(next, Some(Token::Word(_)))
+            }
+            // ON CONFLICT DO UPDATE SET stays on the ON CONFLICT line.
+            "UPDATE" | "DELETE" => prev != Some("DO"),
+            "SET" => !(prev == Some("UPDATE") && self.prev_prev_word.as_deref() == Some("DO")),
+            _ => true,
+        }
+    }
+}
+
+/// Index just past the literal opened at `open`, which `close` ends; a
+/// doubled `close` stays inside it.
+fn quoted_end(chars: &[char], open: usize, close: char) -> usize {
+    let mut i = open + 1;
+    while i < chars.len() {
+        if chars[i] == close {
+            if chars.get(i + 1) == Some(&close) {
+                i += 1;
+            } else {
+                break;
+            }
+        } else if chars[i] == '\\' && close == '\'' {
+            i += 1;
+        }
+        i += 1;
+    }
+    (i + 1).min(chars.len())
+}
+
+fn tokenize(input: &str) -> Vec<Token> {
+    let chars: Vec<char> = input.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        let start = i;
+        if c.is_whitespace() {
+            i += 1;
+            continue;
+        }
+        let token = if c == '-' && chars.get(i + 1) == Some(&'-') {
+            while i < chars.len() && chars[i] != '\n' {
+                i += 1;
+            }
+            Token::LineComment(chars[start..i].iter().collect::<String>().trim_end().to_string())
+        } else if c == '/' && chars.get(i + 1) == Some(&'*') {
+            i += 2;
+            while i < chars.len() && !(chars[i - 1] == '*' && chars[i] == '/' && i > start + 2) {
+                i += 1;
+            }
+            i = (i + 1).min(chars.len());
+            Token::BlockComment(chars[start..i].iter().collect())
+        } else if matches!(c, '\'' | '"' | '`') {
+            i = quoted_end(&chars, i, c);
+            Token::Literal(chars[start..i].iter().collect())
+        } else if matches!(c, 'E' | 'e' | 'N' | 'n' | 'X' | 'x' | 'B' | 'b') && chars.get(i + 1) == Some(&'\'') {
+            // E'...', N'...' and the like: the prefix is part of the string.
+            i = quoted_end(&chars, i + 1, '\'');
+            Token::Literal(chars[start..i].iter().collect())
+        } else if c == '$' {
+            i += 1;
+            while i < chars.len() && (chars[i].is_alphanumeric() || chars[i] == '_') {
+                i += 1;
+            }
+            if chars.get(i) == Some(&'$') {
+                // $tag$ ... $tag$
+                i += 1;
+                let tag: String = chars[start..i].iter().collect();
+                let mut body = String::new();
+                while i < chars.len() && !body.ends_with(&tag) {
+                    body.push(chars[i]);
+                    i += 1;
+                }
+            }
+            Token::Literal(chars[start..i].iter().collect())
+        } else if c.is_ascii_digit() {
+            while i < chars.len() && (chars[i].is_ascii_alphanumeric() || chars[i] == '.') {
+                i += 1;
+            }
+            Token::Literal(chars[start..i].iter().collect())
+        } else if c.is_alphabetic() || c == '_' {
+            while i < chars.len() && (chars[i].is_alphanumeric() || matches!(chars[i], '_' | '$')) {
+                i += 1;
+            }
+            Token::Word(chars[start..i].iter().collect())
+        } else {
+            i += 1;
+            match c {
+                '(' => Token::Open,
+                ')' => Token::Close,
+                ',' => Token::Comma,
+                ';' => Token::Semicolon,
+                '.' => Token::Tight(".".to_string()),
+                // Right after a value, `[` subscripts it or makes an array type.
+                '[' if start > 0 && (chars[start - 1].is_alphanumeric() || "_)]\"'".contains(chars[start - 1])) => {
+                    Token::Tight("[".to_string())
+                }
+                // Otherwise it quotes a SQL Server identifier, `]]` escaping `]`.
+                '[' => {
+                    i = quoted_end(&chars, start, ']');
+                    Token::Literal(chars[start..i].iter().collect())
+                }
+                ']' => Token::Tight("]".to_string()),
+                ':' if chars.get(i) == Some(&':') => {
+                    i += 1;
+                    Token::Tight("::".to_string())
+                }
+                '?' => Token::Literal("?".to_string()),
+                ':' if chars.get(i).is_some_and(|c| c.is_alphabetic()) => {
+                    while i < chars.len() && (chars[i].is_alphanumeric() || chars[i] == '_') {
+                        i += 1;
+                    }
+                    Token::Literal(chars[start..i].iter().collect())
+                }
+                _ => {
+                    while i < chars.len() && "<>=!~|&^%*/+-@#".contains(chars[i]) && !matches!(chars[i], '-' | '+') {
+                        i += 1;
+                    }
+                    Token::Operator(chars[start..i].iter().collect())
+                }
+            }
+        };
+        tokens.push(token);
+    }
+    tokens
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn puts_clauses_on_their_own_lines_and_indents_subqueries() {
+        let sql = "select a, b from t where x in (select id from u where y = 1 and z = 2) order by a";
+        assert_eq!(
+            format_sql(sql),
+            "SELECT\n    a,\n    b\nFROM t\nWHERE x IN (\n    SELECT\n        id\n    FROM u\n    WHERE y = 1\n        AND z = 2\n)\nORDER BY a"
+        );
+    }
+
+    #[test]
+    fn keeps_between_and_on_one_line() {
+        assert_eq!(
+            format_sql("select * from t where a between 1 and 5 and b = 2 or c = 3"),
+            "SELECT\n    *\nFROM t\nWHERE a BETWEEN 1 AND 5\n    AND b = 2\n    OR c = 3"
+        );
+    }
+
+    #[test]
+    fn keeps_comments() {
+        let formatted = format_sql("select a -- first, from\nfrom t /* where  x */ where b = 1");
+        assert!(formatted.contains("a -- first, from\n"));
+        assert!(formatted.contains("FROM t /* where  x */\nWHERE b = 1"));
+    }
+
+    #[test]
+    fn leaves_literals_and_quoted_identifiers_unchanged() {
+        let sql = "select 'It''s  FROM where', E'a\\'b  c', \"Mixed  Case\", `x  y`, [order  by] from t";
+        assert_eq!(
+            format_sql(sql),
+            "SELECT\n    'It''s  FROM where',\n    E'a\\'b  c',\n    \"Mixed  Case\",\n    `x  y`,\n    [order  by]\nFROM t"
+        );
+    }
+
+    #[test]
+    fn keeps_dollar_quoted_bodies_whole() {
+        let sql = "create function f() returns int as $body$ select  1; from $body$ language sql";
+        assert!(format_sql(sql).contains("$body$ select  1; from $body$"));
+        assert_eq!(format_sql("select $$a;b$$"), "SELECT\n    $$a;b$$");
+    }
+
+    #[test]
+    fn writes_casts_without_spaces() {
+        assert_eq!(format_sql("select a :: text, b::int[] from t"), "SELECT\n    a::text,\n    b::int[]\nFROM t");
+        assert_eq!(format_sql("select arr[1], ARRAY[1, 2] from t"), "SELECT\n    arr[1],\n    ARRAY[1, 2]\nFROM t");
+    }
+
+    #[test]
+    fn keeps_on_conflict_do_update_together() {
+        assert_eq!(
+            format_sql("insert into t (a, b) values (1, 2) on conflict (a) do update set b = excluded.b returning *"),
+            "INSERT INTO t (a, b)\nVALUES (1, 2)\nON CONFLICT (a) DO UPDATE SET b = excluded.b\nRETURNING *"
+        );
+    }
+
+    #[test]
+    fn formatting_twice_changes_nothing() {
+        for sql in [
+            "select a, count(*) from t left join u on t.id = u.id where a between 1 and 2 group by a having count(*) > 1",
+            "with x as (select 1 as n) select n from x union all select -1",
+            "select a -- note\n, b from t; delete from t where id = $1",
+            "update t set a = 'x  y', b = b::int + 1 where id in (select id from u)",
+        ] {
+            let once = format_sql(sql);
+            assert_eq!(format_sql(&once), once, "{sql}");
+        }
+    }
+}
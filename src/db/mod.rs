@@ -1,26 +1,77 @@
 mod connection;
+mod format;
+mod mssql;
+mod statement;
+mod tunnel;
 
 pub use connection::*;
+pub use format::{format_sql, SQL_KEYWORDS};
+pub use statement::*;
+pub use tunnel::SshTunnel;
+
+use std::time::Duration;
+
+use anyhow::Result;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum TableKind {
+    Table,
+    View,
+}
 
 #[derive(Clone, Debug)]
 pub struct TableInfo {
     pub name: String,
     pub schema: String,
+    pub kind: TableKind,
+    /// Filled in later by `get_row_counts`; an estimate except on SQLite.
+    pub row_count: Option<u64>,
+}
+
+#[derive(Clone, Debug)]
+pub struct ColumnInfo {
+    pub name: String,
+    pub data_type: String,
 }
 
 #[derive(Clone, Debug)]
 pub struct QueryResult {
     pub columns: Vec<String>,
-    pub rows: Vec<Vec<String>>,
+    /// SQL type name of each column as reported by the driver.
+    pub column_types: Vec<String>,
+    /// Cell values; `None` is SQL NULL, distinct from the text "NULL".
+    pub rows: Vec<Vec<Option<String>>>,
     pub affected_rows: u64,
+    /// The statement reported affected rows instead of returning a result set.
+    pub is_command: bool,
+    /// Statements run to produce this result; more than one for a script.
+    pub statements: usize,
+    /// Wall-clock time the query took, when it was timed.
+    pub elapsed: Option<Duration>,
+    /// Table the result was generated from via the sidebar, if any.
+    pub source_table: Option<TableInfo>,
+    /// Rows skipped before this page, when the result is paginated.
+    pub page_offset: Option<usize>,
 }
 
 impl QueryResult {
     pub fn empty() -> Self {
         Self {
             columns: vec![],
+            column_types: vec![],
             rows: vec![],
             affected_rows: 0,
+            is_command: false,
+            statements: 1,
+            elapsed: None,
+            source_table: None,
+            page_offset: None,
         }
     }
 }
+
+/// Receives rows one at a time from `DatabaseConnection::stream_query`.
+pub trait RowSink {
+    fn columns(&mut self, columns: &[String]) -> Result<()>;
+    fn row(&mut self, row: &[Option<String>]) -> Result<()>;
+}
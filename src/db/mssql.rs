@@ -0,0 +1,382 @@
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+use futures_util::TryStreamExt;
+use tiberius::{AuthMethod, Client, ColumnData, ColumnType, Config, EncryptionLevel, FromSql};
+use tokio::net::TcpStream;
+use tokio_util::compat::{Compat, TokioAsyncWriteCompatExt};
+
+use super::connection::with_context;
+use super::{first_keyword, returns_rows, ConnectSettings, ParamValue, QueryResult, RowSink};
+
+type MssqlClient = Client<Compat<TcpStream>>;
+
+/// Idle connections kept for reuse; more are opened while they're all busy.
+const MAX_IDLE: usize = 4;
+
+/// Connections to a SQL Server. tiberius has no pool of its own, so this
+/// opens connections on demand and keeps a few idle ones around. Cheap to
+/// clone.
+#[derive(Clone)]
+pub struct MssqlPool {
+    config: Config,
+    timeout: Duration,
+    idle: Arc<Mutex<Vec<MssqlClient>>>,
+}
+
+impl MssqlPool {
+    /// Accepts `mssql://` and `sqlserver://` URLs. `encrypt` (`true`,
+    /// `false`, `strict`) and `trust_server_certificate` control TLS;
+    /// `instance` names an instance other than the default one.
+    pub async fn connect(connection_string: &str, settings: &ConnectSettings) -> Result<Self> {
+        let pool = Self {
+            config: parse_config(connection_string, settings)?,
+            timeout: settings.connect_timeout,
+            idle: Arc::new(Mutex::new(Vec::new())),
+        };
+        let client = pool.open().await?;
+        pool.release(client);
+        Ok(pool)
+    }
+
+    async fn open(&self) -> Result<MssqlClient> {
+        let connect = async {
+            let mut config = self.config.clone();
+            loop {
+                let tcp = TcpStream::connect(config.get_addr()).await?;
+                tcp.set_nodelay(true)?;
+                match Client::connect(config.clone(), tcp.compat_write()).await {
+                    // Azure SQL sends clients on to the node holding the database.
+                    Err(tiberius::error::Error::Routing { host, port }) => {
+                        config.host(host);
+                        config.port(port);
+                    }
+                    result => return Ok::<_, anyhow::Error>(result?),
+                }
+            }
+        };
+        tokio::time::timeout(self.timeout, connect)
+            .await
+            .map_err(|_| anyhow::anyhow!("Timed out after {}s waiting for the server", self.timeout.as_secs()))?
+    }
+
+    /// A dedicated connection, e.g. for a transaction; give it back with
+    /// `release` once it is idle again.
+    pub async fn acquire(&self) -> Result<MssqlClient> {
+        let idle = self.idle.lock().map_err(|_| anyhow::anyhow!("Connection pool poisoned"))?.pop();
+        match idle {
+            Some(client) => Ok(client),
+            None => self.open().await,
+        }
+    }
+
+    pub fn release(&self, client: MssqlClient) {
+        if let Ok(mut idle) = self.idle.lock()
+            && idle.len() < MAX_IDLE
+        {
+            idle.push(client);
+        }
+    }
+
+    /// Runs `work` on a pooled connection. The connection is only reused
+    /// when it is still usable; one abandoned mid-query is dropped, which
+    /// also makes the server stop the query.
+    async fn with_client<T>(
+        &self,
+        work: impl AsyncFnOnce(&mut MssqlClient) -> Result<T>,
+    ) -> Result<T> {
+        let mut client = self.acquire().await?;
+        let result = work(&mut client).await;
+        if result.as_ref().is_ok() || result.as_ref().is_err_and(|e| !is_connection_lost(e)) {
+            self.release(client);
+        }
+        result
+    }
+
+    pub async fn execute_query(&self, query: &str) -> Result<QueryResult> {
+        self.with_client(async |client| run_statement(client, query, &[]).await).await
+    }
+
+    /// Runs a statement taking `@P1`, `@P2`...; None binds NULL.
+    pub async fn execute_with_params(&self, query: &str, params: &[Option<String>]) -> Result<QueryResult> {
+        self.with_client(async |client| run_statement(client, query, params).await).await
+    }
+
+    /// Rows of a catalog query taking `@P1`, `@P2`... as text parameters.
+    pub async fn fetch(&self, query: &str, params: &[&str]) -> Result<Vec<Vec<Option<String>>>> {
+        self.with_client(async |client| {
+            let params: Vec<&dyn tiberius::ToSql> = params.iter().map(|p| p as &dyn tiberius::ToSql).collect();
+            let rows = client.query(query, &params).await?.into_first_result().await?;
+            Ok(rows.iter().map(|row| row.cells().map(|(_, value)| extract_mssql_value(value)).collect()).collect())
+        })
+        .await
+    }
+
+    pub async fn stream_query(&self, query: &str, sink: &mut dyn RowSink) -> Result<u64> {
+        self.with_client(async |client| {
+            let mut rows = client.simple_query(query).await?.into_row_stream();
+            let mut count = 0u64;
+            while let Some(row) = rows.try_next().await? {
+                if count == 0 {
+                    let columns: Vec<String> = row.columns().iter().map(|c| c.name().to_string()).collect();
+                    sink.columns(&columns)?;
+                }
+                let values: Vec<Option<String>> = row.cells().map(|(_, value)| extract_mssql_value(value)).collect();
+                sink.row(&values)?;
+                count += 1;
+            }
+            Ok(count)
+        })
+        .await
+    }
+}
+
+/// An open transaction, holding its own connection so the sidebar's
+/// queries stay outside it. Dropping it closes the connection, and the
+/// server rolls the transaction back.
+pub struct MssqlTransaction {
+    pool: MssqlPool,
+    client: MssqlClient,
+}
+
+impl MssqlTransaction {
+    pub async fn begin(pool: &MssqlPool) -> Result<Self> {
+        let mut client = pool.acquire().await?;
+        client.simple_query("BEGIN TRANSACTION").await?.into_results().await?;
+        Ok(Self { pool: pool.clone(), client })
+    }
+
+    pub async fn execute_query(&mut self, query: &str) -> Result<QueryResult> {
+        run_statement(&mut self.client, query, &[]).await
+    }
+
+    pub async fn execute_with_params(&mut self, query: &str, params: &[Option<String>]) -> Result<QueryResult> {
+        run_statement(&mut self.client, query, params).await
+    }
+
+    pub async fn commit(self) -> Result<()> {
+        self.finish("COMMIT TRANSACTION").await
+    }
+
+    pub async fn rollback(self) -> Result<()> {
+        self.finish("ROLLBACK TRANSACTION").await
+    }
+
+    async fn finish(mut self, statement: &str) -> Result<()> {
+        self.client.simple_query(statement).await?.into_results().await?;
+        self.pool.release(self.client);
+        Ok(())
+    }
+}
+
+fn parse_config(connection_string: &str, settings: &ConnectSettings) -> Result<Config> {
+    let url = url::Url::parse(connection_string)?;
+    let decode = |s: &str| {
+        percent_encoding::percent_decode_str(s)
+            .decode_utf8()
+            .map(|s| s.into_owned())
+            .with_context(|| format!("Invalid percent-encoding in the connection string: {s}"))
+    };
+
+    let mut config = Config::new();
+    config.host(url.host_str().context("SQL Server connection string needs a host")?);
+    config.port(url.port().unwrap_or(1433));
+    config.application_name("crux");
+    let database = url.path().trim_start_matches('/');
+    if !database.is_empty() {
+        config.database(decode(database)?);
+    }
+    if !url.username().is_empty() {
+        config.authentication(AuthMethod::sql_server(
+            decode(url.username())?,
+            decode(url.password().unwrap_or_default())?,
+        ));
+    }
+
+    let mut trust = false;
+    let mut ca_in_url = false;
+    for (key, value) in url.query_pairs() {
+        match key.to_ascii_lowercase().as_str() {
+            "encrypt" => config.encryption(match value.to_ascii_lowercase().as_str() {
+                "true" | "yes" | "strict" | "mandatory" => EncryptionLevel::Required,
+                "false" | "no" | "optional" => EncryptionLevel::Off,
+                "off" | "disable" => EncryptionLevel::NotSupported,
+                other => anyhow::bail!("Unknown encrypt value `{other}`: use true, false or off"),
+            }),
+            "trust_server_certificate" | "trustservercertificate" => trust = value.eq_ignore_ascii_case("true"),
+            "sslrootcert" | "ssl-ca" => {
+                config.trust_cert_ca(value.as_ref());
+                ca_in_url = true;
+            }
+            "instance" => config.instance_name(value.as_ref()),
+            _ => {}
+        }
+    }
+    if trust {
+        config.trust_cert();
+    } else if !ca_in_url && let Some(ca) = &settings.ssl_ca {
+        config.trust_cert_ca(ca.display());
+    }
+    Ok(config)
+}
+
+/// Runs one statement: its first result set, or the number of affected
+/// rows for statements that don't return rows.
+async fn run_statement(client: &mut MssqlClient, query: &str, params: &[Option<String>]) -> Result<QueryResult> {
+    // Stored procedures may or may not return rows; ask the stream.
+    let is_procedure = matches!(first_keyword(query).as_str(), "EXEC" | "EXECUTE");
+    if !returns_rows(query) && !is_procedure && !has_output_clause(query) {
+        let result = bind(query, params).execute(client).await?;
+        return Ok(QueryResult {
+            affected_rows: result.total(),
+            is_command: true,
+            ..QueryResult::empty()
+        });
+    }
+
+    // Without parameters the batch runs as is, rather than through
+    // sp_executesql, so e.g. a `USE` lasts past it.
+    let mut stream = if params.is_empty() {
+        client.simple_query(query).await?
+    } else {
+        bind(query, params).query(client).await?
+    };
+    let column_types: Vec<String> = stream
+        .columns()
+        .await?
+        .map(|columns| columns.iter().map(|c| type_name(c.column_type()).to_string()).collect())
+        .unwrap_or_default();
+    let rows = stream.into_first_result().await?;
+    let Some(first) = rows.first() else {
+        return Ok(QueryResult {
+            is_command: column_types.is_empty(),
+            ..QueryResult::empty()
+        });
+    };
+    let columns: Vec<String> = first.columns().iter().map(|c| c.name().to_string()).collect();
+    let data: Vec<Vec<Option<String>>> = rows
+        .iter()
+        .map(|row| row.cells().map(|(_, value)| extract_mssql_value(value)).collect())
+        .collect();
+
+    Ok(QueryResult {
+        columns,
+        column_types,
+        rows: data,
+        affected_rows: rows.len() as u64,
+        ..QueryResult::empty()
+    })
+}
+
+/// The server converts parameters to the types a statement needs, so
+/// values are only sent as numbers when they look like one.
+fn bind<'a>(query: &'a str, params: &'a [Option<String>]) -> tiberius::Query<'a> {
+    let mut statement = tiberius::Query::new(query);
+    for value in params {
+        match ParamValue::infer(value.as_deref()) {
+            ParamValue::Null => statement.bind(None::<&str>),
+            ParamValue::Integer(n) => statement.bind(n),
+            ParamValue::Float(f) => statement.bind(f),
+            ParamValue::Text(text) => statement.bind(text),
+        }
+    }
+    statement
+}
+
+/// `INSERT`/`UPDATE`/`DELETE ... OUTPUT inserted.*` returns rows, like
+/// `RETURNING` elsewhere.
+fn has_output_clause(sql: &str) -> bool {
+    sql.split(|c: char| !c.is_ascii_alphanumeric() && c != '_')
+        .any(|word| word.eq_ignore_ascii_case("OUTPUT"))
+}
+
+/// The SQL Server name of a column type, as far as the wire protocol tells.
+fn type_name(column_type: ColumnType) -> &'static str {
+    match column_type {
+        ColumnType::Null => "NULL",
+        ColumnType::Bit | ColumnType::Bitn => "BIT",
+        ColumnType::Int1 => "TINYINT",
+        ColumnType::Int2 => "SMALLINT",
+        ColumnType::Int4 | ColumnType::Intn => "INT",
+        ColumnType::Int8 => "BIGINT",
+        ColumnType::Float4 => "REAL",
+        ColumnType::Float8 | ColumnType::Floatn => "FLOAT",
+        ColumnType::Money | ColumnType::Money4 => "MONEY",
+        ColumnType::Decimaln | ColumnType::Numericn => "DECIMAL",
+        ColumnType::Guid => "UNIQUEIDENTIFIER",
+        ColumnType::Datetime4 => "SMALLDATETIME",
+        ColumnType::Datetime | ColumnType::Datetimen => "DATETIME",
+        ColumnType::Datetime2 => "DATETIME2",
+        ColumnType::DatetimeOffsetn => "DATETIMEOFFSET",
+        ColumnType::Daten => "DATE",
+        ColumnType::Timen => "TIME",
+        ColumnType::BigVarChar => "VARCHAR",
+        ColumnType::BigChar => "CHAR",
+        ColumnType::NVarchar => "NVARCHAR",
+        ColumnType::NChar => "NCHAR",
+        ColumnType::Text => "TEXT",
+        ColumnType::NText => "NTEXT",
+        ColumnType::BigVarBin => "VARBINARY",
+        ColumnType::BigBinary => "BINARY",
+        ColumnType::Image => "IMAGE",
+        ColumnType::Xml => "XML",
+        ColumnType::Udt => "UDT",
+        ColumnType::SSVariant => "SQL_VARIANT",
+    }
+}
+
+pub(super) fn extract_mssql_value(value: &ColumnData<'static>) -> Option<String> {
+    use sqlx::types::chrono::{DateTime, FixedOffset, NaiveDate, NaiveDateTime, NaiveTime};
+
+    match value {
+        ColumnData::U8(v) => v.map(|v| v.to_string()),
+        ColumnData::I16(v) => v.map(|v| v.to_string()),
+        ColumnData::I32(v) => v.map(|v| v.to_string()),
+        ColumnData::I64(v) => v.map(|v| v.to_string()),
+        ColumnData::F32(v) => v.map(|v| v.to_string()),
+        ColumnData::F64(v) => v.map(|v| v.to_string()),
+        ColumnData::Bit(v) => v.map(|v| v.to_string()),
+        ColumnData::String(v) => v.as_ref().map(|v| v.to_string()),
+        ColumnData::Guid(v) => v.map(|v| v.to_string().to_uppercase()),
+        ColumnData::Binary(v) => v.as_ref().map(|v| format!("0x{}", hex::encode_upper(v))),
+        // Exact, never through f64.
+        ColumnData::Numeric(v) => v.map(|v| v.to_string()),
+        ColumnData::Xml(v) => v.as_ref().map(|v| v.to_string()),
+        ColumnData::DateTime(_) | ColumnData::SmallDateTime(_) | ColumnData::DateTime2(_) => {
+            NaiveDateTime::from_sql(value).ok().flatten().map(|v| v.to_string())
+        }
+        ColumnData::Date(_) => NaiveDate::from_sql(value).ok().flatten().map(|v| v.to_string()),
+        ColumnData::Time(_) => NaiveTime::from_sql(value).ok().flatten().map(|v| v.to_string()),
+        ColumnData::DateTimeOffset(_) => {
+            DateTime::<FixedOffset>::from_sql(value).ok().flatten().map(|v| v.to_string())
+        }
+    }
+}
+
+/// An error from the server as `describe_error` shows it: the message,
+/// then the line of the batch and the error number.
+pub(super) fn describe_error(err: &anyhow::Error) -> Option<String> {
+    let Some(tiberius::error::Error::Server(token)) = err.downcast_ref::<tiberius::error::Error>() else {
+        return None;
+    };
+    let mut text = token.message().to_string();
+    if token.line() > 0 {
+        text.push_str(&format!("\nLINE: {}", token.line()));
+    }
+    text.push_str(&format!("\nCODE: {}", token.code()));
+    Some(with_context::<tiberius::error::Error>(err, text))
+}
+
+/// Login failed (error 18456), e.g. a wrong password.
+pub fn is_auth_failure(err: &anyhow::Error) -> bool {
+    err.downcast_ref::<tiberius::error::Error>()
+        .is_some_and(|e| e.code() == Some(18456))
+}
+
+pub fn is_connection_lost(err: &anyhow::Error) -> bool {
+    matches!(
+        err.downcast_ref::<tiberius::error::Error>(),
+        Some(tiberius::error::Error::Io { .. } | tiberius::error::Error::Protocol(_) | tiberius::error::Error::Tls(_))
+    )
+}
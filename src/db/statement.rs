@@ -0,0 +1,763 @@
+/// First keyword of a statement, uppercased, skipping leading comments.
+pub fn first_keyword(sql: &str) -> String {
+    let mut rest = sql.trim_start();
+    loop {
+        if let Some(after) = rest.strip_prefix("--") {
+            rest = after.split_once('\n').map(|(_, r)| r).unwrap_or("").trim_start();
+        } else if let Some(after) = rest.strip_prefix("/*") {
+            rest = after.split_once("*/").map(|(_, r)| r).unwrap_or("").trim_start();
+        } else if let Some(after) = rest.strip_prefix('(') {
+            rest = after.trim_start();
+        } else {
+            break;
+        }
+    }
+    rest.split(|c: char| !c.is_ascii_alphabetic())
+        .next()
+        .unwrap_or("")
+        .to_ascii_uppercase()
+}
+
+/// Whether a statement only reads data and is safe to run again. This
+/// decides what read-only mode lets through, what needs confirming on
+/// production, and what is retried after a reconnect. `EXPLAIN ANALYZE`
+/// runs the statement it explains, so an `EXPLAIN` is judged by what it
+/// wraps.
+pub fn is_read_only(sql: &str) -> bool {
+    match first_keyword(sql).as_str() {
+        "SELECT" | "VALUES" | "TABLE" | "SHOW" | "DESCRIBE" | "DESC" => true,
+        "EXPLAIN" => is_read_only(&explained_statement(sql)),
+        "PRAGMA" => pragma_is_read_only(sql),
+        "WITH" => !modifies_data(sql),
+        _ => false,
+    }
+}
+
+/// SQLite pragmas that take an argument only to say what to look at.
+const INSPECTING_PRAGMAS: [&str; 10] = [
+    "table_info",
+    "table_xinfo",
+    "table_list",
+    "index_list",
+    "index_info",
+    "index_xinfo",
+    "foreign_key_list",
+    "foreign_key_check",
+    "integrity_check",
+    "quick_check",
+];
+
+/// SQLite pragmas that do something even without an argument.
+const ACTING_PRAGMAS: [&str; 4] = ["optimize", "shrink_memory", "incremental_vacuum", "wal_checkpoint"];
+
+/// Whether a `PRAGMA` only reads a setting or inspects the schema, rather
+/// than setting a value with `= v` or `(v)` or doing maintenance.
+fn pragma_is_read_only(sql: &str) -> bool {
+    let code = code_only(sql);
+    let rest = code.trim_start().get("PRAGMA".len()..).unwrap_or("").trim_start();
+    let end = rest
+        .find(|c: char| !c.is_ascii_alphanumeric() && c != '_' && c != '.')
+        .unwrap_or(rest.len());
+    // `main.user_version` names the pragma of one attached database.
+    let name = rest[..end].rsplit('.').next().unwrap_or("").to_ascii_lowercase();
+    match rest[end..].trim_start().chars().next() {
+        Some('=') => false,
+        Some('(') => INSPECTING_PRAGMAS.contains(&name.as_str()),
+        _ => !ACTING_PRAGMAS.contains(&name.as_str()),
+    }
+}
+
+/// Whether `keyword` appears as a whole word anywhere in the statement.
+fn contains_keyword(sql: &str, keyword: &str) -> bool {
+    sql.split(|c: char| !c.is_ascii_alphanumeric() && c != '_')
+        .any(|word| word.eq_ignore_ascii_case(keyword))
+}
+
+/// Whether a statement produces a result set rather than just a count of
+/// affected rows, e.g. `SELECT` or `DELETE ... RETURNING`.
+pub fn returns_rows(sql: &str) -> bool {
+    is_read_only(sql)
+        || matches!(first_keyword(sql).as_str(), "WITH" | "EXPLAIN" | "PRAGMA")
+        || contains_keyword(&code_only(sql), "RETURNING")
+}
+
+/// Whether `INSERT`, `UPDATE`, `DELETE` or `MERGE` appears outside quotes
+/// and comments, e.g. in a data-modifying `WITH`.
+fn modifies_data(sql: &str) -> bool {
+    code_only(sql)
+        .split(|c: char| !c.is_ascii_alphanumeric() && c != '_')
+        .any(|word| ["INSERT", "UPDATE", "DELETE", "MERGE"].iter().any(|kw| word.eq_ignore_ascii_case(kw)))
+}
+
+/// The statement with quoted text and comments blanked out.
+fn code_only(sql: &str) -> String {
+    let mut code = String::with_capacity(sql.len());
+    let mut chars = sql.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '\'' | '"' | '`' => {
+                // A doubled quote closes and reopens the literal.
+                while let Some(next) = chars.next() {
+                    if next == c {
+                        break;
+                    } else if next == '\\' && c == '\'' {
+                        chars.next();
+                    }
+                }
+                code.push(' ');
+            }
+            '-' if chars.peek() == Some(&'-') => {
+                chars.by_ref().find(|&next| next == '\n');
+                code.push('\n');
+            }
+            '/' if chars.peek() == Some(&'*') => {
+                chars.next();
+                let mut prev = ' ';
+                for next in chars.by_ref() {
+                    if prev == '*' && next == '/' {
+                        break;
+                    }
+                    prev = next;
+                }
+                code.push(' ');
+            }
+            _ => code.push(c),
+        }
+    }
+    code
+}
+
+/// The statement an `EXPLAIN` wraps, past its options, e.g. the `DELETE`
+/// of `EXPLAIN (ANALYZE, BUFFERS) DELETE ...` or of SQLite's
+/// `EXPLAIN QUERY PLAN DELETE ...`. Quotes and comments are blanked out.
+fn explained_statement(sql: &str) -> String {
+    let code = code_only(sql);
+    let mut rest = code.trim_start_matches(|c: char| c == '(' || c.is_whitespace());
+    rest = rest.get("EXPLAIN".len()..).unwrap_or("");
+    loop {
+        rest = rest.trim_start();
+        if let Some(after) = rest.strip_prefix('(') {
+            rest = after.split_once(')').map_or("", |(_, r)| r);
+            continue;
+        }
+        let end = rest.find(|c: char| !c.is_ascii_alphanumeric() && c != '_').unwrap_or(rest.len());
+        let word = rest[..end].to_ascii_uppercase();
+        match word.as_str() {
+            "ANALYZE" | "ANALYSE" | "VERBOSE" | "EXTENDED" | "PARTITIONS" | "QUERY" | "PLAN" => rest = &rest[end..],
+            // MySQL's `FORMAT = JSON`, `FORMAT=TREE`.
+            "FORMAT" => {
+                let value = rest[end..].trim_start().trim_start_matches('=').trim_start();
+                rest = value.split_once(char::is_whitespace).map_or("", |(_, r)| r);
+            }
+            _ => return rest.to_string(),
+        }
+    }
+}
+
+/// Why a statement is likely to destroy data, if it is: `DROP`, `TRUNCATE`,
+/// or a `DELETE`/`UPDATE` without a `WHERE` clause of its own, including
+/// one in a data-modifying `WITH`.
+pub fn destructive_reason(sql: &str) -> Option<&'static str> {
+    let filtered = || contains_keyword(&outer_code(sql), "WHERE");
+    let reason = match main_keyword(sql).as_str() {
+        "DROP" => Some("This statement drops a database object."),
+        "TRUNCATE" => Some("This statement removes every row of the table."),
+        "DELETE" if !filtered() => Some("DELETE without WHERE removes every row of the table."),
+        "UPDATE" if !filtered() => Some("UPDATE without WHERE changes every row of the table."),
+        _ => None,
+    };
+    reason.or_else(|| parenthesized(&code_only(sql)).iter().find_map(|inner| destructive_reason(inner)))
+}
+
+/// The text inside each outermost pair of parentheses.
+fn parenthesized(code: &str) -> Vec<String> {
+    let mut groups = Vec::new();
+    let mut depth = 0usize;
+    for c in code.chars() {
+        match c {
+            '(' if depth == 0 => {
+                depth = 1;
+                groups.push(String::new());
+            }
+            ')' if depth == 1 => depth = 0,
+            _ if depth > 0 => {
+                depth += usize::from(c == '(');
+                depth -= usize::from(c == ')');
+                if let Some(group) = groups.last_mut() {
+                    group.push(c);
+                }
+            }
+            _ => {}
+        }
+    }
+    groups
+}
+
+/// The statement's own verb: its first keyword, or for a `WITH` the one
+/// after the common table expressions.
+fn main_keyword(sql: &str) -> String {
+    let first = first_keyword(sql);
+    if first != "WITH" {
+        return first;
+    }
+    outer_code(sql)
+        .split(|c: char| !c.is_ascii_alphanumeric() && c != '_')
+        .map(str::to_ascii_uppercase)
+        .find(|word| matches!(word.as_str(), "SELECT" | "INSERT" | "UPDATE" | "DELETE" | "MERGE" | "VALUES" | "TABLE"))
+        .unwrap_or(first)
+}
+
+/// The statement outside quotes, comments and parentheses, so without its
+/// subqueries and common table expressions. Each outermost parenthesized
+/// group is left as an empty `()`.
+fn outer_code(sql: &str) -> String {
+    let mut depth = 0usize;
+    let mut outer = String::new();
+    for c in code_only(sql).chars() {
+        match c {
+            '(' => {
+                if depth == 0 {
+                    outer.push_str(" (");
+                }
+                depth += 1;
+            }
+            ')' if depth > 0 => {
+                depth -= 1;
+                if depth == 0 {
+                    outer.push_str(") ");
+                }
+            }
+            _ if depth == 0 => outer.push(c),
+            _ => {}
+        }
+    }
+    outer
+}
+
+/// How an engine limits the rows a query returns.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LimitSyntax {
+    /// `LIMIT n OFFSET m`.
+    Limit,
+    /// SQL Server's `OFFSET m ROWS FETCH NEXT n ROWS ONLY`, which is only
+    /// allowed after an `ORDER BY`.
+    OffsetFetch,
+}
+
+impl LimitSyntax {
+    /// The clause to put after a query; `ordered` when the query already
+    /// ends in an `ORDER BY`.
+    pub fn clause(self, limit: usize, offset: usize, ordered: bool) -> String {
+        match self {
+            Self::Limit if offset == 0 => format!("LIMIT {limit}"),
+            Self::Limit => format!("LIMIT {limit} OFFSET {offset}"),
+            Self::OffsetFetch => format!(
+                "{}OFFSET {offset} ROWS FETCH NEXT {limit} ROWS ONLY",
+                if ordered { "" } else { "ORDER BY (SELECT NULL) " }
+            ),
+        }
+    }
+
+    pub fn apply(self, query: &str, limit: usize, offset: usize) -> String {
+        let query = trim_statement_end(query);
+        format!("{query} {}", self.clause(limit, offset, has_outer_order_by(query)))
+    }
+}
+
+/// Whether the query itself ends in an `ORDER BY`, not just a subquery or
+/// window function inside it.
+fn has_outer_order_by(sql: &str) -> bool {
+    let outer = outer_code(sql);
+    let words: Vec<&str> = outer.split_whitespace().collect();
+    words
+        .windows(2)
+        .any(|pair| pair[0].eq_ignore_ascii_case("ORDER") && pair[1].eq_ignore_ascii_case("BY"))
+}
+
+/// Whether `LIMIT`/`OFFSET` can be appended to page through a query; not
+/// when it already limits its own rows.
+pub fn is_pageable(sql: &str) -> bool {
+    matches!(first_keyword(sql).as_str(), "SELECT" | "WITH" | "VALUES" | "TABLE")
+        && !limits_own_rows(sql)
+        && split_sql_statements(sql).len() == 1
+}
+
+/// Whether the query's own clauses, not a subquery's, limit its rows with
+/// `LIMIT`, `OFFSET`, `FETCH FIRST|NEXT` or `SELECT TOP`.
+fn limits_own_rows(sql: &str) -> bool {
+    let outer = outer_code(sql).to_ascii_uppercase().replace('(', " ( ");
+    let words: Vec<&str> = outer
+        .split(|c: char| !c.is_ascii_alphanumeric() && c != '_' && c != '(')
+        .filter(|word| !word.is_empty())
+        .collect();
+    words.iter().enumerate().any(|(i, &word)| match word {
+        "LIMIT" | "OFFSET" => true,
+        "FETCH" => matches!(words.get(i + 1), Some(&("FIRST" | "NEXT"))),
+        // `SELECT [DISTINCT] TOP 5` or `TOP (5)`, not a column named top.
+        "TOP" => {
+            let after_select = match i.checked_sub(1).map(|p| words[p]) {
+                Some("SELECT") => true,
+                Some("DISTINCT" | "ALL") => i >= 2 && words[i - 2] == "SELECT",
+                _ => false,
+            };
+            let count = |next: &&str| *next == "(" || next.starts_with(|c: char| c.is_ascii_digit());
+            after_select && words.get(i + 1).is_some_and(count)
+        }
+        _ => false,
+    })
+}
+
+/// The statement without the whitespace, `;` and comments after its last
+/// token, so a clause appended to it isn't swallowed by a `--` comment.
+pub fn trim_statement_end(sql: &str) -> &str {
+    let mut end = 0;
+    let mut chars = sql.char_indices().peekable();
+    while let Some((i, c)) = chars.next() {
+        match c {
+            '\'' | '"' | '`' => {
+                // A doubled quote closes and reopens the literal.
+                end = sql.len();
+                while let Some((j, next)) = chars.next() {
+                    if next == c {
+                        end = j + 1;
+                        break;
+                    } else if next == '\\' && c == '\'' {
+                        chars.next();
+                    }
+                }
+            }
+            '-' if chars.peek().is_some_and(|&(_, next)| next == '-') => {
+                chars.by_ref().find(|&(_, next)| next == '\n');
+            }
+            '/' if chars.peek().is_some_and(|&(_, next)| next == '*') => {
+                chars.next();
+                let mut prev = ' ';
+                for (_, next) in chars.by_ref() {
+                    if prev == '*' && next == '/' {
+                        break;
+                    }
+                    prev = next;
+                }
+            }
+            ';' => {}
+            _ if c.is_whitespace() => {}
+            _ => end = i + c.len_utf8(),
+        }
+    }
+    &sql[..end]
+}
+
+/// Splits a script into statements on `;`, ignoring semicolons inside
+/// quotes, comments and Postgres dollar-quoted bodies. Empty statements
+/// are dropped; the trailing `;` is not kept.
+pub fn split_sql_statements(text: &str) -> Vec<String> {
+    let mut statements = Vec::new();
+    let mut current = String::new();
+    let mut chars = text.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '\'' | '"' | '`' => {
+                current.push(c);
+                // A doubled quote is an escaped quote and keeps the literal open.
+                while let Some(next) = chars.next() {
+                    current.push(next);
+                    if next == c {
+                        if chars.peek() == Some(&c) {
+                            current.push(c);
+                            chars.next();
+                        } else {
+                            break;
+                        }
+                    } else if next == '\\'
+                        && c == '\''
+                        && let Some(escaped) = chars.next()
+                    {
+                        current.push(escaped);
+                    }
+                }
+            }
+            '-' if chars.peek() == Some(&'-') => {
+                current.push(c);
+                for next in chars.by_ref() {
+                    current.push(next);
+                    if next == '\n' {
+                        break;
+                    }
+                }
+            }
+            '/' if chars.peek() == Some(&'*') => {
+                current.push(c);
+                current.push(chars.next().unwrap_or('*'));
+                let mut prev = ' ';
+                for next in chars.by_ref() {
+                    current.push(next);
+                    if prev == '*' && next == '/' {
+                        break;
+                    }
+                    prev = next;
+                }
+            }
+            // Not after a word, where `$` is part of a name such as `a$b$`.
+            '$' if !current.ends_with(|p: char| p.is_alphanumeric() || matches!(p, '_' | '$' | '@')) => {
+                // $tag$ ... $tag$, where the tag may be empty.
+                current.push(c);
+                let mut tag = String::from("$");
+                while let Some(&next) = chars.peek() {
+                    if next == '$' {
+                        tag.push(next);
+                        current.push(next);
+                        chars.next();
+                        break;
+                    } else if next.is_alphanumeric() || next == '_' {
+                        tag.push(next);
+                        current.push(next);
+                        chars.next();
+                    } else {
+                        break;
+                    }
+                }
+                if tag.len() >= 2 && tag.ends_with('$') {
+                    let mut body = String::new();
+                    for next in chars.by_ref() {
+                        body.push(next);
+                        if body.ends_with(&tag) {
+                            break;
+                        }
+                    }
+                    current.push_str(&body);
+                }
+            }
+            ';' => {
+                if has_code(&current) {
+                    statements.push(current.trim().to_string());
+                }
+                current.clear();
+            }
+            _ => current.push(c),
+        }
+    }
+    if has_code(&current) {
+        statements.push(current.trim().to_string());
+    }
+    statements
+}
+
+/// Whether a fragment holds anything besides whitespace and comments.
+fn has_code(sql: &str) -> bool {
+    let mut rest = sql.trim_start();
+    loop {
+        if let Some(after) = rest.strip_prefix("--") {
+            rest = after.split_once('\n').map(|(_, r)| r).unwrap_or("").trim_start();
+        } else if let Some(after) = rest.strip_prefix("/*") {
+            rest = after.split_once("*/").map(|(_, r)| r).unwrap_or("").trim_start();
+        } else {
+            return !rest.is_empty();
+        }
+    }
+}
+
+/// Wraps a query as `WITH q AS (...)` followed by `outer`, which selects
+/// from `q`. Only plain queries can be wrapped; returns None otherwise.
+pub fn wrap_in_cte(sql: &str, outer: &str) -> Option<String> {
+    if !matches!(first_keyword(sql).as_str(), "SELECT" | "WITH" | "VALUES" | "TABLE")
+        || split_sql_statements(sql).len() > 1
+    {
+        return None;
+    }
+    let inner = sql.trim().trim_end_matches(|c: char| c == ';' || c.is_whitespace());
+    Some(format!("WITH q AS (\n{}\n) {}", inner, outer))
+}
+
+/// A table named after `FROM`, `JOIN`, `UPDATE` or `INTO`, with its alias.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TableReference {
+    pub schema: Option<String>,
+    pub table: String,
+    pub alias: Option<String>,
+}
+
+/// Tables a query reads or writes, found by looking at the word after
+/// `FROM`, `JOIN`, `UPDATE` and `INTO`. Quotes are stripped.
+pub fn table_references(sql: &str) -> Vec<TableReference> {
+    let words: Vec<&str> = sql
+        .split(|c: char| c.is_whitespace() || matches!(c, ',' | '(' | ')' | ';'))
+        .filter(|w| !w.is_empty())
+        .collect();
+    let unquote = |w: &str| w.trim_matches(|c| matches!(c, '"' | '`' | '[' | ']')).to_string();
+    let is_keyword = |w: &str| super::SQL_KEYWORDS.contains(&w.to_ascii_uppercase().as_str());
+
+    let mut references = Vec::new();
+    for (i, word) in words.iter().enumerate() {
+        if !matches!(word.to_ascii_uppercase().as_str(), "FROM" | "JOIN" | "UPDATE" | "INTO") {
+            continue;
+        }
+        let Some(name) = words.get(i + 1).filter(|w| !is_keyword(w)) else {
+            continue;
+        };
+        let (schema, table) = match name.rsplit_once('.') {
+            Some((schema, table)) => (Some(unquote(schema)), unquote(table)),
+            None => (None, unquote(name)),
+        };
+        let mut alias = words.get(i + 2).copied();
+        if alias.is_some_and(|w| w.eq_ignore_ascii_case("AS")) {
+            alias = words.get(i + 3).copied();
+        }
+        references.push(TableReference {
+            schema,
+            table,
+            alias: alias.filter(|w| !is_keyword(w)).map(unquote),
+        });
+    }
+    references
+}
+
+/// How an engine marks bind parameters in a statement.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ParamStyle {
+    /// Postgres' numbered `$1`, `$2`...
+    Dollar,
+    /// MySQL's and SQLite's positional `?`.
+    Question,
+    /// SQL Server's `@P1`, `@P2`...
+    AtP,
+}
+
+impl ParamStyle {
+    /// How parameter `n`, counting from 1, is shown in the form.
+    pub fn label(self, n: usize) -> String {
+        match self {
+            Self::Dollar => format!("${n}"),
+            Self::Question => format!("?  #{n}"),
+            Self::AtP => format!("@P{n}"),
+        }
+    }
+}
+
+/// Number of bind parameters a statement takes: the highest `$n` or `@Pn`,
+/// or how many `?` it has. Quoted text, comments and dollar-quoted bodies
+/// are skipped.
+pub fn param_count(sql: &str, style: ParamStyle) -> usize {
+    let chars: Vec<char> = sql.chars().collect();
+    let is_word = |i: usize| chars.get(i).is_some_and(|c| c.is_alphanumeric() || matches!(c, '_' | '$' | '@'));
+    let number_at = |start: usize| {
+        let end = (start..chars.len()).find(|&i| !chars[i].is_ascii_digit()).unwrap_or(chars.len());
+        let number = chars[start..end].iter().collect::<String>().parse::<usize>().ok();
+        (end, number.filter(|_| !is_word(end)))
+    };
+    let find = |from: usize, pattern: &[char]| {
+        (from..chars.len())
+            .find(|&i| chars[i..].starts_with(pattern))
+            .map_or(chars.len(), |i| i + pattern.len())
+    };
+
+    let mut count = 0;
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        let next = chars.get(i + 1).copied();
+        i = match c {
+            // A doubled quote closes and reopens the literal, which skips it.
+            '\'' | '"' | '`' => {
+                let mut end = i + 1;
+                while end < chars.len() && chars[end] != c {
+                    end += if chars[end] == '\\' && c == '\'' { 2 } else { 1 };
+                }
+                end + 1
+            }
+            '[' if style == ParamStyle::AtP => find(i + 1, &[']']),
+            '-' if next == Some('-') => find(i + 2, &['\n']),
+            '/' if next == Some('*') => find(i + 2, &['*', '/']),
+            '$' if style == ParamStyle::Dollar && next.is_some_and(|n| n.is_ascii_digit()) => {
+                let (end, number) = number_at(i + 1);
+                if !(i > 0 && is_word(i - 1)) {
+                    count = count.max(number.unwrap_or(0));
+                }
+                end
+            }
+            '$' if style == ParamStyle::Dollar && !(i > 0 && is_word(i - 1)) => {
+                // $tag$ ... $tag$, where the tag may be empty.
+                let tag_end = (i + 1..chars.len()).find(|&j| !(chars[j].is_alphanumeric() || chars[j] == '_'));
+                match tag_end {
+                    Some(end) if chars[end] == '$' => find(end + 1, &chars[i..=end]),
+                    _ => i + 1,
+                }
+            }
+            '?' if style == ParamStyle::Question => {
+                count += 1;
+                i + 1
+            }
+            '@' if style == ParamStyle::AtP
+                && matches!(next, Some('P' | 'p'))
+                && !(i > 0 && is_word(i - 1))
+                && chars.get(i + 2).is_some_and(|n| n.is_ascii_digit()) =>
+            {
+                let (end, number) = number_at(i + 2);
+                count = count.max(number.unwrap_or(0));
+                end
+            }
+            _ => i + 1,
+        };
+    }
+    count
+}
+
+/// A parameter value as bound on engines that don't report parameter
+/// types: whole numbers and decimals as numbers, anything else as text.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ParamValue<'a> {
+    Null,
+    Integer(i64),
+    Float(f64),
+    Text(&'a str),
+}
+
+impl<'a> ParamValue<'a> {
+    pub fn infer(value: Option<&'a str>) -> Self {
+        let Some(value) = value else {
+            return Self::Null;
+        };
+        // Leading zeros or a plus sign mean it was meant as text, e.g. a code.
+        if let Ok(n) = value.parse::<i64>()
+            && n.to_string() == value
+        {
+            return Self::Integer(n);
+        }
+        if value.contains('.')
+            && value.chars().all(|c| c.is_ascii_digit() || matches!(c, '.' | '-'))
+            && let Ok(f) = value.parse::<f64>()
+        {
+            return Self::Float(f);
+        }
+        Self::Text(value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn judges_explain_by_what_it_runs() {
+        assert!(is_read_only("EXPLAIN SELECT * FROM t"));
+        assert!(is_read_only("EXPLAIN (ANALYZE, BUFFERS) SELECT 1"));
+        assert!(is_read_only("EXPLAIN FORMAT=JSON SELECT 1"));
+        assert!(is_read_only("EXPLAIN QUERY PLAN SELECT 1"));
+        assert!(!is_read_only("EXPLAIN ANALYZE DELETE FROM t"));
+        assert!(!is_read_only("explain analyze verbose update t set a = 1"));
+        assert!(!is_read_only("EXPLAIN (ANALYZE) INSERT INTO t VALUES (1)"));
+        assert!(!is_read_only("EXPLAIN ANALYZE WITH d AS (DELETE FROM t RETURNING *) SELECT * FROM d"));
+        assert!(returns_rows("EXPLAIN ANALYZE DELETE FROM t"));
+    }
+
+    #[test]
+    fn pragmas_that_set_or_act_are_writes() {
+        assert!(is_read_only("PRAGMA user_version"));
+        assert!(is_read_only("PRAGMA main.table_info(users)"));
+        assert!(is_read_only("pragma integrity_check"));
+        assert!(!is_read_only("PRAGMA x = 1"));
+        assert!(!is_read_only("PRAGMA main.user_version = 5"));
+        assert!(!is_read_only("PRAGMA journal_mode(WAL)"));
+        assert!(!is_read_only("PRAGMA optimize"));
+    }
+
+    #[test]
+    fn rejects_writes() {
+        assert!(is_read_only("WITH x AS (SELECT 1) SELECT * FROM x"));
+        assert!(!is_read_only("WITH x AS (SELECT 1) DELETE FROM t"));
+        assert!(!is_read_only("UPDATE t SET a = 1"));
+        assert!(!is_read_only("CREATE TABLE t (a int)"));
+    }
+
+    #[test]
+    fn flags_delete_and_update_without_their_own_where() {
+        assert!(destructive_reason("UPDATE t SET note = 'where'").is_some());
+        assert!(destructive_reason("DELETE FROM t -- where").is_some());
+        assert!(destructive_reason("DELETE FROM t /* WHERE id = 1 */").is_some());
+        assert!(destructive_reason("WITH x AS (SELECT 1 WHERE true) DELETE FROM t").is_some());
+        assert!(destructive_reason("WITH RECURSIVE x (n) AS (SELECT 1) UPDATE t SET a = 1").is_some());
+        assert!(destructive_reason("UPDATE t SET a = (SELECT b FROM u WHERE u.id = 1)").is_some());
+
+        assert!(destructive_reason("DELETE FROM t WHERE id = 1").is_none());
+        assert!(destructive_reason("WITH x AS (SELECT 1) DELETE FROM t WHERE id IN (SELECT * FROM x)").is_none());
+        assert!(destructive_reason("WITH d AS (DELETE FROM t RETURNING *) SELECT * FROM d").is_some());
+        assert!(destructive_reason("WITH d AS (DELETE FROM t WHERE a RETURNING *) SELECT * FROM d").is_none());
+        assert!(destructive_reason("SELECT 'drop'").is_none());
+    }
+
+    #[test]
+    fn appends_limits_after_trailing_comments() {
+        assert_eq!(LimitSyntax::Limit.apply("SELECT * FROM t -- note", 10, 20), "SELECT * FROM t LIMIT 10 OFFSET 20");
+        assert_eq!(LimitSyntax::Limit.apply("SELECT 1;\n/* done */ ", 10, 0), "SELECT 1 LIMIT 10");
+        assert_eq!(LimitSyntax::Limit.apply("SELECT '-- kept' -- dropped", 10, 0), "SELECT '-- kept' LIMIT 10");
+        assert_eq!(
+            LimitSyntax::OffsetFetch.apply("SELECT a FROM t ORDER BY a -- note", 5, 0),
+            "SELECT a FROM t ORDER BY a OFFSET 0 ROWS FETCH NEXT 5 ROWS ONLY"
+        );
+    }
+
+    #[test]
+    fn pages_unless_the_query_limits_itself() {
+        assert!(is_pageable("SELECT top, \"limit\" FROM t WHERE note = 'limit 5' -- fetch"));
+        assert!(!is_pageable("SELECT * FROM t LIMIT 5"));
+        assert!(!is_pageable("SELECT TOP 5 * FROM t"));
+        assert!(!is_pageable("SELECT * FROM t OFFSET 0 ROWS FETCH NEXT 5 ROWS ONLY"));
+        assert!(!is_pageable("SELECT DISTINCT TOP (5) a FROM t"));
+        assert!(is_pageable("SELECT * FROM (SELECT * FROM t LIMIT 5) s"));
+    }
+
+    #[test]
+    fn returns_rows_only_for_a_real_returning_clause() {
+        assert!(returns_rows("DELETE FROM t WHERE id = 1 RETURNING *"));
+        assert!(returns_rows("INSERT INTO t VALUES (1) returning id"));
+        assert!(!returns_rows("INSERT INTO t VALUES ('returning')"));
+        assert!(!returns_rows("UPDATE t SET a = 1 WHERE id = 1 -- returning"));
+        assert!(!returns_rows("INSERT INTO t (\"returning\") VALUES (1)"));
+    }
+
+    #[test]
+    fn splits_on_semicolons_and_drops_empty_statements() {
+        assert_eq!(split_sql_statements("SELECT 1; SELECT 2;"), ["SELECT 1", "SELECT 2"]);
+        assert_eq!(split_sql_statements("SELECT 1;;\n ; "), ["SELECT 1"]);
+        assert!(split_sql_statements("  ;  ").is_empty());
+    }
+
+    #[test]
+    fn keeps_semicolons_inside_quotes() {
+        assert_eq!(
+            split_sql_statements("SELECT 'a;b', \"c;d\", `e;f`; SELECT 2"),
+            ["SELECT 'a;b', \"c;d\", `e;f`", "SELECT 2"]
+        );
+        assert_eq!(split_sql_statements("SELECT 'it''s; fine'; SELECT 2"), ["SELECT 'it''s; fine'", "SELECT 2"]);
+        assert_eq!(split_sql_statements(r"SELECT 'a\';b'; SELECT 2"), [r"SELECT 'a\';b'", "SELECT 2"]);
+    }
+
+    #[test]
+    fn keeps_semicolons_inside_comments() {
+        assert_eq!(
+            split_sql_statements("SELECT 1 -- one; two\n; SELECT /* ; */ 2"),
+            ["SELECT 1 -- one; two", "SELECT /* ; */ 2"]
+        );
+        assert_eq!(split_sql_statements("SELECT 1; -- trailing comment"), ["SELECT 1"]);
+        assert_eq!(split_sql_statements("SELECT 1; /* done */"), ["SELECT 1"]);
+    }
+
+    #[test]
+    fn keeps_dollar_quoted_bodies_whole() {
+        let function = "CREATE FUNCTION f() RETURNS int AS $$ BEGIN RETURN 1; END; $$ LANGUAGE plpgsql";
+        assert_eq!(split_sql_statements(&format!("{}; SELECT f()", function)), [function, "SELECT f()"]);
+
+        let tagged = "DO $body$ BEGIN PERFORM 1; END $body$";
+        assert_eq!(split_sql_statements(&format!("{};SELECT 2", tagged)), [tagged, "SELECT 2"]);
+        assert_eq!(split_sql_statements("SELECT $1; SELECT $2"), ["SELECT $1", "SELECT $2"]);
+    }
+
+    #[test]
+    fn dollar_signs_inside_names_do_not_open_quotes() {
+        assert_eq!(split_sql_statements("SELECT a$b$ FROM t; SELECT 2"), ["SELECT a$b$ FROM t", "SELECT 2"]);
+        assert_eq!(
+            split_sql_statements("SELECT x$1 FROM t; SELECT $q$;$q$"),
+            ["SELECT x$1 FROM t", "SELECT $q$;$q$"]
+        );
+    }
+}
@@ -0,0 +1,436 @@
+use std::collections::VecDeque;
+use std::io::{ErrorKind, Read, Write};
+use std::net::{TcpListener, TcpStream, ToSocketAddrs};
+use std::path::Path;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
+
+use anyhow::{Context, Result, bail};
+use polling::{Event, Events, PollMode, Poller};
+use ssh2::{BlockDirections, Channel, CheckResult, KnownHostFileKind, Session};
+
+use crate::config::expand_home;
+
+/// How long to wait for the bastion to answer and each SSH request to finish.
+const TUNNEL_TIMEOUT: Duration = Duration::from_secs(15);
+
+/// Keys tried after the agent when the connection string names none.
+const DEFAULT_KEYS: [&str; 3] = ["~/.ssh/id_ed25519", "~/.ssh/id_ecdsa", "~/.ssh/id_rsa"];
+
+/// How often the idle session sends a keepalive to the bastion.
+const KEEPALIVE_INTERVAL: Duration = Duration::from_secs(30);
+
+/// How long the relay rests after a wakeup that let nothing move, e.g. while
+/// a client isn't reading what the bastion keeps offering.
+const STALL_BACKOFF: Duration = Duration::from_millis(20);
+
+/// A local port forward through a bastion host, run in-process over libssh2.
+///
+/// Requested with query parameters on the connection string, e.g.
+/// `postgres://u:p@db.internal/app?ssh=me@bastion:22&ssh_key=~/.ssh/id_ed25519`.
+/// The listener stays bound for the tunnel's lifetime, and the relay thread
+/// stops when the tunnel is dropped.
+pub struct SshTunnel {
+    stop: Arc<AtomicBool>,
+    poller: Arc<Poller>,
+    connection_string: String,
+}
+
+impl SshTunnel {
+    /// Starts a tunnel if the connection string asks for one.
+    pub async fn open(connection_string: &str) -> Result<Option<Self>> {
+        let Ok(mut url) = url::Url::parse(connection_string) else {
+            return Ok(None);
+        };
+
+        let mut ssh_target = None;
+        let mut ssh_key = None;
+        let mut params = Vec::new();
+        for (key, value) in url.query_pairs() {
+            match key.as_ref() {
+                "ssh" => ssh_target = Some(value.into_owned()),
+                "ssh_key" => ssh_key = Some(value.into_owned()),
+                _ => params.push((key.into_owned(), value.into_owned())),
+            }
+        }
+        let Some(ssh_target) = ssh_target else {
+            return Ok(None);
+        };
+
+        let db_host = url
+            .host_str()
+            .context("SSH tunnel needs a database host")?
+            .to_string();
+        let db_port = match url.port() {
+            Some(port) => port,
+            None => match url.scheme() {
+                "mysql" => 3306,
+                "mssql" | "sqlserver" => 1433,
+                _ => 5432,
+            },
+        };
+
+        let (login, ssh_port) = match ssh_target.rsplit_once(':') {
+            Some((login, port)) if let Ok(port) = port.parse::<u16>() => (login.to_string(), port),
+            _ => (ssh_target.clone(), 22),
+        };
+        let (user, ssh_host) = match login.rsplit_once('@') {
+            Some((user, host)) => (user.to_string(), host.to_string()),
+            None => (
+                std::env::var("USER").context("SSH tunnel needs a user, e.g. ssh=me@bastion")?,
+                login,
+            ),
+        };
+        let key = ssh_key.map(|key| expand_home(&key));
+
+        let (session, ssh_socket, listener) = tokio::task::spawn_blocking(move || -> Result<_> {
+            let (session, ssh_socket) = connect(&ssh_host, ssh_port, &user, key.as_deref())?;
+            let listener = TcpListener::bind("127.0.0.1:0")?;
+            Ok((session, ssh_socket, listener))
+        })
+        .await??;
+        let local_port = listener.local_addr()?.port();
+
+        let stop = Arc::new(AtomicBool::new(false));
+        let poller = Arc::new(Poller::new()?);
+        let relay = Relay::new(session, ssh_socket, listener, poller.clone(), db_host, db_port)?;
+        std::thread::spawn({
+            let stop = stop.clone();
+            move || relay.run(&stop)
+        });
+
+        url.set_host(Some("127.0.0.1"))?;
+        url.set_port(Some(local_port))
+            .map_err(|_| anyhow::anyhow!("Connection string cannot carry a port"))?;
+        if params.is_empty() {
+            url.set_query(None);
+        } else {
+            url.query_pairs_mut().clear().extend_pairs(params);
+        }
+
+        Ok(Some(Self {
+            stop,
+            poller,
+            connection_string: url.to_string(),
+        }))
+    }
+
+    /// The original connection string pointed at the local end of the tunnel.
+    pub fn connection_string(&self) -> &str {
+        &self.connection_string
+    }
+}
+
+impl Drop for SshTunnel {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+        let _ = self.poller.notify();
+    }
+}
+
+/// Opens an authenticated session to the bastion. Also returns a handle to
+/// its socket, for waiting on.
+fn connect(host: &str, port: u16, user: &str, key: Option<&Path>) -> Result<(Session, TcpStream)> {
+    let addr = (host, port)
+        .to_socket_addrs()
+        .with_context(|| format!("Cannot resolve SSH host {}", host))?
+        .next()
+        .with_context(|| format!("Cannot resolve SSH host {}", host))?;
+    let tcp = TcpStream::connect_timeout(&addr, TUNNEL_TIMEOUT).with_context(|| format!("Cannot reach SSH host {}", host))?;
+
+    let ssh_socket = tcp.try_clone()?;
+    let mut session = Session::new()?;
+    session.set_timeout(TUNNEL_TIMEOUT.as_millis() as u32);
+    session.set_tcp_stream(tcp);
+    session.handshake().context("SSH handshake failed")?;
+    verify_host_key(&session, host, port)?;
+    authenticate(&session, user, key)?;
+    session.set_keepalive(true, KEEPALIVE_INTERVAL.as_secs() as u32);
+    Ok((session, ssh_socket))
+}
+
+/// Accepts the bastion only if `~/.ssh/known_hosts` already lists its key,
+/// since crux has nowhere to ask whether to trust a new one.
+fn verify_host_key(session: &Session, host: &str, port: u16) -> Result<()> {
+    let (key, _) = session.host_key().context("SSH server sent no host key")?;
+    let mut known_hosts = session.known_hosts()?;
+    let file = expand_home("~/.ssh/known_hosts");
+    if file.exists() {
+        known_hosts.read_file(&file, KnownHostFileKind::OpenSSH)?;
+    }
+    match known_hosts.check_port(host, port, key) {
+        CheckResult::Match => Ok(()),
+        CheckResult::Mismatch => bail!("Host key for {} does not match ~/.ssh/known_hosts", host),
+        CheckResult::NotFound | CheckResult::Failure => {
+            bail!("Host key for {} is not in ~/.ssh/known_hosts; connect once with ssh to add it", host)
+        }
+    }
+}
+
+/// Logs in with the given key, or else with ssh-agent and the usual keys.
+fn authenticate(session: &Session, user: &str, key: Option<&Path>) -> Result<()> {
+    if let Some(key) = key {
+        return session
+            .userauth_pubkey_file(user, None, key, None)
+            .with_context(|| format!("SSH key {} was not accepted for {}", key.display(), user));
+    }
+    let _ = session.userauth_agent(user);
+    for key in DEFAULT_KEYS {
+        let path = expand_home(key);
+        if session.authenticated() {
+            break;
+        }
+        if path.exists() {
+            let _ = session.userauth_pubkey_file(user, None, &path, None);
+        }
+    }
+    if !session.authenticated() {
+        bail!("SSH login as {} failed: no key was accepted (set ssh_key or add it to ssh-agent)", user);
+    }
+    Ok(())
+}
+
+/// Forwards each connection to the listener over its own channel. libssh2
+/// serializes a session's channels behind one lock, so everything runs on
+/// one thread without blocking, and sleeps in `Poller::wait` until a socket
+/// is ready, a keepalive is due or the tunnel is dropped.
+struct Relay {
+    session: Session,
+    ssh_socket: TcpStream,
+    listener: TcpListener,
+    poller: Arc<Poller>,
+    db_host: String,
+    db_port: u16,
+    /// Accepted connections waiting for their channel, oldest first. libssh2
+    /// opens one channel at a time.
+    opening: VecDeque<(TcpStream, Instant)>,
+    forwards: Vec<Forward>,
+}
+
+impl Relay {
+    fn new(
+        session: Session,
+        ssh_socket: TcpStream,
+        listener: TcpListener,
+        poller: Arc<Poller>,
+        db_host: String,
+        db_port: u16,
+    ) -> Result<Self> {
+        listener.set_nonblocking(true)?;
+        session.set_blocking(false);
+        // Level-triggered, since libssh2 may leave bytes unread on its socket.
+        // Every source is deleted again in `Drop` before it is closed.
+        unsafe {
+            poller.add_with_mode(&listener, Event::readable(0), PollMode::Level)?;
+            poller.add_with_mode(&ssh_socket, Event::none(0), PollMode::Level)?;
+        }
+        Ok(Self {
+            session,
+            ssh_socket,
+            listener,
+            poller,
+            db_host,
+            db_port,
+            opening: VecDeque::new(),
+            forwards: Vec::new(),
+        })
+    }
+
+    fn run(mut self, stop: &AtomicBool) {
+        let mut events = Events::new();
+        while !stop.load(Ordering::Relaxed) {
+            let moved = self.accept() | self.open_channels() | self.pump();
+            let keepalive = match self.session.keepalive_send() {
+                Ok(seconds) => Duration::from_secs(u64::from(seconds.max(1))),
+                Err(_) => Duration::from_secs(1),
+            };
+            // Queued connections must notice their deadline passing.
+            let timeout = if self.opening.is_empty() { keepalive } else { keepalive.min(Duration::from_secs(1)) };
+            if self.watch().is_err() {
+                break;
+            }
+            events.clear();
+            match self.poller.wait(&mut events, Some(timeout)) {
+                Ok(ready) if ready > 0 && !moved => std::thread::sleep(STALL_BACKOFF),
+                Ok(_) => {}
+                Err(e) if e.kind() == ErrorKind::Interrupted => {}
+                Err(_) => break,
+            }
+        }
+    }
+
+    /// Queues every waiting connection for a channel.
+    fn accept(&mut self) -> bool {
+        let mut accepted = false;
+        while let Ok((socket, _)) = self.listener.accept() {
+            if socket.set_nonblocking(true).is_ok() {
+                self.opening.push_back((socket, Instant::now()));
+                accepted = true;
+            }
+        }
+        accepted
+    }
+
+    /// Opens channels for the queued connections without waiting on the
+    /// bastion. A connection is dropped, which its client sees as refused,
+    /// when its channel fails or takes too long.
+    fn open_channels(&mut self) -> bool {
+        let mut moved = false;
+        while let Some((_, queued)) = self.opening.front() {
+            let queued = *queued;
+            let opened = self.session.channel_direct_tcpip(&self.db_host, self.db_port, None);
+            match opened.map_err(std::io::Error::from) {
+                Ok(channel) => {
+                    let Some((socket, _)) = self.opening.pop_front() else {
+                        break;
+                    };
+                    // Deleted again when the forward ends.
+                    let added = unsafe { self.poller.add_with_mode(&socket, Event::none(0), PollMode::Level) };
+                    if added.is_ok() {
+                        self.forwards.push(Forward::new(socket, channel));
+                    }
+                }
+                Err(e) if e.kind() == ErrorKind::WouldBlock && queued.elapsed() < TUNNEL_TIMEOUT => break,
+                Err(_) => {
+                    self.opening.pop_front();
+                }
+            }
+            moved = true;
+        }
+        moved
+    }
+
+    /// Relays until no forward can move any more data.
+    fn pump(&mut self) -> bool {
+        let mut moved = false;
+        loop {
+            let mut progress = false;
+            let poller = &self.poller;
+            self.forwards.retain_mut(|forward| {
+                let open = match forward.pump() {
+                    Ok(moved) => {
+                        progress |= moved;
+                        !forward.is_closed()
+                    }
+                    Err(_) => false,
+                };
+                if !open {
+                    let _ = poller.delete(&forward.socket);
+                }
+                open
+            });
+            if !progress {
+                return moved;
+            }
+            moved = true;
+        }
+    }
+
+    /// Waits on each socket only in the directions something is waiting for.
+    fn watch(&self) -> std::io::Result<()> {
+        for forward in &self.forwards {
+            self.poller.modify_with_mode(&forward.socket, forward.interest(), PollMode::Level)?;
+        }
+        // Unread bastion data only matters while something is relayed;
+        // otherwise, e.g. keepalive replies, it waits for the next channel.
+        let active = !self.forwards.is_empty() || !self.opening.is_empty();
+        let (inbound, outbound) = match self.session.block_directions() {
+            BlockDirections::None => (false, false),
+            BlockDirections::Inbound => (true, false),
+            BlockDirections::Outbound => (false, true),
+            BlockDirections::Both => (true, true),
+        };
+        self.poller
+            .modify_with_mode(&self.ssh_socket, Event::new(0, active || inbound, outbound), PollMode::Level)
+    }
+}
+
+impl Drop for Relay {
+    fn drop(&mut self) {
+        let _ = self.poller.delete(&self.listener);
+        let _ = self.poller.delete(&self.ssh_socket);
+        for forward in &self.forwards {
+            let _ = self.poller.delete(&forward.socket);
+        }
+    }
+}
+
+/// One local connection and the channel it is relayed over, with whatever
+/// the other side was not ready to take yet.
+struct Forward {
+    socket: TcpStream,
+    channel: Channel,
+    upstream: Vec<u8>,
+    downstream: Vec<u8>,
+    closed: bool,
+}
+
+impl Forward {
+    fn new(socket: TcpStream, channel: Channel) -> Self {
+        Self {
+            socket,
+            channel,
+            upstream: Vec::new(),
+            downstream: Vec::new(),
+            closed: false,
+        }
+    }
+
+    fn is_closed(&self) -> bool {
+        self.closed
+    }
+
+    /// Readable while there is room to take more from the client, writable
+    /// while it has data waiting.
+    fn interest(&self) -> Event {
+        Event::new(0, self.upstream.is_empty(), !self.downstream.is_empty())
+    }
+
+    /// Moves what data is ready in both directions. Returns whether any did.
+    fn pump(&mut self) -> std::io::Result<bool> {
+        let mut progress = false;
+        let mut buf = [0u8; 16 * 1024];
+
+        if self.upstream.is_empty() {
+            match self.socket.read(&mut buf) {
+                Ok(0) => self.closed = true,
+                Ok(n) => self.upstream.extend_from_slice(&buf[..n]),
+                Err(e) if e.kind() == ErrorKind::WouldBlock => {}
+                Err(e) => return Err(e),
+            }
+        }
+        if !self.upstream.is_empty() {
+            match self.channel.write(&self.upstream) {
+                Ok(n) => {
+                    self.upstream.drain(..n);
+                    progress = true;
+                }
+                Err(e) if e.kind() == ErrorKind::WouldBlock => {}
+                Err(e) => return Err(e),
+            }
+        }
+
+        if self.downstream.is_empty() {
+            match self.channel.read(&mut buf) {
+                Ok(0) if self.channel.eof() => self.closed = true,
+                Ok(0) => {}
+                Ok(n) => self.downstream.extend_from_slice(&buf[..n]),
+                Err(e) if e.kind() == ErrorKind::WouldBlock => {}
+                Err(e) => return Err(e),
+            }
+        }
+        if !self.downstream.is_empty() {
+            match self.socket.write(&self.downstream) {
+                Ok(n) => {
+                    self.downstream.drain(..n);
+                    progress = true;
+                }
+                Err(e) if e.kind() == ErrorKind::WouldBlock => {}
+                Err(e) => return Err(e),
+            }
+        }
+
+        Ok(progress || self.closed)
+    }
+}
@@ -0,0 +1,559 @@
+use std::collections::HashSet;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+
+use anyhow::Result;
+use unicode_width::UnicodeWidthStr;
+
+use crate::db::{DatabaseConnection, QueryResult, RowSink};
+
+/// Flush buffered output every this many rows while writing to disk.
+const FLUSH_EVERY: u64 = 10_000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportFormat {
+    Csv,
+    Tsv,
+    Json,
+    PgCopy,
+}
+
+impl ExportFormat {
+    pub const ALL: [ExportFormat; 4] = [
+        ExportFormat::Csv,
+        ExportFormat::Tsv,
+        ExportFormat::Json,
+        ExportFormat::PgCopy,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            ExportFormat::Csv => "CSV",
+            ExportFormat::Tsv => "TSV",
+            ExportFormat::Json => "JSON",
+            ExportFormat::PgCopy => "Postgres COPY text",
+        }
+    }
+
+    pub fn extension(self) -> &'static str {
+        match self {
+            ExportFormat::Csv => "csv",
+            ExportFormat::Tsv => "tsv",
+            ExportFormat::Json => "json",
+            ExportFormat::PgCopy => "copy",
+        }
+    }
+
+    pub fn formatter(self) -> Box<dyn ResultFormatter> {
+        match self {
+            ExportFormat::Csv => Box::new(CsvFormatter),
+            ExportFormat::Tsv => Box::new(TsvFormatter),
+            ExportFormat::Json => Box::new(JsonFormatter::default()),
+            ExportFormat::PgCopy => Box::new(PgCopyFormatter),
+        }
+    }
+
+    pub fn render(self, result: &QueryResult) -> String {
+        let mut buf = Vec::new();
+        // Writing into a Vec cannot fail.
+        let _ = write_result(result, self, &mut buf);
+        String::from_utf8_lossy(&buf).into_owned()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportDestination {
+    Clipboard,
+    File,
+    StreamFile,
+}
+
+impl ExportDestination {
+    pub const ALL: [ExportDestination; 3] = [
+        ExportDestination::Clipboard,
+        ExportDestination::File,
+        ExportDestination::StreamFile,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            ExportDestination::Clipboard => "Clipboard",
+            ExportDestination::File => "File (prompt path)",
+            ExportDestination::StreamFile => "File, re-run query streamed (large results)",
+        }
+    }
+}
+
+/// Structured rendering of a single row for the detail view.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RowFormat {
+    Json,
+    Yaml,
+}
+
+impl RowFormat {
+    pub fn label(self) -> &'static str {
+        match self {
+            RowFormat::Json => "JSON",
+            RowFormat::Yaml => "YAML",
+        }
+    }
+
+    pub fn toggled(self) -> Self {
+        match self {
+            RowFormat::Json => RowFormat::Yaml,
+            RowFormat::Yaml => RowFormat::Json,
+        }
+    }
+
+    pub fn render(self, result: &QueryResult, row: usize) -> String {
+        let value = row_to_json(result, row);
+        match self {
+            RowFormat::Json => serde_json::to_string_pretty(&value).unwrap_or_default(),
+            RowFormat::Yaml => serde_yaml::to_string(&value).unwrap_or_default(),
+        }
+    }
+}
+
+/// Maps column names to values, typed from the column's SQL type so numbers
+/// and booleans aren't quoted and NULL becomes null.
+pub fn row_to_json(result: &QueryResult, row: usize) -> serde_json::Value {
+    let mut map = serde_json::Map::new();
+    if let Some(cells) = result.rows.get(row) {
+        for (i, (column, cell)) in result.columns.iter().zip(cells).enumerate() {
+            let type_name = result.column_types.get(i).map(String::as_str).unwrap_or("");
+            map.insert(column.clone(), typed_value(cell.as_deref(), type_name));
+        }
+    }
+    serde_json::Value::Object(map)
+}
+
+fn typed_value(cell: Option<&str>, type_name: &str) -> serde_json::Value {
+    use serde_json::Value;
+
+    let Some(cell) = cell else {
+        return Value::Null;
+    };
+    let type_name = type_name.to_ascii_uppercase();
+    if type_name.starts_with("BOOL") || type_name == "TINYINT(1)" {
+        if let Ok(v) = cell.parse::<bool>() {
+            return Value::Bool(v);
+        }
+    } else if type_name.contains("INT") || type_name.contains("SERIAL") {
+        if let Ok(v) = cell.parse::<i64>() {
+            return Value::from(v);
+        }
+    } else if ["FLOAT", "REAL", "DOUBLE", "NUMERIC", "DECIMAL"]
+        .iter()
+        .any(|t| type_name.contains(t))
+    {
+        // Only emit a number when f64 keeps every digit; otherwise keep the text.
+        if let Ok(v) = cell.parse::<f64>()
+            && v.is_finite()
+            && v.to_string() == cell
+        {
+            return Value::from(v);
+        }
+    } else if type_name.starts_with("JSON")
+        && let Ok(v) = serde_json::from_str(cell)
+    {
+        return v;
+    }
+    Value::String(cell.to_string())
+}
+
+/// One row as TSV with a header line, for pasting a single record.
+pub fn row_to_tsv(result: &QueryResult, row: usize) -> Option<String> {
+    let values = result.rows.get(row)?;
+    let mut buf = Vec::new();
+    // Writing into a Vec cannot fail.
+    let _ = write_tsv_record(&mut buf, result.columns.iter().map(String::as_str));
+    let _ = write_tsv_record(&mut buf, values.iter().map(|c| c.as_deref().unwrap_or("NULL")));
+    Some(String::from_utf8_lossy(&buf).into_owned())
+}
+
+/// `result` without the columns at `hidden`, so exports match the grid.
+pub fn without_columns(result: &QueryResult, hidden: &HashSet<usize>) -> QueryResult {
+    QueryResult {
+        columns: keep_visible(&result.columns, hidden),
+        column_types: keep_visible(&result.column_types, hidden),
+        rows: result.rows.iter().map(|row| keep_visible(row, hidden)).collect(),
+        source_table: result.source_table.clone(),
+        ..*result
+    }
+}
+
+fn keep_visible<T: Clone>(items: &[T], hidden: &HashSet<usize>) -> Vec<T> {
+    items
+        .iter()
+        .enumerate()
+        .filter(|(i, _)| !hidden.contains(i))
+        .map(|(_, item)| item.clone())
+        .collect()
+}
+
+/// The whole result as a GitHub-flavored Markdown table with padded columns.
+/// Values are never truncated; pipes are escaped and line breaks become
+/// `<br>` so every row stays on one line.
+pub fn to_markdown_table(result: &QueryResult) -> String {
+    let escape = |s: &str| s.replace('|', "\\|").replace("\r\n", "<br>").replace(['\n', '\r'], "<br>");
+    let header: Vec<String> = result.columns.iter().map(|c| escape(c)).collect();
+    let rows: Vec<Vec<String>> = result
+        .rows
+        .iter()
+        .map(|row| row.iter().map(|c| c.as_deref().map_or_else(|| "NULL".to_string(), escape)).collect())
+        .collect();
+    // Markdown needs at least three dashes in the separator.
+    let widths: Vec<usize> = (0..header.len())
+        .map(|i| {
+            rows.iter()
+                .filter_map(|row| row.get(i))
+                .chain([&header[i]])
+                .map(|cell| cell.width())
+                .max()
+                .unwrap_or(0)
+                .max(3)
+        })
+        .collect();
+
+    let line = |cells: &[String]| {
+        let padded: Vec<String> = cells
+            .iter()
+            .zip(&widths)
+            .map(|(cell, &width)| format!("{cell}{}", " ".repeat(width - cell.width())))
+            .collect();
+        format!("| {} |\n", padded.join(" | "))
+    };
+    let mut out = line(&header);
+    let dashes: Vec<String> = widths.iter().map(|&w| "-".repeat(w)).collect();
+    out.push_str(&line(&dashes));
+    for row in &rows {
+        out.push_str(&line(row));
+    }
+    out
+}
+
+/// `INSERT INTO table (...) VALUES (...)` for one row of a result that came
+/// from a known table. `quote_ident` applies the database's identifier quoting.
+pub fn row_to_insert(result: &QueryResult, row: usize, quote_ident: impl Fn(&str) -> String) -> Option<String> {
+    let table = result.source_table.as_ref()?;
+    let cells = result.rows.get(row)?;
+
+    let columns: Vec<String> = result.columns.iter().map(|c| quote_ident(c)).collect();
+    let values: Vec<String> = cells
+        .iter()
+        .enumerate()
+        .map(|(i, cell)| sql_literal(cell.as_deref(), result.column_types.get(i).map(String::as_str).unwrap_or("")))
+        .collect();
+
+    Some(format!(
+        "INSERT INTO {}.{} ({}) VALUES ({});",
+        quote_ident(&table.schema),
+        quote_ident(&table.name),
+        columns.join(", "),
+        values.join(", ")
+    ))
+}
+
+/// An UPDATE setting one cell of a row of a table opened from the sidebar,
+/// with the row picked out by the single-column key `key_col`.
+pub fn cell_update(
+    result: &QueryResult,
+    row: usize,
+    col: usize,
+    key_col: usize,
+    value: Option<&str>,
+    quote_ident: impl Fn(&str) -> String,
+) -> Option<String> {
+    let table = result.source_table.as_ref()?;
+    let key = result.rows.get(row)?.get(key_col)?.as_deref()?;
+    let type_of = |i: usize| result.column_types.get(i).map(String::as_str).unwrap_or("");
+    Some(format!(
+        "UPDATE {}.{} SET {} = {} WHERE {} = {};",
+        quote_ident(&table.schema),
+        quote_ident(&table.name),
+        quote_ident(result.columns.get(col)?),
+        sql_literal(value, type_of(col)),
+        quote_ident(result.columns.get(key_col)?),
+        sql_literal(Some(key), type_of(key_col))
+    ))
+}
+
+fn sql_literal(cell: Option<&str>, type_name: &str) -> String {
+    let Some(cell) = cell else {
+        return "NULL".to_string();
+    };
+    let type_name = type_name.to_ascii_uppercase();
+    let numeric = ["INT", "SERIAL", "FLOAT", "REAL", "DOUBLE", "NUMERIC", "DECIMAL"]
+        .iter()
+        .any(|t| type_name.contains(t));
+    let bool_type = type_name.starts_with("BOOL");
+    // Hex literals as rendered for MySQL and SQLite blobs are already valid SQL.
+    let blob_literal = (type_name.contains("BLOB") || type_name.contains("BINARY"))
+        && (cell.starts_with("0x") || cell.starts_with("X'"));
+
+    if (numeric && cell.parse::<f64>().is_ok_and(f64::is_finite)) || (bool_type && cell.parse::<bool>().is_ok()) || blob_literal {
+        cell.to_string()
+    } else {
+        format!("'{}'", cell.replace('\'', "''"))
+    }
+}
+
+/// Serializes a result set one row at a time so output can be streamed.
+pub trait ResultFormatter {
+    fn write_header(&mut self, out: &mut dyn Write, columns: &[String]) -> io::Result<()>;
+    fn write_row(&mut self, out: &mut dyn Write, row: &[Option<String>]) -> io::Result<()>;
+    fn finish(&mut self, _out: &mut dyn Write) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+struct CsvFormatter;
+
+impl ResultFormatter for CsvFormatter {
+    fn write_header(&mut self, out: &mut dyn Write, columns: &[String]) -> io::Result<()> {
+        write_csv_record(out, columns.iter().map(String::as_str))
+    }
+
+    fn write_row(&mut self, out: &mut dyn Write, row: &[Option<String>]) -> io::Result<()> {
+        // NULL is written as an empty unquoted field so it reimports as NULL.
+        write_csv_record(out, row.iter().map(|c| c.as_deref().unwrap_or("")))
+    }
+}
+
+fn write_csv_record<'a>(out: &mut dyn Write, fields: impl Iterator<Item = &'a str>) -> io::Result<()> {
+    for (i, field) in fields.enumerate() {
+        if i > 0 {
+            out.write_all(b",")?;
+        }
+        if field.contains([',', '"', '\n', '\r']) {
+            write!(out, "\"{}\"", field.replace('"', "\"\""))?;
+        } else {
+            out.write_all(field.as_bytes())?;
+        }
+    }
+    out.write_all(b"\r\n")
+}
+
+struct TsvFormatter;
+
+impl ResultFormatter for TsvFormatter {
+    fn write_header(&mut self, out: &mut dyn Write, columns: &[String]) -> io::Result<()> {
+        write_tsv_record(out, columns.iter().map(String::as_str))
+    }
+
+    fn write_row(&mut self, out: &mut dyn Write, row: &[Option<String>]) -> io::Result<()> {
+        write_tsv_record(out, row.iter().map(|c| c.as_deref().unwrap_or("NULL")))
+    }
+}
+
+fn write_tsv_record<'a>(out: &mut dyn Write, fields: impl Iterator<Item = &'a str>) -> io::Result<()> {
+    let line: Vec<String> = fields
+        .map(|f| f.replace(['\t', '\n', '\r'], " "))
+        .collect();
+    writeln!(out, "{}", line.join("\t"))
+}
+
+/// A JSON array of objects keyed by column name. NULL becomes `null`; every
+/// other value stays a string, since cells reach the grid already stringified
+/// and guessing types here would be lossy (use the row detail view for typed
+/// values).
+#[derive(Default)]
+struct JsonFormatter {
+    columns: Vec<String>,
+    rows: u64,
+}
+
+impl ResultFormatter for JsonFormatter {
+    fn write_header(&mut self, _out: &mut dyn Write, columns: &[String]) -> io::Result<()> {
+        self.columns = columns.to_vec();
+        Ok(())
+    }
+
+    fn write_row(&mut self, out: &mut dyn Write, row: &[Option<String>]) -> io::Result<()> {
+        let object: serde_json::Map<String, serde_json::Value> = self
+            .columns
+            .iter()
+            .zip(row)
+            .map(|(column, cell)| {
+                let value = match cell {
+                    Some(cell) => serde_json::Value::String(cell.clone()),
+                    None => serde_json::Value::Null,
+                };
+                (column.clone(), value)
+            })
+            .collect();
+        out.write_all(if self.rows == 0 { b"[\n  " } else { b",\n  " })?;
+        serde_json::to_writer(&mut *out, &object)?;
+        self.rows += 1;
+        Ok(())
+    }
+
+    fn finish(&mut self, out: &mut dyn Write) -> io::Result<()> {
+        out.write_all(if self.rows == 0 { b"[]\n" } else { b"\n]\n" })
+    }
+}
+
+/// Postgres `COPY` text format: tab-delimited, `\N` for NULL, backslash
+/// escapes for specials and no header row. Load the file back with
+/// `\copy table FROM 'results.copy'` in psql, or `COPY table FROM '/path'`
+/// when the file is on the server; the columns must be in the same order.
+struct PgCopyFormatter;
+
+impl ResultFormatter for PgCopyFormatter {
+    fn write_header(&mut self, _out: &mut dyn Write, _columns: &[String]) -> io::Result<()> {
+        Ok(())
+    }
+
+    fn write_row(&mut self, out: &mut dyn Write, row: &[Option<String>]) -> io::Result<()> {
+        for (i, field) in row.iter().enumerate() {
+            if i > 0 {
+                out.write_all(b"\t")?;
+            }
+            match field {
+                Some(field) => out.write_all(escape_copy_field(field).as_bytes())?,
+                None => out.write_all(b"\\N")?,
+            }
+        }
+        out.write_all(b"\n")
+    }
+}
+
+fn escape_copy_field(field: &str) -> String {
+    let mut escaped = String::with_capacity(field.len());
+    for c in field.chars() {
+        match c {
+            '\\' => escaped.push_str("\\\\"),
+            '\t' => escaped.push_str("\\t"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            '\u{8}' => escaped.push_str("\\b"),
+            '\u{c}' => escaped.push_str("\\f"),
+            '\u{b}' => escaped.push_str("\\v"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+pub fn write_result(result: &QueryResult, format: ExportFormat, out: &mut dyn Write) -> io::Result<()> {
+    let mut sink = FormatterSink::new(format, out);
+    sink.formatter.write_header(sink.out, &result.columns)?;
+    for row in &result.rows {
+        sink.write_row(row)?;
+    }
+    sink.finish()
+}
+
+pub fn export_to_file(result: &QueryResult, format: ExportFormat, path: &Path) -> Result<()> {
+    let mut out = BufWriter::new(File::create(path)?);
+    write_result(result, format, &mut out)?;
+    Ok(())
+}
+
+/// Re-runs `query` and writes rows to `path` as they arrive from the
+/// database, so arbitrarily large results never sit in memory at once.
+pub async fn stream_query_to_file(
+    conn: &DatabaseConnection,
+    query: &str,
+    format: ExportFormat,
+    hidden: &HashSet<usize>,
+    path: &Path,
+) -> Result<u64> {
+    let mut out = BufWriter::new(File::create(path)?);
+    let mut sink = FormatterSink::new(format, &mut out);
+    sink.hidden = hidden.clone();
+    let rows = conn.stream_query(query, &mut sink).await?;
+    sink.finish()?;
+    Ok(rows)
+}
+
+struct FormatterSink<'w> {
+    formatter: Box<dyn ResultFormatter>,
+    out: &'w mut dyn Write,
+    rows: u64,
+    /// Columns left out of the output.
+    hidden: HashSet<usize>,
+}
+
+impl<'w> FormatterSink<'w> {
+    fn new(format: ExportFormat, out: &'w mut dyn Write) -> Self {
+        Self {
+            formatter: format.formatter(),
+            out,
+            rows: 0,
+            hidden: HashSet::new(),
+        }
+    }
+
+    fn write_row(&mut self, row: &[Option<String>]) -> io::Result<()> {
+        if self.hidden.is_empty() {
+            self.formatter.write_row(self.out, row)?;
+        } else {
+            self.formatter.write_row(self.out, &keep_visible(row, &self.hidden))?;
+        }
+        self.rows += 1;
+        if self.rows.is_multiple_of(FLUSH_EVERY) {
+            self.out.flush()?;
+        }
+        Ok(())
+    }
+
+    fn finish(&mut self) -> io::Result<()> {
+        self.formatter.finish(self.out)?;
+        self.out.flush()
+    }
+}
+
+impl RowSink for FormatterSink<'_> {
+    fn columns(&mut self, columns: &[String]) -> Result<()> {
+        self.formatter.write_header(self.out, &keep_visible(columns, &self.hidden))?;
+        Ok(())
+    }
+
+    fn row(&mut self, row: &[Option<String>]) -> Result<()> {
+        self.write_row(row)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn result(columns: &[&str], rows: &[&[Option<&str>]]) -> QueryResult {
+        QueryResult {
+            columns: columns.iter().map(|c| c.to_string()).collect(),
+            rows: rows
+                .iter()
+                .map(|row| row.iter().map(|cell| cell.map(str::to_string)).collect())
+                .collect(),
+            ..QueryResult::empty()
+        }
+    }
+
+    #[test]
+    fn markdown_separator_has_at_least_three_dashes() {
+        let table = to_markdown_table(&result(&["a", "long_name"], &[&[Some("1"), Some("2")]]));
+        assert_eq!(table, "| a   | long_name |\n| --- | --------- |\n| 1   | 2         |\n");
+    }
+
+    #[test]
+    fn markdown_escapes_pipes_and_line_breaks() {
+        let table = to_markdown_table(&result(&["a|b"], &[&[Some("x|y\nz\r\nw")]]));
+        assert_eq!(table, "| a\\|b           |\n| -------------- |\n| x\\|y<br>z<br>w |\n");
+    }
+
+    #[test]
+    fn markdown_writes_null_cells_as_null() {
+        let table = to_markdown_table(&result(&["v"], &[&[None], &[Some("")]]));
+        assert_eq!(table, "| v    |\n| ---- |\n| NULL |\n|      |\n");
+    }
+
+    #[test]
+    fn markdown_pads_wide_characters_by_display_width() {
+        let table = to_markdown_table(&result(&["name", "n"], &[&[Some("東京"), Some("1")], &[Some("ab"), Some("2")]]));
+        assert_eq!(table, "| name | n   |\n| ---- | --- |\n| 東京 | 1   |\n| ab   | 2   |\n");
+    }
+}
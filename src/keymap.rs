@@ -0,0 +1,353 @@
+use std::collections::HashMap;
+use std::str::FromStr;
+
+use anyhow::{anyhow, bail};
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use serde::Deserialize;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KeyContext {
+    Global,
+    Sidebar,
+    Editor,
+    Results,
+}
+
+impl KeyContext {
+    pub const ALL: [KeyContext; 4] = [
+        KeyContext::Global,
+        KeyContext::Sidebar,
+        KeyContext::Editor,
+        KeyContext::Results,
+    ];
+
+    pub fn title(self) -> &'static str {
+        match self {
+            KeyContext::Global => "Global",
+            KeyContext::Sidebar => "Sidebar",
+            KeyContext::Editor => "Editor",
+            KeyContext::Results => "Results",
+        }
+    }
+}
+
+pub struct KeyBinding {
+    pub context: KeyContext,
+    pub keys: &'static str,
+    pub description: &'static str,
+}
+
+const fn bind(context: KeyContext, keys: &'static str, description: &'static str) -> KeyBinding {
+    KeyBinding { context, keys, description }
+}
+
+pub const BINDINGS: &[KeyBinding] = &[
+    bind(KeyContext::Global, "Tab", "Cycle focus"),
+    bind(KeyContext::Global, "? / F1", "Toggle this help"),
+    bind(KeyContext::Global, "F2", "Toggle read-only mode (remembered per connection)"),
+    bind(KeyContext::Global, "F3", "Begin a transaction"),
+    bind(KeyContext::Global, "F4 / F5", "Commit / roll back the transaction"),
+    bind(KeyContext::Global, "Ctrl+P", "Find and open a table by name"),
+    bind(KeyContext::Global, "Ctrl+B", "Show / hide the sidebar"),
+    bind(KeyContext::Global, "Ctrl+O", "Saved queries (Enter: load, d: delete)"),
+    bind(KeyContext::Global, "Esc / Ctrl+C", "Cancel the running query"),
+    bind(KeyContext::Global, "Esc", "Quit"),
+    bind(KeyContext::Sidebar, "↓ / j", "Next node"),
+    bind(KeyContext::Sidebar, "↑ / k", "Previous node"),
+    bind(KeyContext::Sidebar, "Enter", "Open table / toggle schema"),
+    bind(KeyContext::Sidebar, "e", "SELECT the table's columns by name into the editor"),
+    bind(KeyContext::Sidebar, "i", "INSERT template for the table into the editor"),
+    bind(KeyContext::Sidebar, "d", "Show the table's CREATE statement"),
+    bind(KeyContext::Sidebar, "→", "Expand schema / show table columns"),
+    bind(KeyContext::Sidebar, "←", "Collapse / go to parent"),
+    bind(KeyContext::Sidebar, "Space", "Toggle schema / table columns"),
+    bind(KeyContext::Sidebar, "Click ▶ / double-click", "Expand node / open table"),
+    bind(KeyContext::Sidebar, "Drag right edge", "Resize the sidebar (remembered)"),
+    bind(KeyContext::Sidebar, "/", "Search (Enter keeps, Esc clears)"),
+    bind(KeyContext::Sidebar, "f", "Filter the tree by name (Enter keeps, Esc clears)"),
+    bind(KeyContext::Sidebar, "n / N", "Next / previous match"),
+    bind(KeyContext::Sidebar, "s", "Show active sessions (Postgres/MySQL)"),
+    bind(KeyContext::Sidebar, "a", "Attach SQLite database"),
+    bind(KeyContext::Sidebar, "r", "Reload schemas and tables, keeping what is expanded"),
+    bind(KeyContext::Editor, "Alt+C", "Count rows of the query (wrapped in a CTE)"),
+    bind(KeyContext::Editor, "Alt+S", "Sample one page of the query (wrapped in a CTE)"),
+    bind(KeyContext::Editor, "Ctrl+Space", "Complete table / column name (also after .)"),
+    bind(KeyContext::Editor, "Tab / Enter", "Insert the selected completion"),
+    bind(KeyContext::Editor, "Tab / ↑↓, Enter", "Parameter form ($1, ?, @P1): next field, run the query"),
+    bind(KeyContext::Editor, "Alt+F", "Format the query (Ctrl+U undoes)"),
+    bind(KeyContext::Editor, "Ctrl+S", "Save the query under a name"),
+    bind(KeyContext::Editor, "Esc / i a o", "Vim mode: normal mode / back to insert"),
+    bind(KeyContext::Editor, "h j k l w b", "Vim normal mode: move"),
+    bind(KeyContext::Editor, "x / dd", "Vim normal mode: delete character / line"),
+    bind(KeyContext::Editor, "← / →", "Select button (button bar)"),
+    bind(KeyContext::Editor, "Enter", "Press button (button bar)"),
+    bind(KeyContext::Results, "↓ / j", "Next row"),
+    bind(KeyContext::Results, "↑ / k", "Previous row"),
+    bind(KeyContext::Results, "← / h", "Previous column"),
+    bind(KeyContext::Results, "→ / l", "Next column"),
+    bind(KeyContext::Results, "g / Home", "First row"),
+    bind(KeyContext::Results, "G / End", "Last row"),
+    bind(KeyContext::Results, "PgUp / PgDn", "Previous / next screen of rows"),
+    bind(KeyContext::Results, "Click header", "Sort ascending / descending / off"),
+    bind(KeyContext::Results, "/", "Filter rows (Enter keeps, Esc clears)"),
+    bind(KeyContext::Results, "n / N", "Next / previous matching row"),
+    bind(KeyContext::Results, "] / [", "Next / previous page (tables and SELECTs without LIMIT)"),
+    bind(KeyContext::Results, "s", "Refresh the session list"),
+    bind(KeyContext::Results, "x", "Kill the selected session (session list only)"),
+    bind(KeyContext::Results, "Enter", "Row detail as JSON / YAML (f: format, y: copy)"),
+    bind(KeyContext::Results, "r", "Record view of the selected row (↑↓ scroll, ←→ other rows)"),
+    bind(KeyContext::Results, "v", "Full value of the selected cell, hex dump for blobs (y: copy)"),
+    bind(KeyContext::Results, "y", "Copy the error text (after a failed query)"),
+    bind(KeyContext::Results, "Esc", "Dismiss the error panel"),
+    bind(KeyContext::Results, "c", "Copy selected row as TSV with headers"),
+    bind(KeyContext::Results, "i", "Copy selected row as INSERT (table results)"),
+    bind(KeyContext::Results, "m", "Copy all loaded rows as a Markdown table"),
+    bind(KeyContext::Results, "e", "Edit the selected cell and review the UPDATE (table results; Ctrl+N: NULL)"),
+    bind(KeyContext::Results, "d", "Toggle compact / comfortable rows"),
+    bind(KeyContext::Results, "#", "Show / hide row numbers"),
+    bind(KeyContext::Results, "T", "Show / hide column types under the headers"),
+    bind(KeyContext::Results, "H", "Hide the selected column (grid and exports)"),
+    bind(KeyContext::Results, "V", "Choose which columns are shown"),
+    bind(KeyContext::Results, "F", "Freeze the first column while scrolling sideways"),
+    bind(KeyContext::Results, "+ / -", "Widen / narrow truncated cells"),
+    bind(KeyContext::Results, "t", "Column types legend"),
+    bind(KeyContext::Results, "s", "Min, max, sum and mean of the selected column (loaded rows)"),
+    bind(KeyContext::Results, "Ctrl+E", "Export results (clipboard or file)"),
+    bind(KeyContext::Results, "Ctrl+J", "Export results as JSON"),
+];
+
+pub fn bindings_for(context: KeyContext) -> impl Iterator<Item = &'static KeyBinding> {
+    BINDINGS.iter().filter(move |b| b.context == context)
+}
+
+/// Commands that can be rebound in `~/.crux/keys.toml`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Action {
+    Quit,
+    NextFocus,
+    Help,
+    ToggleReadOnly,
+    BeginTransaction,
+    Commit,
+    Rollback,
+    OpenPalette,
+    ToggleSidebar,
+    SavedQueries,
+    RunQuery,
+    FormatQuery,
+    ClearQuery,
+    CopyQuery,
+    CountQuery,
+    SampleQuery,
+    SaveQuery,
+    SelectNextRow,
+    SelectPrevRow,
+    SelectNextColumn,
+    SelectPrevColumn,
+}
+
+impl Action {
+    /// Where the action applies; Editor actions also work on the button bar.
+    pub fn context(self) -> KeyContext {
+        match self {
+            Action::Quit
+            | Action::NextFocus
+            | Action::Help
+            | Action::ToggleReadOnly
+            | Action::BeginTransaction
+            | Action::Commit
+            | Action::Rollback
+            | Action::OpenPalette
+            | Action::ToggleSidebar
+            | Action::SavedQueries => KeyContext::Global,
+            Action::RunQuery
+            | Action::FormatQuery
+            | Action::ClearQuery
+            | Action::CopyQuery
+            | Action::CountQuery
+            | Action::SampleQuery
+            | Action::SaveQuery => KeyContext::Editor,
+            Action::SelectNextRow | Action::SelectPrevRow | Action::SelectNextColumn | Action::SelectPrevColumn => {
+                KeyContext::Results
+            }
+        }
+    }
+
+    fn default_keys(self) -> &'static [&'static str] {
+        match self {
+            Action::Quit => &["esc"],
+            Action::NextFocus => &["tab"],
+            Action::Help => &["f1", "?"],
+            Action::ToggleReadOnly => &["f2"],
+            Action::BeginTransaction => &["f3"],
+            Action::Commit => &["f4"],
+            Action::Rollback => &["f5"],
+            Action::OpenPalette => &["ctrl+p"],
+            Action::ToggleSidebar => &["ctrl+b"],
+            Action::SavedQueries => &["ctrl+o"],
+            Action::RunQuery | Action::ClearQuery | Action::CopyQuery => &[],
+            Action::FormatQuery => &["alt+f"],
+            Action::CountQuery => &["alt+c"],
+            Action::SampleQuery => &["alt+s"],
+            Action::SaveQuery => &["ctrl+s"],
+            Action::SelectNextRow => &["down", "j"],
+            Action::SelectPrevRow => &["up", "k"],
+            Action::SelectNextColumn => &["right", "l"],
+            Action::SelectPrevColumn => &["left", "h"],
+        }
+    }
+
+    const ALL: [Action; 21] = [
+        Action::Quit,
+        Action::NextFocus,
+        Action::Help,
+        Action::ToggleReadOnly,
+        Action::BeginTransaction,
+        Action::Commit,
+        Action::Rollback,
+        Action::OpenPalette,
+        Action::ToggleSidebar,
+        Action::SavedQueries,
+        Action::RunQuery,
+        Action::FormatQuery,
+        Action::ClearQuery,
+        Action::CopyQuery,
+        Action::CountQuery,
+        Action::SampleQuery,
+        Action::SaveQuery,
+        Action::SelectNextRow,
+        Action::SelectPrevRow,
+        Action::SelectNextColumn,
+        Action::SelectPrevColumn,
+    ];
+}
+
+/// A key with modifiers, written like `ctrl+p`, `f5`, `esc` or `?`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct KeyChord {
+    code: KeyCode,
+    modifiers: KeyModifiers,
+}
+
+impl KeyChord {
+    pub fn matches(&self, key: &KeyEvent) -> bool {
+        // Shift is part of the character itself, e.g. `?` or `N`.
+        let ignore = if matches!(self.code, KeyCode::Char(_)) { KeyModifiers::SHIFT } else { KeyModifiers::NONE };
+        key.code == self.code && key.modifiers.difference(ignore) == self.modifiers.difference(ignore)
+    }
+
+    /// Plain keys that would otherwise be typed into the editor.
+    pub fn is_printable(&self) -> bool {
+        matches!(self.code, KeyCode::Char(_))
+            && !self.modifiers.intersects(KeyModifiers::CONTROL | KeyModifiers::ALT)
+    }
+}
+
+impl FromStr for KeyChord {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        let (mods, key) = match s.rsplit_once('+') {
+            // A bare or trailing `+` is the plus key itself.
+            Some((mods, "")) => (mods.strip_suffix('+').unwrap_or(mods), "+"),
+            Some((mods, key)) => (mods, key),
+            None => ("", s),
+        };
+        let mut modifiers = KeyModifiers::NONE;
+        for m in mods.split('+').filter(|m| !m.is_empty()) {
+            modifiers |= match m.to_ascii_lowercase().as_str() {
+                "ctrl" | "control" => KeyModifiers::CONTROL,
+                "alt" => KeyModifiers::ALT,
+                "shift" => KeyModifiers::SHIFT,
+                other => bail!("unknown modifier `{other}` in `{s}`"),
+            };
+        }
+        let lower = key.to_ascii_lowercase();
+        let code = match lower.as_str() {
+            "esc" | "escape" => KeyCode::Esc,
+            "enter" | "return" => KeyCode::Enter,
+            "tab" => KeyCode::Tab,
+            "backtab" => KeyCode::BackTab,
+            "backspace" => KeyCode::Backspace,
+            "delete" | "del" => KeyCode::Delete,
+            "insert" => KeyCode::Insert,
+            "space" => KeyCode::Char(' '),
+            "up" => KeyCode::Up,
+            "down" => KeyCode::Down,
+            "left" => KeyCode::Left,
+            "right" => KeyCode::Right,
+            "home" => KeyCode::Home,
+            "end" => KeyCode::End,
+            "pageup" => KeyCode::PageUp,
+            "pagedown" => KeyCode::PageDown,
+            f if f.len() > 1 && f.starts_with('f') => {
+                KeyCode::F(f[1..].parse().map_err(|_| anyhow!("unknown key `{key}` in `{s}`"))?)
+            }
+            _ => {
+                let mut chars = key.chars();
+                match (chars.next(), chars.next()) {
+                    // Letters arrive uppercase with Shift and lowercase otherwise.
+                    (Some(c), None) if modifiers.contains(KeyModifiers::SHIFT) => KeyCode::Char(c.to_ascii_uppercase()),
+                    (Some(c), None) if !modifiers.is_empty() => KeyCode::Char(c.to_ascii_lowercase()),
+                    (Some(c), None) => KeyCode::Char(c),
+                    _ => bail!("unknown key `{key}` in `{s}`"),
+                }
+            }
+        };
+        Ok(Self { code, modifiers })
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum KeyList {
+    One(String),
+    Many(Vec<String>),
+}
+
+/// The chords bound to each action: the defaults, with any action listed in
+/// `~/.crux/keys.toml` taking only the keys given there.
+#[derive(Debug, Clone)]
+pub struct KeyMap {
+    bindings: Vec<(KeyChord, Action)>,
+}
+
+impl Default for KeyMap {
+    fn default() -> Self {
+        Self::with_overrides(HashMap::new())
+    }
+}
+
+impl KeyMap {
+    /// An unreadable file is ignored, as are chords that don't parse.
+    pub fn load() -> Self {
+        crate::config::config_dir()
+            .map(|dir| dir.join("keys.toml"))
+            .and_then(|path| std::fs::read_to_string(path).ok())
+            .and_then(|contents| toml::from_str::<HashMap<Action, KeyList>>(&contents).ok())
+            .map(Self::with_overrides)
+            .unwrap_or_default()
+    }
+
+    fn with_overrides(overrides: HashMap<Action, KeyList>) -> Self {
+        let mut bindings = Vec::new();
+        for action in Action::ALL {
+            let keys: Vec<&str> = match overrides.get(&action) {
+                Some(KeyList::One(key)) => vec![key.as_str()],
+                Some(KeyList::Many(keys)) => keys.iter().map(String::as_str).collect(),
+                None => action.default_keys().to_vec(),
+            };
+            bindings.extend(keys.iter().filter_map(|k| k.parse().ok()).map(|chord| (chord, action)));
+        }
+        Self { bindings }
+    }
+
+    /// The first action bound to `key` that `available` allows.
+    pub fn resolve(&self, key: &KeyEvent, available: impl Fn(Action, &KeyChord) -> bool) -> Option<Action> {
+        self.bindings
+            .iter()
+            .find(|(chord, action)| chord.matches(key) && available(*action, chord))
+            .map(|(_, action)| *action)
+    }
+}
@@ -1,29 +1,54 @@
 mod app;
+mod clipboard;
+mod config;
 mod db;
 mod event;
+mod export;
+mod keymap;
 mod storage;
 mod ui;
 
+use std::borrow::Cow;
 use std::io;
-use std::time::Duration;
+use std::sync::Arc;
+use std::sync::atomic::Ordering;
+use std::time::{Duration, Instant};
 
-use anyhow::Result;
+use anyhow::{Context, Result};
 use crossterm::{
-    event::{Event, KeyCode, KeyModifiers, MouseButton, MouseEventKind, EnableMouseCapture, DisableMouseCapture},
+    event::{Event, KeyCode, KeyEvent, KeyModifiers, MouseButton, MouseEventKind, EnableMouseCapture, DisableMouseCapture},
     execute,
     terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
 };
 use ratatui::{backend::CrosstermBackend, layout::{Constraint, Direction, Layout}, Terminal};
 
-use app::{App, AppState, ConnectionFocus, Focus};
+use app::{App, AppState, Confirm, ConfirmAction, ConnectionFocus, Focus, Modal, PasswordRetry, Prompt, PromptAction, QueryKind, QueryOutcome, SidebarClick, SidebarLoad, SidebarReload};
+use config::Config;
 use db::DatabaseConnection;
 use event::poll_event;
 use storage::Storage;
-use ui::{render_connection_dialog, render_query_panel, render_results, render_sidebar, QueryButton, Theme};
+use export::{ExportDestination, RowFormat};
+use keymap::{Action, KeyChord, KeyContext, KeyMap};
+use ui::export::{ExportDialog, ExportStep};
+use ui::pagination::PageButton;
+use ui::palette::Palette;
+use ui::params::ParamForm;
+use ui::saved::SavedQueryList;
+use ui::theme::ColorLevel;
+use ui::{render_confirm, render_connection_dialog, render_error_panel, render_cell_detail, render_ddl, render_row_detail, render_export_dialog, render_help, render_pagination, render_palette, render_param_form, render_production_banner, render_prompt, render_status_bar, render_transaction_banner, render_completion, Completion, render_types_legend, render_query_panel, render_column_stats, render_column_chooser, render_results, render_saved_queries, render_sidebar, QueryButton, Theme, VimMode};
+
+/// Settings key under which the sidebar's width is kept.
+const SIDEBAR_PCT_KEY: &str = "sidebar_pct";
+/// How often the screen is redrawn while a query's spinner is turning.
+const BUSY_POLL: Duration = Duration::from_millis(80);
+/// How long to wait for input when nothing is animating. Input wakes the
+/// loop at once; this only bounds how late background results are noticed.
+const IDLE_POLL: Duration = Duration::from_millis(500);
 
 #[tokio::main]
 async fn main() -> Result<()> {
-    let storage = Storage::new().await?;
+    let config = Config::load();
+    let storage = Storage::new(config.keyring).await?;
 
     enable_raw_mode()?;
     let mut stdout = io::stdout();
@@ -31,15 +56,27 @@ async fn main() -> Result<()> {
     let backend = CrosstermBackend::new(stdout);
     let mut terminal = Terminal::new(backend)?;
 
-    let mut app = App::new();
-    let theme = Theme::default();
+    let mut app = App::new(config, KeyMap::load());
+    let theme = Theme::load().downgraded(ColorLevel::detect());
 
     if let Ok(recent) = storage.get_recent_connections(10).await {
         app.set_recent_connections(recent);
     }
+    if let Ok(Some(pct)) = storage.get_setting(SIDEBAR_PCT_KEY).await
+        && let Ok(pct) = pct.parse::<u16>()
+    {
+        app.sidebar_pct = pct.clamp(app::MIN_SIDEBAR_PCT, app::MAX_SIDEBAR_PCT);
+    }
 
     let result = run_app(&mut terminal, &mut app, &storage, &theme).await;
 
+    let _ = storage.set_setting(SIDEBAR_PCT_KEY, &app.sidebar_pct.to_string()).await;
+    if let Some(conn_str) = &app.connection_string {
+        let _ = storage.set_setting(&Storage::query_key(conn_str), &app.get_query_text()).await;
+        let read_only = if app.read_only { "1" } else { "0" };
+        let _ = storage.set_setting(&Storage::read_only_key(conn_str), read_only).await;
+    }
+
     disable_raw_mode()?;
     execute!(terminal.backend_mut(), LeaveAlternateScreen, DisableMouseCapture)?;
     terminal.show_cursor()?;
@@ -57,75 +94,244 @@ async fn run_app<B: ratatui::backend::Backend>(
     storage: &Storage,
     theme: &Theme,
 ) -> Result<()> {
+    // Redraw only when something may have changed: input, a finished query,
+    // background results, the spinner, or a status message expiring.
+    let mut dirty = true;
+    let mut status_shown = false;
     loop {
-        terminal.draw(|frame| {
-            match app.state {
-                AppState::Connection => {
-                    render_connection_dialog(
-                        frame,
-                        &app.connection_input,
-                        app.connection_error.as_deref(),
-                        &app.recent_connections,
-                        &mut app.recent_connections_state,
-                        app.connection_focus,
-                        theme,
-                    );
-                }
-                AppState::Browser => {
-                    let chunks = Layout::default()
-                        .direction(Direction::Horizontal)
-                        .constraints([Constraint::Percentage(22), Constraint::Percentage(78)])
-                        .split(frame.area());
-
-                    app.sidebar_area = Some(chunks[0]);
-
-                    render_sidebar(
-                        frame,
-                        chunks[0],
-                        &mut app.tree_state,
-                        app.focus == Focus::Sidebar,
-                        theme,
-                    );
+        if app.query_running || status_shown != app.current_status().is_some() {
+            dirty = true;
+        }
+        if dirty {
+            status_shown = app.current_status().is_some();
+            dirty = false;
+            terminal.draw(|frame| {
+                match app.state {
+                    AppState::Connection => {
+                        render_connection_dialog(
+                            frame,
+                            &app.connection_input,
+                            &app.connection_form,
+                            app.connection_error.as_deref(),
+                            &app.recent_connections,
+                            &app.collapsed_groups,
+                            &mut app.recent_connections_state,
+                            app.connection_focus,
+                            theme,
+                        );
+                        if let Some(retry) = &app.password_retry {
+                            render_prompt(
+                                frame,
+                                "Authentication failed",
+                                "Re-enter the password; the rest of the connection string is kept",
+                                &retry.input,
+                                theme,
+                            );
+                        }
+                        if let Some(prompt) = &app.connection_prompt {
+                            render_prompt(frame, &prompt.title, &prompt.hint, &prompt.input, theme);
+                        }
+                    }
+                    AppState::Browser => {
+                        let mut area = frame.area();
+                        if app.is_production() {
+                            let banner = Layout::vertical([Constraint::Length(1), Constraint::Min(0)]).split(area);
+                            render_production_banner(frame, banner[0], theme);
+                            area = banner[1];
+                        }
+                        if app.transaction.is_some() {
+                            let banner = Layout::vertical([Constraint::Length(1), Constraint::Min(0)]).split(area);
+                            render_transaction_banner(frame, banner[0], theme);
+                            area = banner[1];
+                        }
+                        if let Some(conn) = &app.connection {
+                            let bar = Layout::vertical([Constraint::Min(0), Constraint::Length(1)]).split(area);
+                            render_status_bar(
+                                frame,
+                                bar[1],
+                                conn.engine(),
+                                app.server_version.as_deref(),
+                                &app.connection_name,
+                                theme,
+                            );
+                            area = bar[0];
+                        }
 
-                    let right_chunks = Layout::default()
-                        .direction(Direction::Vertical)
-                        .constraints([Constraint::Length(8), Constraint::Min(10)])
-                        .split(chunks[1]);
-
-                    let button_region = render_query_panel(
-                        frame,
-                        right_chunks[0],
-                        &app.query_input,
-                        app.focus == Focus::Query || app.focus == Focus::QueryButtons,
-                        app.selected_button,
-                        app.hovered_button,
-                        theme,
-                    );
-                    app.button_region = Some(button_region);
-
-                    render_results(
-                        frame,
-                        right_chunks[1],
-                        &app.query_result,
-                        &mut app.results_state,
-                        app.focus == Focus::Results,
-                        theme,
-                    );
+                        let main_area = if app.show_sidebar {
+                            let chunks = Layout::default()
+                                .direction(Direction::Horizontal)
+                                .constraints([
+                                    Constraint::Percentage(app.sidebar_pct),
+                                    Constraint::Percentage(100 - app.sidebar_pct),
+                                ])
+                                .split(area);
+
+                            app.browser_area = Some(area);
+                            app.sidebar_area = Some(chunks[0]);
+
+                            render_sidebar(
+                                frame,
+                                chunks[0],
+                                &mut app.tree_state,
+                                app.focus == Focus::Sidebar,
+                                theme,
+                            );
+                            chunks[1]
+                        } else {
+                            app.sidebar_area = None;
+                            area
+                        };
+
+                        let right_chunks = Layout::default()
+                            .direction(Direction::Vertical)
+                            .constraints([Constraint::Length(8), Constraint::Min(10)])
+                            .split(main_area);
+
+                        let button_region = render_query_panel(
+                            frame,
+                            right_chunks[0],
+                            &app.query_input,
+                            app.focus == Focus::Query || app.focus == Focus::QueryButtons,
+                            app.selected_button,
+                            app.hovered_button,
+                            app.read_only,
+                            app.config.vim_mode.then_some(app.vim_mode),
+                            theme,
+                        );
+                        app.button_region = Some(button_region);
+
+                        let mut results_area = right_chunks[1];
+                        app.pagination_region = None;
+                        if let Some(pagination) = &app.pagination {
+                            let split = Layout::vertical([Constraint::Min(0), Constraint::Length(1)]).split(results_area);
+                            results_area = split[0];
+                            app.pagination_region = Some(render_pagination(
+                                frame,
+                                split[1],
+                                pagination.page,
+                                pagination.total_pages(),
+                                app.hovered_page_button,
+                                theme,
+                            ));
+                        }
+
+                        let status = app.running_status().or_else(|| app.current_status().cloned());
+                        if let Some(error) = &app.query_error {
+                            render_error_panel(
+                                frame,
+                                results_area,
+                                error,
+                                app.error_scroll,
+                                app.focus == Focus::Results,
+                                status.as_ref(),
+                                theme,
+                            );
+                        } else {
+                            render_results(
+                                frame,
+                                results_area,
+                                &app.query_result,
+                                &mut app.results_state,
+                                app.focus == Focus::Results,
+                                status.as_ref(),
+                                &app.config,
+                                theme,
+                            );
+                        }
+
+                        if app.focus == Focus::Query
+                            && let Some(completion) = &app.completion
+                        {
+                            render_completion(frame, right_chunks[0], &app.query_input, completion, theme);
+                        }
+
+                        match &mut app.modal {
+                            Some(Modal::Help) => render_help(frame, theme),
+                            Some(Modal::Export(dialog)) => render_export_dialog(frame, dialog, theme),
+                            Some(Modal::Prompt(prompt)) => {
+                                render_prompt(frame, &prompt.title, &prompt.hint, &prompt.input, theme)
+                            }
+                            Some(Modal::TypesLegend { scroll }) => {
+                                render_types_legend(frame, &app.query_result, *scroll, theme)
+                            }
+                            Some(Modal::Confirm(confirm)) => {
+                                render_confirm(frame, &confirm.title, &confirm.message, &confirm.query, theme)
+                            }
+                            Some(Modal::RowDetail { format, scroll }) => render_row_detail(
+                                frame,
+                                &app.query_result,
+                                app.results_state.selected_row,
+                                *format,
+                                *scroll,
+                                theme,
+                            ),
+                            Some(Modal::CellDetail { scroll }) => render_cell_detail(
+                                frame,
+                                &app.query_result,
+                                app.results_state.selected_row,
+                                app.results_state.selected_col,
+                                scroll,
+                                theme,
+                            ),
+                            Some(Modal::Ddl { table, ddl, scroll }) => render_ddl(frame, table, ddl, scroll, theme),
+                            Some(Modal::Palette(palette)) => {
+                                render_palette(frame, palette, &app.tables, !app.tree_state.all_schemas_loaded(), theme)
+                            }
+                            Some(Modal::Params(form)) => render_param_form(frame, form, theme),
+                            Some(Modal::SavedQueries(list)) => render_saved_queries(frame, list, theme),
+                            Some(Modal::ColumnStats { column, stats }) => {
+                                render_column_stats(frame, column, app.query_result.rows.len(), stats, theme)
+                            }
+                            Some(Modal::Columns { selected }) => render_column_chooser(
+                                frame,
+                                &app.query_result.columns,
+                                &app.results_state.hidden_cols,
+                                *selected,
+                                theme,
+                            ),
+                            None => {}
+                        }
+                    }
                 }
+            })?;
+        }
+
+        if !app.sidebar_loading() {
+            if let Some(schema) = app.tree_state.loading.clone() {
+                load_schema_tables(app, schema);
+                dirty = true;
+            } else if let Some((schema, table)) = app.tree_state.loading_columns.clone() {
+                load_table_columns(app, schema, table);
+                dirty = true;
             }
-        })?;
+        }
 
-        if let Some(event) = poll_event(Duration::from_millis(50))? {
+        let timeout = if app.query_running || app.sidebar_loading() { BUSY_POLL } else { IDLE_POLL };
+        if let Some(event) = poll_event(timeout)? {
+            dirty = true;
             match app.state {
                 AppState::Connection => {
                     handle_connection_event(app, storage, event).await;
                 }
                 AppState::Browser => {
-                    handle_browser_event(app, event).await;
+                    handle_browser_event(app, storage, event).await;
                 }
             }
         }
 
+        if let Some(outcome) = app.poll_query() {
+            finish_query(app, outcome);
+            dirty = true;
+        }
+        if let Some(load) = app.poll_sidebar() {
+            finish_sidebar_load(app, load);
+            dirty = true;
+        }
+        while let Ok((schema, counts)) = app.row_counts_rx.try_recv() {
+            app.set_row_counts(&schema, &counts);
+            dirty = true;
+        }
+
         if app.should_quit {
             break;
         }
@@ -135,14 +341,70 @@ async fn run_app<B: ratatui::backend::Backend>(
 }
 
 async fn handle_connection_event(app: &mut App<'_>, storage: &Storage, event: Event) {
+    if let Some(prompt) = app.connection_prompt.as_mut() {
+        if let Event::Key(key) = event {
+            match key.code {
+                KeyCode::Esc => app.connection_prompt = None,
+                KeyCode::Enter => {
+                    let prompt = app.connection_prompt.take().unwrap();
+                    submit_prompt(app, storage, prompt.action, &prompt.value()).await;
+                }
+                _ => {
+                    prompt.input.input(event);
+                }
+            }
+        }
+        return;
+    }
+
+    if let Some(retry) = app.password_retry.as_mut() {
+        if let Event::Key(key) = event {
+            match key.code {
+                KeyCode::Esc => {
+                    app.password_retry = None;
+                }
+                KeyCode::Enter => {
+                    let retry = app.password_retry.take().unwrap();
+                    match db::with_password(&retry.connection_string, &retry.password()) {
+                        Ok(conn_str) => connect(app, storage, conn_str).await,
+                        Err(e) => app.connection_error = Some(e.to_string()),
+                    }
+                }
+                _ => {
+                    retry.input.input(event);
+                }
+            }
+        }
+        return;
+    }
+
     if let Event::Key(key) = event {
-        match key.code {
-            KeyCode::Esc => {
-                app.should_quit = true;
+        // Plain keys are typed into the connection string instead.
+        if app.keymap.resolve(&key, |action, chord| action == Action::Quit && !chord.is_printable()).is_some() {
+            app.should_quit = true;
+            return;
+        }
+        if app.connection_focus == ConnectionFocus::Form && key.code != KeyCode::Enter {
+            match key.code {
+                KeyCode::Tab | KeyCode::Down => {
+                    if !app.connection_form.next_field() {
+                        app.toggle_connection_focus();
+                    }
+                }
+                KeyCode::BackTab | KeyCode::Up => {
+                    if !app.connection_form.prev_field() {
+                        app.connection_focus = ConnectionFocus::NewInput;
+                    }
+                }
+                _ => app.connection_form.input(key),
             }
+            return;
+        }
+        match key.code {
             KeyCode::Tab => {
                 app.toggle_connection_focus();
             }
+            KeyCode::Enter if app.connection_focus == ConnectionFocus::RecentList && app.toggle_recent_group() => {}
             KeyCode::Enter => {
                 let conn_str = match app.connection_focus {
                     ConnectionFocus::RecentList => {
@@ -153,43 +415,62 @@ async fn handle_connection_event(app: &mut App<'_>, storage: &Storage, event: Ev
                         let input = app.connection_input.lines().join("");
                         if input.is_empty() { None } else { Some(input) }
                     }
-                };
-
-                if let Some(conn_str) = conn_str {
-                    match DatabaseConnection::connect(&conn_str).await {
-                        Ok(conn) => {
-                            match conn.get_tables().await {
-                                Ok(tables) => {
-                                    app.set_tables(tables);
-                                }
-                                Err(e) => {
-                                    app.connection_error = Some(e.to_string());
-                                    return;
-                                }
-                            }
-                            let _ = storage.add_connection(&conn_str).await;
-
-                            app.connection = Some(conn);
-                            app.connection_error = None;
-                            app.state = AppState::Browser;
-                        }
+                    ConnectionFocus::Form => match app.connection_form.connection_string() {
+                        Ok(conn_str) => Some(conn_str),
                         Err(e) => {
                             app.connection_error = Some(e.to_string());
+                            None
                         }
-                    }
+                    },
+                };
+
+                if let Some(conn_str) = conn_str {
+                    connect(app, storage, conn_str).await;
                 }
             }
             KeyCode::Delete | KeyCode::Backspace if key.modifiers.contains(KeyModifiers::CONTROL) => {
-                if app.connection_focus == ConnectionFocus::RecentList {
-                    if let Some(conn) = app.get_selected_recent_connection() {
-                        let id = conn.id;
-                        let _ = storage.delete_connection(id).await;
-                        if let Ok(recent) = storage.get_recent_connections(10).await {
-                            app.set_recent_connections(recent);
-                        }
+                if app.connection_focus == ConnectionFocus::RecentList
+                    && let Some(conn) = app.get_selected_recent_connection()
+                {
+                    let id = conn.id;
+                    let _ = storage.delete_connection(id).await;
+                    if let Ok(recent) = storage.get_recent_connections(10).await {
+                        app.set_recent_connections(recent);
+                    }
+                }
+            }
+            KeyCode::Char('p') if app.connection_focus == ConnectionFocus::RecentList => {
+                if let Some(conn) = app.get_selected_recent_connection() {
+                    let id = conn.id;
+                    if let Err(e) = storage.toggle_pin(id).await {
+                        app.connection_error = Some(format!("Pinning failed: {e}"));
+                    } else {
+                        reload_recent_connections(app, storage, id).await;
                     }
                 }
             }
+            KeyCode::Char('g') if app.connection_focus == ConnectionFocus::RecentList => {
+                if let Some(conn) = app.get_selected_recent_connection() {
+                    let mut prompt = Prompt::new(
+                        PromptAction::ConnectionGroup { id: conn.id },
+                        "Move to group",
+                        "Leave it empty to take the connection out of its group",
+                    );
+                    prompt.input.insert_str(conn.group.as_deref().unwrap_or_default());
+                    app.connection_prompt = Some(prompt);
+                }
+            }
+            KeyCode::F(2) if app.connection_focus == ConnectionFocus::RecentList => {
+                if let Some(conn) = app.get_selected_recent_connection() {
+                    let mut prompt = Prompt::new(
+                        PromptAction::RenameConnection { id: conn.id },
+                        "Rename connection",
+                        "Leave it empty to go back to the generated name",
+                    );
+                    prompt.input.insert_str(&conn.display_name);
+                    app.connection_prompt = Some(prompt);
+                }
+            }
             KeyCode::Down if app.connection_focus == ConnectionFocus::RecentList => {
                 app.select_next_recent();
             }
@@ -205,11 +486,103 @@ async fn handle_connection_event(app: &mut App<'_>, storage: &Storage, event: Ev
     }
 }
 
-async fn handle_browser_event(app: &mut App<'_>, event: Event) {
+/// Reads the recent list again after a change, keeping the connection
+/// with `id` selected.
+async fn reload_recent_connections(app: &mut App<'_>, storage: &Storage, id: i64) {
+    if let Ok(recent) = storage.get_recent_connections(10).await {
+        app.set_recent_connections(recent);
+        app.select_recent_connection(id);
+    }
+}
+
+async fn connect(app: &mut App<'_>, storage: &Storage, conn_str: String) {
+    let tunnel = match db::SshTunnel::open(&conn_str).await {
+        Ok(tunnel) => tunnel,
+        Err(e) => {
+            app.connection_error = Some(e.to_string());
+            return;
+        }
+    };
+    let target = tunnel.as_ref().map_or(conn_str.as_str(), |t| t.connection_string());
+
+    match DatabaseConnection::connect(target, &storage.connect_settings().await).await {
+        Ok(conn) => {
+            match conn.get_schemas().await {
+                Ok(schemas) => {
+                    app.set_schemas(&schemas);
+                }
+                Err(e) => {
+                    app.connection_error = Some(e.to_string());
+                    return;
+                }
+            }
+            let _ = storage.add_connection(&conn_str).await;
+
+            app.server_version = conn.server_version().await.ok();
+            app.connection_name = Storage::generate_display_name(&conn_str);
+            app.connection = Some(conn);
+            app.transaction = None;
+            app.connection_string = Some(conn_str);
+            app.tunnel = tunnel;
+            app.connection_error = None;
+            app.state = AppState::Browser;
+            restore_read_only(app, storage).await;
+            restore_query(app, storage).await;
+        }
+        Err(e) => {
+            if db::is_auth_failure(&e) {
+                app.password_retry = Some(PasswordRetry::new(conn_str));
+            }
+            app.connection_error = Some(e.to_string());
+        }
+    }
+}
+
+/// Last choice for this connection; production connections start read-only.
+async fn restore_read_only(app: &mut App<'_>, storage: &Storage) {
+    let Some(conn_str) = &app.connection_string else {
+        return;
+    };
+    app.read_only = match storage.get_setting(&Storage::read_only_key(conn_str)).await {
+        Ok(Some(value)) => value == "1",
+        _ => app.is_production(),
+    };
+}
+
+/// Puts the query saved at the end of the last session back in the editor.
+async fn restore_query(app: &mut App<'_>, storage: &Storage) {
+    let Some(conn_str) = &app.connection_string else {
+        return;
+    };
+    let Ok(Some(query)) = storage.get_setting(&Storage::query_key(conn_str)).await else {
+        return;
+    };
+    if query.trim().is_empty() {
+        return;
+    }
+    app.set_query_text(&query);
+    if app.config.auto_run_restored_query {
+        execute_query(app).await;
+    } else {
+        app.set_status("Restored last query (not run)");
+    }
+}
+
+async fn handle_browser_event(app: &mut App<'_>, storage: &Storage, event: Event) {
     match event {
+        Event::Mouse(_) if app.modal.is_some() => {}
         Event::Mouse(mouse) => {
             match mouse.kind {
                 MouseEventKind::Down(MouseButton::Left) | MouseEventKind::Drag(MouseButton::Left) => {
+                    if matches!(mouse.kind, MouseEventKind::Down(_)) && app.on_sidebar_edge(mouse.column, mouse.row) {
+                        app.resizing_sidebar = true;
+                        return;
+                    }
+                    if app.resizing_sidebar {
+                        app.resize_sidebar(mouse.column);
+                        return;
+                    }
+
                     if let Some(ratio) = app.results_state.scrollbar_region.hit_test_vertical(mouse.column, mouse.row) {
                         let total_rows = app.query_result.rows.len();
                         app.results_state.scroll_to_vertical_ratio(ratio, total_rows);
@@ -224,6 +597,22 @@ async fn handle_browser_event(app: &mut App<'_>, event: Event) {
                     }
 
                     if matches!(mouse.kind, MouseEventKind::Down(MouseButton::Left)) {
+                        if let Some(col) = app.results_state.header_region.hit_test(mouse.column, mouse.row) {
+                            app.cycle_sort(col);
+                            app.focus = Focus::Results;
+                            return;
+                        }
+
+                        if let Some(button) = app
+                            .pagination_region
+                            .as_ref()
+                            .map(|r| r.hit_test(mouse.column, mouse.row))
+                            .filter(|b| *b != PageButton::None)
+                        {
+                            handle_page_button(app, button).await;
+                            return;
+                        }
+
                         if let Some(ref region) = app.button_region {
                             let button = region.hit_test(mouse.column, mouse.row);
                             match button {
@@ -231,6 +620,10 @@ async fn handle_browser_event(app: &mut App<'_>, event: Event) {
                                     execute_query(app).await;
                                     return;
                                 }
+                                QueryButton::Format => {
+                                    app.format_query();
+                                    return;
+                                }
                                 QueryButton::Clear => {
                                     app.clear_query();
                                     return;
@@ -239,19 +632,32 @@ async fn handle_browser_event(app: &mut App<'_>, event: Event) {
                                     copy_query_to_clipboard(app);
                                     return;
                                 }
+                                QueryButton::Export => {
+                                    open_export_dialog(app, ExportDialog::new());
+                                    return;
+                                }
                                 QueryButton::None => {}
                             }
                         }
 
-                        if app.handle_sidebar_click(mouse.column, mouse.row) {
-                            return;
+                        if app.handle_sidebar_click(mouse.column, mouse.row) == SidebarClick::OpenTable
+                            && let Some((schema, table)) = app.tree_state.get_selected_table()
+                        {
+                            let (schema, table) = (schema.to_string(), table.to_string());
+                            open_table(app, &schema, &table).await;
                         }
                     }
                 }
+                MouseEventKind::Up(MouseButton::Left) => app.resizing_sidebar = false,
                 MouseEventKind::Moved => {
                     if let Some(ref region) = app.button_region {
                         app.hovered_button = region.hit_test(mouse.column, mouse.row);
                     }
+                    app.hovered_page_button = app
+                        .pagination_region
+                        .as_ref()
+                        .map(|r| r.hit_test(mouse.column, mouse.row))
+                        .unwrap_or(PageButton::None);
                 }
                 MouseEventKind::ScrollUp => {
                     if app.focus == Focus::Results {
@@ -271,10 +677,31 @@ async fn handle_browser_event(app: &mut App<'_>, event: Event) {
             }
         }
         Event::Key(key) => {
-            if key.code == KeyCode::Esc {
-                app.should_quit = true;
-            } else if key.code == KeyCode::Tab {
-                app.cycle_focus();
+            if app.modal.is_some() {
+                handle_modal_key(app, storage, key).await;
+            } else if app.focus == Focus::Sidebar && app.tree_state.searching {
+                handle_sidebar_search_key(app, key);
+            } else if app.focus == Focus::Sidebar && app.tree_state.filtering {
+                handle_sidebar_filter_key(app, key);
+            } else if app.focus == Focus::Results && app.results_state.searching {
+                handle_results_search_key(app, key);
+            } else if app.focus == Focus::Query
+                && ((app.completion.is_some() && handle_completion_key(app, key))
+                    || (app.config.vim_mode && vim_input(app, key)))
+            {
+            } else if app.query_running
+                && (key.code == KeyCode::Esc
+                    || (key.code == KeyCode::Char('c') && key.modifiers.contains(KeyModifiers::CONTROL)))
+            {
+                cancel_running_query(app);
+            } else if key.code == KeyCode::Esc && app.focus == Focus::Results && app.query_error.is_some() {
+                app.dismiss_query_error();
+            } else if key.code == KeyCode::Esc && app.focus == Focus::Results && !app.results_state.search.is_empty() {
+                app.clear_results_filter();
+            } else if key.code == KeyCode::Esc && app.focus == Focus::Sidebar && !app.tree_state.filter.is_empty() {
+                app.tree_state.filter.clear();
+            } else if let Some(action) = app.keymap.resolve(&key, |action, chord| action_available(app.focus, action, chord)) {
+                run_action(app, storage, action).await;
             } else if app.focus == Focus::QueryButtons {
                 match key.code {
                     KeyCode::Left => {
@@ -286,8 +713,10 @@ async fn handle_browser_event(app: &mut App<'_>, event: Event) {
                     KeyCode::Enter => {
                         match app.selected_button {
                             QueryButton::Run => execute_query(app).await,
+                            QueryButton::Format => app.format_query(),
                             QueryButton::Clear => app.clear_query(),
                             QueryButton::Copy => copy_query_to_clipboard(app),
+                            QueryButton::Export => open_export_dialog(app, ExportDialog::new()),
                             QueryButton::None => {}
                         }
                     }
@@ -302,61 +731,176 @@ async fn handle_browser_event(app: &mut App<'_>, event: Event) {
                         KeyCode::Up | KeyCode::Char('k') => {
                             app.tree_state.select_prev();
                         }
-                        KeyCode::Enter | KeyCode::Right => {
+                        KeyCode::Enter => {
                             if app.tree_state.is_selected_schema() {
                                 app.tree_state.toggle_selected();
                             } else if let Some((schema, table)) = app.tree_state.get_selected_table() {
-                                let query = format!(
-                                    "SELECT * FROM {}.{} LIMIT 100",
-                                    schema, table
-                                );
-                                app.query_input = tui_textarea::TextArea::from(vec![query.clone()]);
-                                app.query_input.set_cursor_line_style(ratatui::style::Style::default());
-
-                                if let Some(conn) = &app.connection {
-                                    match conn.execute_query(&query).await {
-                                        Ok(result) => {
-                                            app.set_query_result(result);
-                                        }
-                                        Err(e) => {
-                                            app.set_query_result(db::QueryResult {
-                                                columns: vec!["Error".to_string()],
-                                                rows: vec![vec![e.to_string()]],
-                                                affected_rows: 0,
-                                            });
-                                        }
-                                    }
-                                }
-                                app.focus = Focus::Results;
+                                let (schema, table) = (schema.to_string(), table.to_string());
+                                open_table(app, &schema, &table).await;
                             }
                         }
-                        KeyCode::Left => {
-                            if app.tree_state.is_selected_schema() {
-                                app.tree_state.toggle_selected();
+                        KeyCode::Char('e') => {
+                            if let Some((schema, table)) = app.tree_state.get_selected_table() {
+                                let (schema, table) = (schema.to_string(), table.to_string());
+                                edit_table_select(app, &schema, &table).await;
+                            }
+                        }
+                        KeyCode::Char('i') => {
+                            if let Some((schema, table)) = app.tree_state.get_selected_table() {
+                                let (schema, table) = (schema.to_string(), table.to_string());
+                                edit_table_insert(app, &schema, &table).await;
+                            }
+                        }
+                        KeyCode::Char('d') => {
+                            if let Some((schema, table)) = app.tree_state.get_selected_table() {
+                                let (schema, table) = (schema.to_string(), table.to_string());
+                                show_table_ddl(app, &schema, &table).await;
                             }
                         }
+                        KeyCode::Right => app.tree_state.expand_selected(),
+                        KeyCode::Left => app.tree_state.collapse_selected(),
                         KeyCode::Char(' ') => {
                             app.tree_state.toggle_selected();
                         }
+                        KeyCode::Char('/') => {
+                            app.tree_state.search.clear();
+                            app.tree_state.searching = true;
+                        }
+                        KeyCode::Char('f') => app.tree_state.filtering = true,
+                        KeyCode::Char('n') => app.tree_state.jump_to_match(true, false),
+                        KeyCode::Char('N') => app.tree_state.jump_to_match(false, false),
+                        KeyCode::Char('s') => show_sessions(app).await,
+                        KeyCode::Char('r') => refresh_sidebar(app),
+                        KeyCode::Char('a') => {
+                            if matches!(app.connection, Some(DatabaseConnection::Sqlite(_))) {
+                                app.modal = Some(Modal::Prompt(Box::new(Prompt::new(
+                                    PromptAction::AttachDatabase,
+                                    "Attach SQLite Database",
+                                    "path/to/file.db [AS alias]",
+                                ))));
+                            } else {
+                                app.set_error_status("ATTACH is only available for SQLite");
+                            }
+                        }
                         _ => {}
                     },
                     Focus::Query => {
-                        app.query_input.input(Event::Key(key));
+                        if key.code == KeyCode::Char(' ') && key.modifiers.contains(KeyModifiers::CONTROL) {
+                            update_completion(app, true).await;
+                        } else {
+                            let completing = app.completion.is_some();
+                            app.query_input.input(Event::Key(key));
+                            if completing || key.code == KeyCode::Char('.') {
+                                update_completion(app, false).await;
+                            }
+                        }
                     }
                     Focus::QueryButtons => {}
                     Focus::Results => match key.code {
-                        KeyCode::Down | KeyCode::Char('j') => {
+                        KeyCode::Char('j') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                            open_export_dialog(app, ExportDialog::with_format(export::ExportFormat::Json));
+                        }
+                        KeyCode::Char('y') if app.query_error.is_some() => {
+                            let error = app.query_error.clone().unwrap_or_default();
+                            copy_to_clipboard(app, &error, "Copied error to clipboard");
+                        }
+                        KeyCode::Char('/') if !app.query_result.columns.is_empty() => {
+                            app.results_state.searching = true;
+                        }
+                        KeyCode::Char('n') if !app.results_state.search.is_empty() => {
                             app.results_state.select_next(app.query_result.rows.len());
                         }
-                        KeyCode::Up | KeyCode::Char('k') => {
+                        KeyCode::Char('N') if !app.results_state.search.is_empty() => {
                             app.results_state.select_prev(app.query_result.rows.len());
                         }
-                        KeyCode::Left | KeyCode::Char('h') => {
-                            app.results_state.scroll_left();
+                        KeyCode::Char(']') => page_editor_query(app, PageButton::Next).await,
+                        KeyCode::Char('[') => page_editor_query(app, PageButton::Prev).await,
+                        KeyCode::Char('s') if app.sessions_view => show_sessions(app).await,
+                        KeyCode::Char('s') if !app.query_result.columns.is_empty() => {
+                            let col = app.results_state.selected_col;
+                            if let Some(column) = app.query_result.columns.get(col) {
+                                app.modal = Some(Modal::ColumnStats {
+                                    column: column.clone(),
+                                    stats: ui::column_stats(&app.query_result.rows, col),
+                                });
+                            }
+                        }
+                        KeyCode::Char('x') if app.sessions_view => confirm_kill_session(app),
+                        KeyCode::Enter if !app.query_result.rows.is_empty() => {
+                            app.modal = Some(Modal::RowDetail { format: RowFormat::Json, scroll: 0 });
+                        }
+                        KeyCode::Char('v') if !app.query_result.rows.is_empty() => {
+                            app.modal = Some(Modal::CellDetail { scroll: 0 });
+                        }
+                        KeyCode::Char('i') if !app.query_result.rows.is_empty() => copy_row_as_insert(app),
+                        KeyCode::Char('m') if !app.query_result.columns.is_empty() => {
+                            let table = export::to_markdown_table(&visible_result(app));
+                            let rows = app.query_result.rows.len();
+                            copy_to_clipboard(app, &table, format!("Copied {rows} rows as a Markdown table"));
+                        }
+                        KeyCode::Char('e')
+                            if !key.modifiers.contains(KeyModifiers::CONTROL) && !app.query_result.rows.is_empty() =>
+                        {
+                            edit_cell(app).await
                         }
-                        KeyCode::Right | KeyCode::Char('l') => {
-                            let max_scroll = app.results_state.column_widths.iter().sum::<u16>() as usize;
-                            app.results_state.scroll_right(max_scroll);
+                        KeyCode::Char('c') => {
+                            if let Some(tsv) = export::row_to_tsv(&app.query_result, app.results_state.selected_row) {
+                                copy_to_clipboard(app, &tsv, "Row copied");
+                            }
+                        }
+                        KeyCode::Char('d') => {
+                            app.config.density = app.config.density.toggled();
+                        }
+                        KeyCode::Char('g') | KeyCode::Home if app.query_error.is_some() => app.error_scroll = 0,
+                        KeyCode::Char('G') | KeyCode::End if app.query_error.is_some() => {
+                            app.scroll_query_error(isize::MAX)
+                        }
+                        KeyCode::PageUp if app.query_error.is_some() => app.scroll_query_error(-10),
+                        KeyCode::PageDown if app.query_error.is_some() => app.scroll_query_error(10),
+                        KeyCode::Char('g') | KeyCode::Home => {
+                            app.results_state.select_first();
+                        }
+                        KeyCode::Char('G') | KeyCode::End => {
+                            app.results_state.select_last(app.query_result.rows.len());
+                        }
+                        KeyCode::PageUp => {
+                            let height = app.results_state.scrollbar_region.visible_height;
+                            app.results_state.page_up(height);
+                        }
+                        KeyCode::PageDown => {
+                            let height = app.results_state.scrollbar_region.visible_height;
+                            app.results_state.page_down(height, app.query_result.rows.len());
+                        }
+                        KeyCode::Char('r') => {
+                            app.results_state.record_view = !app.results_state.record_view;
+                            app.results_state.record_scroll = 0;
+                        }
+                        KeyCode::Char('H') if !app.query_result.columns.is_empty() => {
+                            let col = app.results_state.selected_col;
+                            let count = app.query_result.columns.len();
+                            if !app.results_state.toggle_column(col, count) {
+                                app.set_error_status("The last visible column can't be hidden");
+                            }
+                        }
+                        KeyCode::Char('V') if !app.query_result.columns.is_empty() => {
+                            app.modal = Some(Modal::Columns { selected: app.results_state.selected_col });
+                        }
+                        KeyCode::Char('F') => {
+                            app.results_state.freeze_first_col = !app.results_state.freeze_first_col;
+                        }
+                        KeyCode::Char('T') => {
+                            app.results_state.show_column_types = !app.results_state.show_column_types;
+                        }
+                        KeyCode::Char('#') => {
+                            app.results_state.show_row_numbers = !app.results_state.show_row_numbers;
+                        }
+                        KeyCode::Char('+') | KeyCode::Char('=') => app.results_state.widen_cells(),
+                        KeyCode::Char('-') => app.results_state.narrow_cells(),
+                        KeyCode::Char('t') if !app.query_result.columns.is_empty() => {
+                            app.modal = Some(Modal::TypesLegend { scroll: 0 });
+                        }
+                        KeyCode::Char('e') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                            open_export_dialog(app, ExportDialog::new());
                         }
                         _ => {}
                     },
@@ -367,58 +911,1402 @@ async fn handle_browser_event(app: &mut App<'_>, event: Event) {
     }
 }
 
-async fn execute_query(app: &mut App<'_>) {
-    let query = app.get_query_text();
-    if query.trim().is_empty() {
-        return;
+/// Whether a chord bound to `action` applies with `focus`. Plain keys never
+/// fire from the editor, where they are typed instead.
+fn action_available(focus: Focus, action: Action, chord: &KeyChord) -> bool {
+    if focus == Focus::Query && chord.is_printable() {
+        return false;
     }
-    if let Some(conn) = &app.connection {
-        match conn.execute_query(&query).await {
-            Ok(result) => {
-                app.set_query_result(result);
+    match action.context() {
+        KeyContext::Global => true,
+        KeyContext::Editor => matches!(focus, Focus::Query | Focus::QueryButtons),
+        KeyContext::Results => focus == Focus::Results,
+        KeyContext::Sidebar => focus == Focus::Sidebar,
+    }
+}
+
+async fn run_action(app: &mut App<'_>, storage: &Storage, action: Action) {
+    match action {
+        Action::Quit => app.should_quit = true,
+        Action::NextFocus => app.cycle_focus(),
+        Action::Help => app.modal = Some(Modal::Help),
+        Action::ToggleReadOnly => {
+            app.read_only = !app.read_only;
+            app.set_status(if app.read_only { "Read-only mode on" } else { "Read-only mode off" });
+        }
+        Action::BeginTransaction => begin_transaction(app).await,
+        Action::Commit => end_transaction(app, true).await,
+        Action::Rollback => end_transaction(app, false).await,
+        Action::OpenPalette => app.modal = Some(Modal::Palette(Box::new(Palette::new()))),
+        Action::ToggleSidebar => app.toggle_sidebar(),
+        Action::SavedQueries => match storage.list_saved_queries().await {
+            Ok(items) => app.modal = Some(Modal::SavedQueries(SavedQueryList::new(items))),
+            Err(e) => app.set_error_status(format!("Loading saved queries failed: {e}")),
+        },
+        Action::RunQuery => execute_query(app).await,
+        Action::FormatQuery => app.format_query(),
+        Action::ClearQuery => app.clear_query(),
+        Action::CopyQuery => copy_query_to_clipboard(app),
+        Action::CountQuery => run_wrapped_query(app, "SELECT COUNT(*) FROM q").await,
+        Action::SampleQuery => {
+            let outer = app.limit_syntax().apply("SELECT * FROM q", app.config.page_size, 0);
+            run_wrapped_query(app, &outer).await;
+        }
+        Action::SaveQuery => {
+            if app.get_query_text().trim().is_empty() {
+                app.set_error_status("Nothing to save; the editor is empty");
+            } else {
+                app.modal = Some(Modal::Prompt(Box::new(Prompt::new(
+                    PromptAction::SaveQuery,
+                    "Save Query",
+                    "Name (an existing name is replaced)",
+                ))));
             }
-            Err(e) => {
-                app.set_query_result(db::QueryResult {
-                    columns: vec!["Error".to_string()],
-                    rows: vec![vec![e.to_string()]],
-                    affected_rows: 0,
-                });
+        }
+        // With an error showing, the row keys scroll the error instead.
+        Action::SelectNextRow if app.query_error.is_some() => app.scroll_query_error(1),
+        Action::SelectPrevRow if app.query_error.is_some() => app.scroll_query_error(-1),
+        // The record view scrolls its lines and flips between rows sideways.
+        Action::SelectNextRow if app.results_state.record_view => app.results_state.record_scroll += 1,
+        Action::SelectPrevRow if app.results_state.record_view => {
+            app.results_state.record_scroll = app.results_state.record_scroll.saturating_sub(1);
+        }
+        Action::SelectNextColumn if app.results_state.record_view => {
+            app.results_state.select_next(app.query_result.rows.len());
+            app.results_state.record_scroll = 0;
+        }
+        Action::SelectPrevColumn if app.results_state.record_view => {
+            app.results_state.select_prev(app.query_result.rows.len());
+            app.results_state.record_scroll = 0;
+        }
+        Action::SelectNextRow => app.results_state.select_next(app.query_result.rows.len()),
+        Action::SelectPrevRow => app.results_state.select_prev(app.query_result.rows.len()),
+        Action::SelectNextColumn => app.results_state.select_next_col(),
+        Action::SelectPrevColumn => app.results_state.select_prev_col(),
+    }
+}
+
+fn handle_sidebar_search_key(app: &mut App<'_>, key: KeyEvent) {
+    let tree = &mut app.tree_state;
+    match key.code {
+        KeyCode::Esc => {
+            tree.search.clear();
+            tree.searching = false;
+        }
+        KeyCode::Enter => tree.searching = false,
+        KeyCode::Backspace => {
+            tree.search.pop();
+            tree.jump_to_match(true, true);
+        }
+        KeyCode::Char(c) => {
+            tree.search.push(c);
+            tree.jump_to_match(true, true);
+        }
+        _ => {}
+    }
+}
+
+fn handle_sidebar_filter_key(app: &mut App<'_>, key: KeyEvent) {
+    let tree = &mut app.tree_state;
+    match key.code {
+        KeyCode::Esc => {
+            tree.filter.clear();
+            tree.filtering = false;
+        }
+        KeyCode::Enter => tree.filtering = false,
+        KeyCode::Backspace => {
+            tree.filter.pop();
+            tree.select_visible();
+        }
+        KeyCode::Char(c) => {
+            tree.filter.push(c);
+            tree.select_visible();
+        }
+        _ => {}
+    }
+}
+
+fn handle_results_search_key(app: &mut App<'_>, key: KeyEvent) {
+    match key.code {
+        KeyCode::Esc => app.clear_results_filter(),
+        KeyCode::Enter => {
+            app.results_state.searching = false;
+            if app.results_state.search.is_empty() {
+                app.clear_results_filter();
             }
         }
+        KeyCode::Backspace => {
+            app.results_state.search.pop();
+            app.refresh_rows();
+        }
+        KeyCode::Char(c) => {
+            app.results_state.search.push(c);
+            app.refresh_rows();
+        }
+        _ => {}
     }
 }
 
-fn copy_query_to_clipboard(app: &App<'_>) {
-    let query = app.get_query_text();
-    if !query.is_empty() {
-        #[cfg(target_os = "macos")]
-        {
-            use std::process::{Command, Stdio};
-            if let Ok(mut child) = Command::new("pbcopy")
-                .stdin(Stdio::piped())
-                .spawn()
-            {
-                if let Some(stdin) = child.stdin.as_mut() {
-                    use std::io::Write;
-                    let _ = stdin.write_all(query.as_bytes());
+async fn handle_modal_key(app: &mut App<'_>, storage: &Storage, key: KeyEvent) {
+    match &mut app.modal {
+        Some(Modal::Help) => {
+            if matches!(key.code, KeyCode::Esc | KeyCode::Char('?') | KeyCode::F(1)) {
+                app.modal = None;
+            }
+        }
+        Some(Modal::Export(dialog)) => match (dialog.step, key.code) {
+            (_, KeyCode::Esc) => app.modal = None,
+            (ExportStep::Path, KeyCode::Enter) => {
+                let format = dialog.format();
+                let destination = dialog.destination();
+                let path = config::expand_home(dialog.path().trim());
+                app.modal = None;
+                let result = if destination == ExportDestination::StreamFile {
+                    stream_export(app, format, &path).await
+                } else {
+                    export::export_to_file(&visible_result(app), format, &path)
+                        .map(|()| app.query_result.rows.len() as u64)
+                };
+                match result {
+                    Ok(rows) => app.set_status(format!("Exported {rows} rows to {}", path.display())),
+                    Err(e) => app.set_error_status(format!("Export failed: {e}")),
+                }
+            }
+            (ExportStep::Path, _) => {
+                dialog.path_input.input(Event::Key(key));
+            }
+            (_, KeyCode::Down | KeyCode::Char('j')) => dialog.select_next(),
+            (_, KeyCode::Up | KeyCode::Char('k')) => dialog.select_prev(),
+            (ExportStep::Format, KeyCode::Enter) => dialog.step = ExportStep::Destination,
+            (ExportStep::Destination, KeyCode::Enter) => match dialog.destination() {
+                ExportDestination::Clipboard => {
+                    let format = dialog.format();
+                    app.modal = None;
+                    let text = format.render(&visible_result(app));
+                    let rows = app.query_result.rows.len();
+                    copy_to_clipboard(app, &text, format!("Copied {rows} rows as {} to clipboard", format.label()));
+                }
+                ExportDestination::File | ExportDestination::StreamFile => dialog.enter_path_step(),
+            },
+            _ => {}
+        },
+        Some(Modal::TypesLegend { scroll }) => match key.code {
+            KeyCode::Esc | KeyCode::Char('t') => app.modal = None,
+            KeyCode::Down | KeyCode::Char('j') => {
+                *scroll = (*scroll + 1).min(app.query_result.columns.len().saturating_sub(1));
+            }
+            KeyCode::Up | KeyCode::Char('k') => *scroll = scroll.saturating_sub(1),
+            _ => {}
+        },
+        Some(Modal::RowDetail { format, scroll }) => match key.code {
+            KeyCode::Esc | KeyCode::Enter => app.modal = None,
+            KeyCode::Char('f') | KeyCode::Tab => {
+                *format = format.toggled();
+                *scroll = 0;
+            }
+            KeyCode::Char('y') => {
+                let text = format.render(&app.query_result, app.results_state.selected_row);
+                let label = format.label();
+                copy_to_clipboard(app, &text, format!("Copied row as {label} to clipboard"));
+            }
+            KeyCode::Down | KeyCode::Char('j') => {
+                let lines = format
+                    .render(&app.query_result, app.results_state.selected_row)
+                    .lines()
+                    .count();
+                *scroll = (*scroll + 1).min(lines.saturating_sub(1));
+            }
+            KeyCode::Up | KeyCode::Char('k') => *scroll = scroll.saturating_sub(1),
+            _ => {}
+        },
+        Some(Modal::CellDetail { scroll }) => match key.code {
+            KeyCode::Esc | KeyCode::Char('v') => app.modal = None,
+            KeyCode::Char('y') => {
+                let state = &app.results_state;
+                let value = app
+                    .query_result
+                    .rows
+                    .get(state.selected_row)
+                    .and_then(|r| r.get(state.selected_col))
+                    .map(|c| c.as_deref().unwrap_or("NULL").to_string())
+                    .unwrap_or_default();
+                copy_to_clipboard(app, &value, "Copied cell to clipboard");
+            }
+            KeyCode::Down | KeyCode::Char('j') => *scroll += 1,
+            KeyCode::Up | KeyCode::Char('k') => *scroll = scroll.saturating_sub(1),
+            KeyCode::PageDown => *scroll += 10,
+            KeyCode::PageUp => *scroll = scroll.saturating_sub(10),
+            _ => {}
+        },
+        Some(Modal::Ddl { table, ddl, scroll }) => match key.code {
+            KeyCode::Esc | KeyCode::Char('d') => app.modal = None,
+            KeyCode::Char('y') => {
+                let (ddl, message) = (ddl.clone(), format!("Copied the CREATE statement of {table}"));
+                copy_to_clipboard(app, &ddl, message);
+            }
+            KeyCode::Down | KeyCode::Char('j') => *scroll += 1,
+            KeyCode::Up | KeyCode::Char('k') => *scroll = scroll.saturating_sub(1),
+            KeyCode::PageDown => *scroll += 10,
+            KeyCode::PageUp => *scroll = scroll.saturating_sub(10),
+            _ => {}
+        },
+        Some(Modal::Confirm(confirm)) => match key.code {
+            KeyCode::Char('y') | KeyCode::Char('Y') => {
+                let query = std::mem::take(&mut confirm.query);
+                let action = confirm.action;
+                app.modal = None;
+                match action {
+                    ConfirmAction::RunQuery => run_query(app, &query),
+                    ConfirmAction::KillSession => kill_session(app, &query).await,
+                    ConfirmAction::UpdateCell => update_cell(app, &query),
+                }
+            }
+            KeyCode::Esc | KeyCode::Char('n') | KeyCode::Char('N') => app.modal = None,
+            _ => {}
+        },
+        Some(Modal::Prompt(prompt)) => match key.code {
+            KeyCode::Esc => app.modal = None,
+            KeyCode::Enter => {
+                let action = prompt.action;
+                let value = prompt.value();
+                app.modal = None;
+                submit_prompt(app, storage, action, &value).await;
+            }
+            KeyCode::Char('n') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                if let PromptAction::EditCell { row, col, key_col } = prompt.action {
+                    app.modal = None;
+                    review_cell_update(app, row, col, key_col, None);
                 }
-                let _ = child.wait();
+            }
+            _ => {
+                prompt.input.input(Event::Key(key));
+            }
+        },
+        Some(Modal::ColumnStats { .. }) => {
+            if matches!(key.code, KeyCode::Esc | KeyCode::Char('s')) {
+                app.modal = None;
             }
         }
-        #[cfg(target_os = "linux")]
-        {
-            use std::process::{Command, Stdio};
-            if let Ok(mut child) = Command::new("xclip")
-                .args(["-selection", "clipboard"])
-                .stdin(Stdio::piped())
-                .spawn()
-            {
-                if let Some(stdin) = child.stdin.as_mut() {
-                    use std::io::Write;
-                    let _ = stdin.write_all(query.as_bytes());
+        Some(Modal::Columns { selected }) => {
+            let count = app.query_result.columns.len();
+            match key.code {
+                KeyCode::Esc | KeyCode::Char('V') => app.modal = None,
+                KeyCode::Down | KeyCode::Char('j') => *selected = (*selected + 1).min(count.saturating_sub(1)),
+                KeyCode::Up | KeyCode::Char('k') => *selected = selected.saturating_sub(1),
+                KeyCode::Char(' ') | KeyCode::Enter => {
+                    let col = *selected;
+                    if !app.results_state.toggle_column(col, count) {
+                        app.set_error_status("The last visible column can't be hidden");
+                    }
                 }
-                let _ = child.wait();
+                KeyCode::Char('a') => app.results_state.hidden_cols.clear(),
+                _ => {}
             }
         }
+        Some(Modal::SavedQueries(list)) => match key.code {
+            KeyCode::Esc => app.modal = None,
+            KeyCode::Down | KeyCode::Char('j') => list.select_next(),
+            KeyCode::Up | KeyCode::Char('k') => list.select_prev(),
+            KeyCode::Enter => {
+                let Some(query) = list.selected_item().map(|q| q.query_text.clone()) else {
+                    return;
+                };
+                app.modal = None;
+                app.pagination = None;
+                app.set_query_text(&query);
+                app.focus = Focus::Query;
+            }
+            KeyCode::Char('d') => {
+                let Some(saved) = list.selected_item().cloned() else {
+                    return;
+                };
+                if let Err(e) = storage.delete_saved_query(saved.id).await {
+                    app.set_error_status(format!("Deleting the query failed: {e}"));
+                    return;
+                }
+                list.items.retain(|q| q.id != saved.id);
+                list.selected = list.selected.min(list.items.len().saturating_sub(1));
+                app.set_status(format!("Deleted \"{}\"", saved.name));
+            }
+            _ => {}
+        },
+        Some(Modal::Params(form)) => match key.code {
+            KeyCode::Esc => app.modal = None,
+            KeyCode::Tab | KeyCode::Down => form.select_next(),
+            KeyCode::BackTab | KeyCode::Up => form.select_prev(),
+            KeyCode::Enter => {
+                let query = form.query.clone();
+                let values = form.values();
+                let params = values.iter().map(|v| (v != "NULL").then(|| v.clone())).collect();
+                app.param_values.insert(query.trim().to_string(), values);
+                app.modal = None;
+                start_query(app, &query, QueryKind::Editor, params);
+            }
+            _ => {
+                form.input_mut().input(Event::Key(key));
+            }
+        },
+        Some(Modal::Palette(palette)) => match key.code {
+            KeyCode::Esc => app.modal = None,
+            KeyCode::Down => {
+                let last = palette.matches(&app.tables).len().saturating_sub(1);
+                palette.selected = (palette.selected + 1).min(last);
+            }
+            KeyCode::Char('n') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                let last = palette.matches(&app.tables).len().saturating_sub(1);
+                palette.selected = (palette.selected + 1).min(last);
+            }
+            KeyCode::Up => palette.selected = palette.selected.saturating_sub(1),
+            KeyCode::Char('p') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                palette.selected = palette.selected.saturating_sub(1)
+            }
+            KeyCode::Enter => {
+                let matches = palette.matches(&app.tables);
+                let Some(m) = matches.get(palette.selected.min(matches.len().saturating_sub(1))) else {
+                    return;
+                };
+                let (schema, table) = (m.table.schema.clone(), m.table.name.clone());
+                app.modal = None;
+                open_table(app, &schema, &table).await;
+            }
+            _ => {
+                if palette.input.input(Event::Key(key)) {
+                    palette.selected = 0;
+                }
+            }
+        },
+        None => {}
+    }
+}
+
+async fn stream_export(app: &App<'_>, format: export::ExportFormat, path: &std::path::Path) -> anyhow::Result<u64> {
+    let query = app.get_query_text();
+    if !db::is_read_only(&query) {
+        anyhow::bail!("only read-only queries can be re-run for a streamed export");
+    }
+    if app.pool_held_by_transaction() {
+        anyhow::bail!("streamed exports can't run during a SQLite transaction");
+    }
+    let conn = app
+        .connection
+        .as_ref()
+        .ok_or_else(|| anyhow::anyhow!("not connected"))?;
+    export::stream_query_to_file(conn, &query, format, &app.results_state.hidden_cols, path).await
+}
+
+/// The result as the grid shows it, without hidden columns.
+fn visible_result<'r>(app: &'r App<'_>) -> Cow<'r, db::QueryResult> {
+    if app.results_state.hidden_cols.is_empty() {
+        Cow::Borrowed(&app.query_result)
+    } else {
+        Cow::Owned(export::without_columns(&app.query_result, &app.results_state.hidden_cols))
+    }
+}
+
+async fn submit_prompt(app: &mut App<'_>, storage: &Storage, action: PromptAction, value: &str) {
+    match action {
+        PromptAction::AttachDatabase => {
+            let value = value.trim();
+            if value.is_empty() {
+                return;
+            }
+            let (path, alias) = parse_attach_target(value);
+            let path = config::expand_home(&path);
+            if pool_held(app) {
+                return;
+            }
+            let Some(conn) = &app.connection else {
+                return;
+            };
+            let result = match conn.attach_database(&path.to_string_lossy(), &alias).await {
+                Ok(()) => conn.get_schemas().await,
+                Err(e) => Err(e),
+            };
+            match result {
+                Ok(schemas) => {
+                    app.set_schemas(&schemas);
+                    app.set_status(format!("Attached {} as {}", path.display(), alias));
+                }
+                Err(e) => app.set_error_status(format!("Attach failed: {e}")),
+            }
+        }
+        PromptAction::SaveQuery => {
+            let name = value.trim();
+            if name.is_empty() {
+                return;
+            }
+            match storage.save_query(name, &app.get_query_text()).await {
+                Ok(()) => app.set_status(format!("Saved query \"{name}\"")),
+                Err(e) => app.set_error_status(format!("Saving the query failed: {e}")),
+            }
+        }
+        PromptAction::RenameConnection { id } => {
+            let mut name = value.trim().to_string();
+            if name.is_empty()
+                && let Some(conn) = app.recent_connections.iter().find(|c| c.id == id)
+            {
+                name = Storage::generate_display_name(&conn.connection_string);
+            }
+            if let Err(e) = storage.rename_connection(id, &name).await {
+                app.connection_error = Some(format!("Renaming failed: {e}"));
+                return;
+            }
+            reload_recent_connections(app, storage, id).await;
+        }
+        PromptAction::ConnectionGroup { id } => {
+            let group = value.trim();
+            if let Err(e) = storage.set_connection_group(id, (!group.is_empty()).then_some(group)).await {
+                app.connection_error = Some(format!("Moving the connection failed: {e}"));
+                return;
+            }
+            // Open the group so the connection stays in view.
+            app.collapsed_groups.remove(if group.is_empty() { ui::UNGROUPED } else { group });
+            reload_recent_connections(app, storage, id).await;
+        }
+        PromptAction::EditCell { row, col, key_col } => review_cell_update(app, row, col, key_col, Some(value)),
+    }
+}
+
+/// Asks to confirm the UPDATE setting a cell to `value`; None sets NULL.
+fn review_cell_update(app: &mut App<'_>, row: usize, col: usize, key_col: usize, value: Option<&str>) {
+    let Some(conn) = &app.connection else {
+        return;
+    };
+    let Some(statement) = export::cell_update(&app.query_result, row, col, key_col, value, |name| {
+        conn.quote_identifier(name)
+    }) else {
+        return;
+    };
+    app.modal = Some(Modal::Confirm(Confirm {
+        title: "Update row".to_string(),
+        message: "Run this UPDATE?".to_string(),
+        query: statement,
+        action: ConfirmAction::UpdateCell,
+    }));
+}
+
+/// Vim-style editing in the SQL editor. Returns false for keys left to the
+/// usual handling: typing in insert mode and the global shortcuts.
+fn vim_input(app: &mut App<'_>, key: KeyEvent) -> bool {
+    use tui_textarea::CursorMove;
+
+    if app.vim_mode == VimMode::Insert {
+        if key.code != KeyCode::Esc {
+            return false;
+        }
+        app.vim_mode = VimMode::Normal;
+        if app.query_input.cursor().1 > 0 {
+            app.query_input.move_cursor(CursorMove::Back);
+        }
+        return true;
+    }
+    if key.modifiers.intersects(KeyModifiers::CONTROL | KeyModifiers::ALT)
+        || matches!(key.code, KeyCode::Tab | KeyCode::F(_))
+    {
+        return false;
+    }
+    let pending = app.vim_pending.take();
+    // Esc still cancels a running query, but never quits from normal mode.
+    if key.code == KeyCode::Esc {
+        return !app.query_running;
+    }
+
+    let (row, col) = app.query_input.cursor();
+    let line_len = app.query_input.lines()[row].chars().count();
+    let ta = &mut app.query_input;
+    match key.code {
+        KeyCode::Char('h') | KeyCode::Left if col > 0 => ta.move_cursor(CursorMove::Back),
+        KeyCode::Char('l') | KeyCode::Right if col + 1 < line_len => ta.move_cursor(CursorMove::Forward),
+        KeyCode::Char('j') | KeyCode::Down => ta.move_cursor(CursorMove::Down),
+        KeyCode::Char('k') | KeyCode::Up => ta.move_cursor(CursorMove::Up),
+        KeyCode::Char('w') => ta.move_cursor(CursorMove::WordForward),
+        KeyCode::Char('b') => ta.move_cursor(CursorMove::WordBack),
+        KeyCode::Char('x') if col < line_len => {
+            ta.delete_next_char();
+        }
+        KeyCode::Char('d') if pending == Some('d') => delete_editor_line(ta),
+        KeyCode::Char('d') => app.vim_pending = Some('d'),
+        KeyCode::Char('o') => {
+            ta.move_cursor(CursorMove::End);
+            ta.insert_newline();
+            app.vim_mode = VimMode::Insert;
+        }
+        KeyCode::Char('i') => app.vim_mode = VimMode::Insert,
+        KeyCode::Char('a') => {
+            if col < line_len {
+                ta.move_cursor(CursorMove::Forward);
+            }
+            app.vim_mode = VimMode::Insert;
+        }
+        _ => {}
+    }
+    true
+}
+
+/// Cuts the cursor's line, newline included, like vim's `dd`.
+fn delete_editor_line(ta: &mut tui_textarea::TextArea<'_>) {
+    use tui_textarea::CursorMove;
+
+    let row = ta.cursor().0;
+    let last = ta.lines().len() - 1;
+    ta.cancel_selection();
+    if row < last {
+        ta.move_cursor(CursorMove::Head);
+        ta.start_selection();
+        ta.move_cursor(CursorMove::Down);
+        ta.move_cursor(CursorMove::Head);
+    } else if row > 0 {
+        ta.move_cursor(CursorMove::Up);
+        ta.move_cursor(CursorMove::End);
+        ta.start_selection();
+        ta.move_cursor(CursorMove::Down);
+        ta.move_cursor(CursorMove::End);
+    } else {
+        ta.move_cursor(CursorMove::Head);
+        ta.start_selection();
+        ta.move_cursor(CursorMove::End);
+    }
+    ta.cut();
+    ta.move_cursor(CursorMove::Head);
+}
+
+/// Splits `path [AS alias]`, defaulting the alias to the file stem.
+fn parse_attach_target(value: &str) -> (String, String) {
+    // ASCII lowercasing keeps byte offsets valid for slicing `value`.
+    let lower = value.to_ascii_lowercase();
+    if let Some(pos) = lower.rfind(" as ") {
+        let alias = value[pos + 4..].trim();
+        if !alias.is_empty() {
+            return (value[..pos].trim().to_string(), alias.to_string());
+        }
+    }
+    let alias = std::path::Path::new(value)
+        .file_stem()
+        .map(|s| s.to_string_lossy().replace(|c: char| !c.is_alphanumeric() && c != '_', "_"))
+        .unwrap_or_else(|| "attached".to_string());
+    (value.to_string(), alias)
+}
+
+async fn open_table(app: &mut App<'_>, schema: &str, table: &str) {
+    let table = db::TableInfo {
+        schema: schema.to_string(),
+        name: table.to_string(),
+        kind: db::TableKind::Table,
+        row_count: None,
+    };
+    let pagination = app::Pagination::new(table, app.config.page_size, app.limit_syntax());
+    app.set_query_text(&pagination.query());
+    app.pagination = Some(pagination);
+
+    if app.config.auto_run_on_select {
+        execute_query(app).await;
+        app.focus = Focus::Results;
+    } else {
+        app.query_input.move_cursor(tui_textarea::CursorMove::End);
+        app.focus = Focus::Query;
+    }
+}
+
+/// The table's column names quoted for the engine, fetched unless cached.
+async fn quoted_columns(app: &mut App<'_>, schema: &str, table: &str) -> Option<Vec<String>> {
+    let conn = app.connection.clone()?;
+    let key = (schema.to_string(), table.to_string());
+    if !app.column_cache.contains_key(&key) {
+        if pool_held(app) {
+            return None;
+        }
+        match conn.get_columns(schema, table).await {
+            Ok(columns) => {
+                app.column_cache.insert(key.clone(), columns);
+            }
+            Err(e) => {
+                app.set_error_status(format!("Loading columns of {table} failed: {e}"));
+                return None;
+            }
+        }
+    }
+    Some(app.column_cache[&key].iter().map(|c| conn.quote_identifier(&c.name)).collect())
+}
+
+/// Puts a SELECT naming every column of the table in the editor, one per
+/// line so unwanted ones are easy to delete. Nothing is run.
+async fn edit_table_select(app: &mut App<'_>, schema: &str, table: &str) {
+    let Some(columns) = quoted_columns(app, schema, table).await else {
+        return;
+    };
+    let Some(conn) = &app.connection else {
+        return;
+    };
+    let mut columns: Vec<String> = columns.iter().map(|c| format!("    {c}")).collect();
+    if columns.is_empty() {
+        columns.push("    *".to_string());
+    }
+    let query = format!(
+        "SELECT\n{}\nFROM {}.{}\n{}",
+        columns.join(",\n"),
+        conn.quote_identifier(schema),
+        conn.quote_identifier(table),
+        conn.limit_syntax().clause(app.config.page_size, 0, false)
+    );
+    app.pagination = None;
+    app.set_query_text(&query);
+    app.query_input.move_cursor(tui_textarea::CursorMove::Top);
+    app.focus = Focus::Query;
+}
+
+/// Opens the CREATE statement of a table in a popup.
+async fn show_table_ddl(app: &mut App<'_>, schema: &str, table: &str) {
+    if pool_held(app) {
+        return;
+    }
+    let Some(conn) = &app.connection else {
+        return;
+    };
+    match conn.get_table_ddl(schema, table).await {
+        Ok(ddl) => {
+            app.modal = Some(Modal::Ddl {
+                table: format!("{schema}.{table}"),
+                ddl,
+                scroll: 0,
+            })
+        }
+        Err(e) => app.set_error_status(format!("Loading the CREATE statement failed: {e}")),
+    }
+}
+
+/// Puts an INSERT listing the table's columns in the editor, with the
+/// cursor inside the empty VALUES list. Nothing is run.
+async fn edit_table_insert(app: &mut App<'_>, schema: &str, table: &str) {
+    let Some(columns) = quoted_columns(app, schema, table).await else {
+        return;
+    };
+    let Some(conn) = &app.connection else {
+        return;
+    };
+    if columns.is_empty() {
+        app.set_error_status(format!("{table} has no columns to insert into"));
+        return;
+    }
+    let head = format!(
+        "INSERT INTO {}.{} ({}) VALUES (",
+        conn.quote_identifier(schema),
+        conn.quote_identifier(table),
+        columns.join(", ")
+    );
+    let cursor = head.chars().count();
+    let query = format!("{head}{});", vec![""; columns.len()].join(", "));
+    app.pagination = None;
+    app.set_query_text(&query);
+    app.query_input.move_cursor(tui_textarea::CursorMove::Jump(0, cursor as u16));
+    app.focus = Focus::Query;
+}
+
+/// Fetches a schema's tables on a task; they arrive in `finish_sidebar_load`.
+fn load_schema_tables(app: &mut App<'_>, schema: String) {
+    if pool_held(app) {
+        app.tree_state.cancel_loading();
+        return;
+    }
+    let Some(conn) = app.connection.clone() else {
+        app.tree_state.cancel_loading();
+        return;
+    };
+    let (tx, rx) = tokio::sync::oneshot::channel();
+    let task = tokio::spawn(async move {
+        let result = conn.get_tables_in_schema(&schema).await;
+        let _ = tx.send(SidebarLoad::Tables { schema, result });
+    });
+    app.start_sidebar_load(task.abort_handle(), rx);
+}
+
+/// Lists the schemas and tables again, e.g. after DDL, reloading what was
+/// expanded and keeping the selection where it can.
+fn refresh_sidebar(app: &mut App<'_>) {
+    if pool_held(app) {
+        return;
+    }
+    let Some(conn) = app.connection.clone() else {
+        return;
+    };
+    let snapshot = app.tree_state.snapshot();
+    let expanded_schemas = snapshot.schemas.clone();
+    let expanded_tables = snapshot.tables.clone();
+    let (tx, rx) = tokio::sync::oneshot::channel();
+    let task = tokio::spawn(async move {
+        let result = async {
+            let schemas = conn.get_schemas().await?;
+            let mut tables = Vec::new();
+            for schema in expanded_schemas.into_iter().filter(|s| schemas.contains(s)) {
+                let listed = conn.get_tables_in_schema(&schema).await?;
+                tables.push((schema, listed));
+            }
+            let mut columns = Vec::new();
+            for (schema, table) in expanded_tables {
+                let exists = tables
+                    .iter()
+                    .any(|(s, listed)| *s == schema && listed.iter().any(|t| t.name == table));
+                if exists {
+                    let listed = conn.get_columns(&schema, &table).await?;
+                    columns.push((schema, table, listed));
+                }
+            }
+            Ok(SidebarReload { schemas, tables, columns })
+        }
+        .await;
+        let _ = tx.send(SidebarLoad::Reload { snapshot, result });
+    });
+    app.start_sidebar_load(task.abort_handle(), rx);
+    app.set_status("Reloading sidebar…");
+}
+
+fn finish_sidebar_load(app: &mut App<'_>, load: SidebarLoad) {
+    match load {
+        SidebarLoad::Tables { schema, result: Ok(tables) } => {
+            app.tree_state.set_schema_tables(&schema, &tables);
+            app.tables.extend(tables);
+            fetch_row_counts(app, &schema);
+        }
+        SidebarLoad::Tables { schema, result: Err(e) } => {
+            app.tree_state.cancel_loading();
+            app.set_error_status(format!("Loading {schema} failed: {e}"));
+        }
+        SidebarLoad::Columns { schema, table, result: Ok(columns) } => {
+            app.tree_state.set_table_columns(&schema, &table, &columns);
+            app.column_cache.insert((schema, table), columns);
+        }
+        SidebarLoad::Columns { table, result: Err(e), .. } => {
+            app.tree_state.cancel_loading_columns();
+            app.set_error_status(format!("Loading columns of {table} failed: {e}"));
+        }
+        SidebarLoad::Reload { snapshot, result: Ok(reload) } => {
+            app.set_schemas(&reload.schemas);
+            for (schema, tables) in reload.tables {
+                app.tree_state.set_schema_tables(&schema, &tables);
+                app.tables.extend(tables);
+                fetch_row_counts(app, &schema);
+            }
+            for (schema, table, columns) in reload.columns {
+                app.tree_state.set_table_columns(&schema, &table, &columns);
+                app.column_cache.insert((schema, table), columns);
+            }
+            app.tree_state.restore_selection(&snapshot);
+            app.set_status("Sidebar reloaded");
+        }
+        SidebarLoad::Reload { result: Err(e), .. } => {
+            app.set_error_status(format!("Reloading schemas failed: {e}"));
+        }
+    }
+}
+
+/// Counts arrive on `app.row_counts_rx` so slow counts never block the sidebar.
+fn fetch_row_counts(app: &App<'_>, schema: &str) {
+    if app.pool_held_by_transaction() {
+        return;
+    }
+    let Some(conn) = app.connection.clone() else {
+        return;
+    };
+    let tx = app.row_counts_tx.clone();
+    let schema = schema.to_string();
+    tokio::spawn(async move {
+        if let Ok(counts) = conn.get_row_counts(&schema).await {
+            let _ = tx.send((schema, counts));
+        }
+    });
+}
+
+/// Fetches a table's columns on a task; they arrive in `finish_sidebar_load`.
+fn load_table_columns(app: &mut App<'_>, schema: String, table: String) {
+    if pool_held(app) {
+        app.tree_state.cancel_loading_columns();
+        return;
+    }
+    let Some(conn) = app.connection.clone() else {
+        app.tree_state.cancel_loading_columns();
+        return;
+    };
+    let (tx, rx) = tokio::sync::oneshot::channel();
+    let task = tokio::spawn(async move {
+        let result = conn.get_columns(&schema, &table).await;
+        let _ = tx.send(SidebarLoad::Columns { schema, table, result });
+    });
+    app.start_sidebar_load(task.abort_handle(), rx);
+}
+
+/// Navigates the completion popup; false for keys that go to the editor.
+fn handle_completion_key(app: &mut App<'_>, key: crossterm::event::KeyEvent) -> bool {
+    let Some(completion) = app.completion.as_mut() else {
+        return false;
+    };
+    match key.code {
+        KeyCode::Down => completion.select_next(),
+        KeyCode::Up => completion.select_prev(),
+        KeyCode::Char('n') if key.modifiers.contains(KeyModifiers::CONTROL) => completion.select_next(),
+        KeyCode::Char('p') if key.modifiers.contains(KeyModifiers::CONTROL) => completion.select_prev(),
+        KeyCode::Tab | KeyCode::Enter => app.accept_completion(),
+        KeyCode::Esc => app.completion = None,
+        _ => return false,
+    }
+    true
+}
+
+/// Recomputes the suggestions for the word before the cursor, fetching
+/// the columns of the tables involved first. Without `explicit`, a bare
+/// word with nothing typed yet closes the popup.
+async fn update_completion(app: &mut App<'_>, explicit: bool) {
+    let (qualifier, prefix) = app.completion_context();
+    if qualifier.is_none() && prefix.is_empty() && !explicit {
+        app.completion = None;
+        return;
+    }
+
+    let tables = match &qualifier {
+        Some(qualifier) => app.resolve_table(qualifier).into_iter().collect(),
+        None => app.query_tables(),
+    };
+    // Suggest from the cache alone rather than wait on a SQLite transaction.
+    let tables = if app.pool_held_by_transaction() { Vec::new() } else { tables };
+    for (schema, table) in tables {
+        let key = (schema, table);
+        if app.column_cache.contains_key(&key) {
+            continue;
+        }
+        let Some(conn) = &app.connection else {
+            break;
+        };
+        // Cache failures too, so typing on doesn't retry on every key.
+        let columns = conn.get_columns(&key.0, &key.1).await.unwrap_or_default();
+        app.column_cache.insert(key, columns);
+    }
+
+    let items = app.completion_items(qualifier.as_deref(), &prefix);
+    app.completion = (!items.is_empty()).then(|| Completion {
+        items,
+        selected: 0,
+        prefix_len: prefix.chars().count(),
+    });
+}
+
+async fn handle_page_button(app: &mut App<'_>, button: PageButton) {
+    // The last page needs a count first, which runs on the pool.
+    let needs_count = app.pagination.as_ref().is_some_and(|p| p.total_pages().is_none());
+    if matches!(button, PageButton::Last) && needs_count && pool_held(app) {
+        return;
+    }
+    let Some(pagination) = &app.pagination else {
+        return;
+    };
+    let target = match button {
+        PageButton::None => return,
+        PageButton::First => 0,
+        PageButton::Prev => pagination.page.saturating_sub(1),
+        PageButton::Next => match pagination.total_pages() {
+            Some(total) if pagination.page + 1 >= total => return,
+            _ => pagination.page + 1,
+        },
+        PageButton::Last => {
+            let total_pages = match pagination.total_pages() {
+                Some(total) => total,
+                None => {
+                    let Some(conn) = &app.connection else {
+                        return;
+                    };
+                    match conn.count_rows(&pagination.base_query).await {
+                        Ok(total) => {
+                            if let Some(p) = app.pagination.as_mut() {
+                                p.total_rows = Some(total);
+                            }
+                            app.pagination.as_ref().and_then(|p| p.total_pages()).unwrap_or(1)
+                        }
+                        Err(e) => {
+                            app.set_error_status(format!("Count failed: {e}"));
+                            return;
+                        }
+                    }
+                }
+            };
+            total_pages - 1
+        }
+    };
+    goto_page(app, target).await;
+}
+
+/// Pages through the current results, starting to page the editor's query
+/// if it isn't paged yet.
+async fn page_editor_query(app: &mut App<'_>, button: PageButton) {
+    if app.pagination.is_none() {
+        match app::Pagination::for_query(&app.get_query_text(), app.config.page_size, app.limit_syntax()) {
+            // The unpaged results already hold the first page and more.
+            Some(pagination) if button == PageButton::Next => app.pagination = Some(pagination),
+            Some(_) => return,
+            None => {
+                app.set_error_status("Only SELECT queries without LIMIT can be paged");
+                return;
+            }
+        }
+    }
+    if button == PageButton::Prev && app.pagination.as_ref().is_some_and(|p| p.page == 0) {
+        return;
+    }
+    handle_page_button(app, button).await;
+}
+
+async fn goto_page(app: &mut App<'_>, page: usize) {
+    if query_busy(app) {
+        return;
+    }
+    let Some(pagination) = app.pagination.as_mut() else {
+        return;
+    };
+    pagination.page = page;
+    let query = pagination.query();
+    app.set_query_text(&query);
+    execute_query(app).await;
+}
+
+async fn execute_query(app: &mut App<'_>) {
+    let query = app.get_query_text();
+    if query.trim().is_empty() {
+        return;
+    }
+    // Editing the generated query leaves table paging.
+    if app.pagination.as_ref().is_some_and(|p| p.query() != query) {
+        app.pagination = None;
+    }
+    confirm_and_run_query(app, query);
+}
+
+/// Runs `query`, first asking to confirm a destructive statement or a
+/// write on a production connection.
+fn confirm_and_run_query(app: &mut App<'_>, query: String) {
+    if let Some(reason) = db::split_sql_statements(&query).iter().find_map(|s| db::destructive_reason(s)) {
+        app.modal = Some(Modal::Confirm(Confirm {
+            title: "Destructive statement".to_string(),
+            message: format!("{reason} Run it anyway?"),
+            query,
+            action: ConfirmAction::RunQuery,
+        }));
+        return;
+    }
+    if app.is_production() && !db::split_sql_statements(&query).iter().all(|s| db::is_read_only(s)) {
+        app.modal = Some(Modal::Confirm(Confirm {
+            title: "Production write".to_string(),
+            message: "This connection is flagged as production. Run this statement?".to_string(),
+            query,
+            action: ConfirmAction::RunQuery,
+        }));
+        return;
+    }
+    run_query(app, &query);
+}
+
+/// Runs the editor's query wrapped in a CTE without touching the editor.
+async fn run_wrapped_query(app: &mut App<'_>, outer: &str) {
+    let query = app.get_query_text();
+    if query.trim().is_empty() {
+        return;
+    }
+    match db::wrap_in_cte(&query, outer) {
+        Some(wrapped) => confirm_and_run_query(app, wrapped),
+        None => app.set_error_status("Only SELECT-style queries can be wrapped"),
+    }
+}
+
+async fn show_sessions(app: &mut App<'_>) {
+    let Some(query) = app.connection.as_ref().and_then(|c| c.sessions_query()) else {
+        app.set_error_status("Session list is only available for Postgres, MySQL and SQL Server");
+        return;
+    };
+    if query_busy(app) {
+        return;
+    }
+    app.pagination = None;
+    start_query(app, query, QueryKind::Sessions, Vec::new());
+    app.focus = Focus::Results;
+}
+
+fn confirm_kill_session(app: &mut App<'_>) {
+    let Some(row) = app.query_result.rows.get(app.results_state.selected_row) else {
+        return;
+    };
+    let id = row.first().cloned().flatten().unwrap_or_default();
+    let user = row.get(1).cloned().flatten().unwrap_or_default();
+    if app.read_only {
+        app.set_error_status("Read-only mode: press F2 to allow killing sessions");
+        return;
+    }
+    let Some(query) = app.connection.as_ref().and_then(|c| c.kill_session_query(&id)) else {
+        app.set_error_status(format!("Cannot kill session {id}"));
+        return;
+    };
+    app.modal = Some(Modal::Confirm(Confirm {
+        title: "Kill session".to_string(),
+        message: format!("Terminate session {id} ({user})?"),
+        query,
+        action: ConfirmAction::KillSession,
+    }));
+}
+
+async fn kill_session(app: &mut App<'_>, query: &str) {
+    let Some(conn) = &app.connection else {
+        return;
+    };
+    match conn.execute_query(query).await {
+        Ok(_) => {
+            app.set_status("Session terminated");
+            show_sessions(app).await;
+        }
+        Err(e) => app.set_error_status(format!("Kill failed: {e}")),
+    }
+}
+
+/// Runs a query from the editor, first asking for the values of its bind
+/// parameters when it has any.
+fn run_query(app: &mut App<'_>, query: &str) {
+    let Some(style) = app.connection.as_ref().map(|c| c.param_style()) else {
+        return;
+    };
+    let count = db::param_count(query, style);
+    if count == 0 {
+        start_query(app, query, QueryKind::Editor, Vec::new());
+        return;
+    }
+    if db::split_sql_statements(query).len() > 1 {
+        app.set_error_status("Parameters only work in a single statement");
+        return;
+    }
+    let labels = (1..=count).map(|n| style.label(n)).collect();
+    let values = app.param_values.get(query.trim()).cloned().unwrap_or_default();
+    app.modal = Some(Modal::Params(Box::new(ParamForm::new(query.to_string(), labels, &values))));
+}
+
+/// Runs a query on a background task; `finish_query` handles the outcome.
+/// `params` are bound to its placeholders; None binds NULL.
+fn start_query(app: &mut App<'_>, query: &str, kind: QueryKind, params: Vec<Option<String>>) {
+    if query_busy(app) {
+        return;
+    }
+    if app.read_only && !db::split_sql_statements(query).iter().all(|s| db::is_read_only(s)) {
+        app.set_query_error(
+            "Read-only mode: only statements that read data can run.\n\
+             Press F2 to turn read-only mode off."
+                .to_string(),
+        );
+        return;
+    }
+    let Some(conn) = app.connection.clone() else {
+        return;
+    };
+    let transaction = app.transaction.clone();
+    let query = query.to_string();
+    let read_timeout = app.config.read_timeout();
+    let reconnecting = app.reconnecting.clone();
+    reconnecting.store(false, Ordering::Relaxed);
+    let (tx, rx) = tokio::sync::oneshot::channel();
+    let backend = db::BackendId::default();
+    let running = backend.clone();
+    let task = tokio::spawn(async move {
+        let started = Instant::now();
+        let run = async || match &transaction {
+            Some(transaction) => transaction.lock().await.execute_tracked(&query, &params, &backend).await,
+            None => conn.execute_tracked(&query, &params, &backend).await,
+        };
+        let (mut result, mut timed_out) = with_read_timeout(read_timeout, &conn, &backend, run()).await;
+        let mut disconnected = false;
+        if transaction.is_none() && !timed_out && result.as_ref().is_err_and(|e| conn.is_connection_lost(e)) {
+            // The pool opens a fresh connection for the retry. Writes are not
+            // repeated since they may already have run; a ping reconnects.
+            reconnecting.store(true, Ordering::Relaxed);
+            if db::split_sql_statements(&query).iter().all(|s| db::is_read_only(s)) {
+                (result, timed_out) = with_read_timeout(read_timeout, &conn, &backend, run()).await;
+                disconnected = result.as_ref().is_err_and(|e| conn.is_connection_lost(e));
+            } else {
+                match conn.execute_query("SELECT 1").await {
+                    Ok(_) => {
+                        result = result.context("Reconnected, but the statement may or may not have run");
+                    }
+                    Err(e) => {
+                        disconnected = conn.is_connection_lost(&e);
+                    }
+                }
+            }
+            reconnecting.store(false, Ordering::Relaxed);
+        }
+        if let Ok(result) = result.as_mut() {
+            result.elapsed = Some(started.elapsed());
+        }
+        let _ = tx.send(QueryOutcome {
+            query,
+            kind,
+            result,
+            timed_out,
+            disconnected,
+        });
+    });
+    app.start_query(running, task.abort_handle(), rx);
+}
+
+/// Runs a query under the client-side read timeout; true when it expired.
+/// An expired query is stopped on the server like Esc does, before its
+/// connection is let go.
+async fn with_read_timeout(
+    limit: Option<Duration>,
+    conn: &DatabaseConnection,
+    backend: &db::BackendId,
+    run: impl Future<Output = anyhow::Result<db::QueryResult>>,
+) -> (anyhow::Result<db::QueryResult>, bool) {
+    let Some(limit) = limit else {
+        return (run.await, false);
+    };
+    let run = std::pin::pin!(run);
+    tokio::select! {
+        result = run => (result, false),
+        _ = tokio::time::sleep(limit) => {
+            if let Some(backend) = backend.get() {
+                let _ = conn.cancel_query(backend).await;
+            }
+            (
+                Err(anyhow::anyhow!(
+                    "query read timed out after {:?} — connection may be stalled \
+                     (client-side read timeout, not a server statement timeout)",
+                    limit
+                )),
+                true,
+            )
+        }
+    }
+}
+
+/// Detaches from the running query and asks the server to stop it.
+fn cancel_running_query(app: &mut App<'_>) {
+    let Some(backend) = app.cancel_query() else {
+        return;
+    };
+    app.set_status("Query cancelled");
+    if let (Some(conn), Some(backend)) = (app.connection.clone(), backend.get()) {
+        tokio::spawn(async move {
+            let _ = conn.cancel_query(backend).await;
+        });
+    }
+}
+
+/// Reports and returns true when a query is already in flight.
+fn query_busy(app: &mut App<'_>) -> bool {
+    if app.query_running {
+        app.set_error_status("A query is already running (Esc to cancel)");
+    }
+    app.query_running
+}
+
+/// Reports and returns true when a SQLite transaction holds the only
+/// connection, so metadata can't be loaded until it ends.
+fn pool_held(app: &mut App<'_>) -> bool {
+    let held = app.pool_held_by_transaction();
+    if held {
+        app.set_error_status("Unavailable during a SQLite transaction (F4/F5 ends it)");
+    }
+    held
+}
+
+fn finish_query(app: &mut App<'_>, outcome: anyhow::Result<QueryOutcome>) {
+    let outcome = match outcome {
+        Ok(outcome) => outcome,
+        Err(e) => {
+            app.set_query_error(db::describe_error(&e));
+            return;
+        }
+    };
+    if outcome.timed_out {
+        app.set_error_status("Read timeout");
+    }
+    if outcome.kind == QueryKind::CellUpdate
+        && let Ok(result) = &outcome.result
+    {
+        finish_cell_update(app, result);
+        return;
+    }
+    app.sessions_view = false;
+    match outcome.result {
+        Ok(mut result) => {
+            if let Some(pagination) = app.pagination.as_mut()
+                && pagination.query() == outcome.query
+            {
+                pagination.record_page_len(result.rows.len());
+                result.source_table = pagination.table.clone();
+                result.page_offset = Some(pagination.offset());
+            }
+            app.set_query_result(result);
+            app.sessions_view = outcome.kind == QueryKind::Sessions;
+        }
+        Err(e) if outcome.disconnected => {
+            app.disconnect(format!("Lost the connection and could not reconnect: {e}"));
+        }
+        Err(e) => {
+            if app.transaction.is_some() && db::is_connection_lost(&e) {
+                app.transaction = None;
+                app.set_error_status("Connection lost; the transaction was rolled back");
+            }
+            app.set_query_error(db::describe_error(&e));
+        }
+    }
+}
+
+async fn begin_transaction(app: &mut App<'_>) {
+    if app.transaction.is_some() {
+        app.set_error_status("A transaction is already open");
+        return;
+    }
+    let Some(conn) = &app.connection else {
+        return;
+    };
+    match conn.begin().await {
+        Ok(transaction) => {
+            app.transaction = Some(Arc::new(tokio::sync::Mutex::new(transaction)));
+            app.set_status("Transaction started");
+        }
+        Err(e) => app.set_query_error(db::describe_error(&e)),
+    }
+}
+
+/// Commits or rolls back the open transaction.
+async fn end_transaction(app: &mut App<'_>, commit: bool) {
+    if app.transaction.is_none() {
+        app.set_error_status("No transaction is open (F3 to begin)");
+        return;
+    }
+    if query_busy(app) {
+        return;
+    }
+    let Some(transaction) = app.transaction.take() else {
+        return;
+    };
+    // A cancelled query's task may not have let go of it yet.
+    let transaction = match Arc::try_unwrap(transaction) {
+        Ok(transaction) => transaction.into_inner(),
+        Err(transaction) => {
+            app.transaction = Some(transaction);
+            app.set_error_status("Transaction is still busy; try again");
+            return;
+        }
+    };
+    let result = if commit { transaction.commit().await } else { transaction.rollback().await };
+    match result {
+        Ok(()) => app.set_status(if commit { "Transaction committed" } else { "Transaction rolled back" }),
+        Err(e) => {
+            app.set_query_error(db::describe_error(&e));
+            if db::is_connection_lost(&e) {
+                app.set_error_status("Connection lost; the transaction was rolled back");
+            }
+        }
+    }
+}
+
+fn open_export_dialog(app: &mut App<'_>, dialog: ExportDialog<'static>) {
+    if app.query_result.columns.is_empty() {
+        app.set_error_status("Nothing to export; run a query first");
+        return;
+    }
+    app.modal = Some(Modal::Export(Box::new(dialog)));
+}
+
+fn copy_row_as_insert(app: &mut App<'_>) {
+    let Some(conn) = &app.connection else {
+        return;
+    };
+    let statement = export::row_to_insert(&app.query_result, app.results_state.selected_row, |name| {
+        conn.quote_identifier(name)
+    });
+    match statement {
+        Some(statement) => {
+            copy_to_clipboard(app, &statement, "Copied row as INSERT to clipboard");
+        }
+        None => app.set_error_status("Copy as INSERT needs a table opened from the sidebar"),
+    }
+}
+
+/// Opens a prompt for a new value of the selected cell. Only results of a
+/// table opened from the sidebar with a single-column primary key qualify.
+async fn edit_cell(app: &mut App<'_>) {
+    if app.read_only {
+        app.set_error_status("Read-only mode: press F2 to allow edits");
+        return;
+    }
+    let Some(conn) = app.connection.clone() else {
+        return;
+    };
+    let Some(table) = app.query_result.source_table.clone() else {
+        app.set_error_status("Editing needs a table opened from the sidebar");
+        return;
+    };
+    if pool_held(app) {
+        return;
+    }
+    let key = match conn.get_primary_key(&table.schema, &table.name).await {
+        Ok(key) => key,
+        Err(e) => {
+            app.set_error_status(format!("Reading the primary key failed: {e}"));
+            return;
+        }
+    };
+    let [key] = key.as_slice() else {
+        app.set_error_status(format!("Editing needs a single-column primary key; {} has none", table.name));
+        return;
+    };
+    let Some(key_col) = app.query_result.columns.iter().position(|c| c == key) else {
+        app.set_error_status(format!("The primary key {key} is not in the results"));
+        return;
+    };
+    let (row, col) = (app.results_state.selected_row, app.results_state.selected_col);
+    let Some(cells) = app.query_result.rows.get(row) else {
+        return;
+    };
+    if cells.get(key_col).is_none_or(Option::is_none) {
+        app.set_error_status("The selected row has no primary key value");
+        return;
+    }
+    let Some(column) = app.query_result.columns.get(col) else {
+        return;
+    };
+    let mut prompt = Prompt::new(
+        PromptAction::EditCell { row, col, key_col },
+        format!("Edit {column}"),
+        "Enter: review the UPDATE  |  Ctrl+N: set NULL",
+    );
+    prompt.input.insert_str(cells.get(col).cloned().flatten().unwrap_or_default());
+    app.modal = Some(Modal::Prompt(Box::new(prompt)));
+}
+
+/// Runs a confirmed cell UPDATE like any other query, inside the open
+/// transaction if there is one; `finish_query` reloads the page after it.
+fn update_cell(app: &mut App<'_>, statement: &str) {
+    start_query(app, statement, QueryKind::CellUpdate, Vec::new());
+}
+
+/// Reports a finished cell UPDATE and reloads the page to show the stored
+/// value.
+fn finish_cell_update(app: &mut App<'_>, result: &db::QueryResult) {
+    if result.affected_rows == 0 {
+        app.set_error_status("No row was changed");
+    } else {
+        app.set_status("Row updated");
+    }
+    if let Some(pagination) = &app.pagination {
+        let query = pagination.query();
+        run_query(app, &query);
+    }
+}
+
+fn copy_query_to_clipboard(app: &mut App<'_>) {
+    let query = app.get_query_text();
+    if !query.is_empty() {
+        copy_to_clipboard(app, &query, "Copied query to clipboard");
+    }
+}
+
+/// Copies `text`, reporting success or the clipboard error in the status area.
+fn copy_to_clipboard(app: &mut App<'_>, text: &str, success: impl Into<String>) {
+    match clipboard::copy(text) {
+        Ok(()) => app.set_status(success),
+        Err(e) => app.set_error_status(format!("Copy failed: {e:#}")),
     }
 }
@@ -1,21 +1,49 @@
 use anyhow::Result;
 use sqlx::sqlite::SqlitePool;
 use std::path::PathBuf;
+use std::time::Duration;
+
+use crate::config::expand_home;
+use crate::db::ConnectSettings;
 
 #[derive(Debug, Clone)]
 pub struct RecentConnection {
     pub id: i64,
     pub connection_string: String,
     pub display_name: String,
+    #[allow(dead_code)]
     pub last_used: String,
+    /// Listed before the others regardless of when it was last used.
+    pub pinned: bool,
+    /// Header the connection is listed under; `None` for the default one.
+    pub group: Option<String>,
+}
+
+impl RecentConnection {
+    /// For display: the password, if any, is replaced by `***`.
+    pub fn masked_connection_string(&self) -> String {
+        crate::db::mask_password(&self.connection_string)
+    }
+}
+
+/// A named query kept for reuse on any connection.
+#[derive(Debug, Clone)]
+pub struct SavedQuery {
+    pub id: i64,
+    pub name: String,
+    pub query_text: String,
 }
 
+const KEYRING_SERVICE: &str = "crux";
+
 pub struct Storage {
     pool: SqlitePool,
+    /// Passwords go to the OS keyring and are left out of `crux.db`.
+    use_keyring: bool,
 }
 
 impl Storage {
-    pub async fn new() -> Result<Self> {
+    pub async fn new(use_keyring: bool) -> Result<Self> {
         let db_path = Self::get_db_path()?;
         
         if let Some(parent) = db_path.parent() {
@@ -25,7 +53,7 @@ impl Storage {
         let db_url = format!("sqlite:{}?mode=rwc", db_path.display());
         let pool = SqlitePool::connect(&db_url).await?;
         
-        let storage = Self { pool };
+        let storage = Self { pool, use_keyring };
         storage.init_schema().await?;
         
         Ok(storage)
@@ -43,7 +71,38 @@ impl Storage {
                 id INTEGER PRIMARY KEY AUTOINCREMENT,
                 connection_string TEXT NOT NULL UNIQUE,
                 display_name TEXT NOT NULL,
-                last_used DATETIME DEFAULT CURRENT_TIMESTAMP
+                last_used DATETIME DEFAULT CURRENT_TIMESTAMP,
+                pinned INTEGER NOT NULL DEFAULT 0,
+                group_name TEXT
+            )
+            "#,
+        )
+        .execute(&self.pool)
+        .await?;
+
+        // Databases created before connections could be pinned or grouped.
+        self.add_column_if_missing("recent_connections", "pinned", "INTEGER NOT NULL DEFAULT 0").await?;
+        self.add_column_if_missing("recent_connections", "group_name", "TEXT").await?;
+
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS settings (
+                key TEXT PRIMARY KEY,
+                value TEXT NOT NULL
+            )
+            "#,
+        )
+        .execute(&self.pool)
+        .await?;
+        self.strip_passwords_from_setting_keys().await?;
+
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS saved_queries (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                name TEXT NOT NULL UNIQUE,
+                query_text TEXT NOT NULL,
+                created_at DATETIME DEFAULT CURRENT_TIMESTAMP
             )
             "#,
         )
@@ -52,10 +111,160 @@ impl Storage {
         
         Ok(())
     }
+
+    async fn add_column_if_missing(&self, table: &str, column: &str, declaration: &str) -> Result<()> {
+        let exists: bool = sqlx::query_scalar("SELECT COUNT(*) > 0 FROM pragma_table_info(?) WHERE name = ?")
+            .bind(table)
+            .bind(column)
+            .fetch_one(&self.pool)
+            .await?;
+        if !exists {
+            sqlx::query(&format!("ALTER TABLE {table} ADD COLUMN {column} {declaration}"))
+                .execute(&self.pool)
+                .await?;
+        }
+        Ok(())
+    }
+
+    pub async fn get_setting(&self, key: &str) -> Result<Option<String>> {
+        let value = sqlx::query_scalar("SELECT value FROM settings WHERE key = ?")
+            .bind(key)
+            .fetch_optional(&self.pool)
+            .await?;
+        Ok(value)
+    }
+
+    pub async fn set_setting(&self, key: &str, value: &str) -> Result<()> {
+        sqlx::query(
+            r#"
+            INSERT INTO settings (key, value) VALUES (?, ?)
+            ON CONFLICT(key) DO UPDATE SET value = excluded.value
+            "#,
+        )
+        .bind(key)
+        .bind(value)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Connection settings, overridden by the `connect_timeout_secs`,
+    /// `statement_timeout_secs` (0 disables it) and `ssl_ca` settings.
+    pub async fn connect_settings(&self) -> ConnectSettings {
+        let mut settings = ConnectSettings::default();
+        if let Some(secs) = self.get_secs_setting("connect_timeout_secs").await {
+            settings.connect_timeout = Duration::from_secs(secs.max(1));
+        }
+        if let Some(secs) = self.get_secs_setting("statement_timeout_secs").await {
+            settings.statement_timeout = (secs > 0).then(|| Duration::from_secs(secs));
+        }
+        if let Ok(Some(path)) = self.get_setting("ssl_ca").await {
+            settings.ssl_ca = Some(expand_home(path.trim()));
+        }
+        settings
+    }
+
+    async fn get_secs_setting(&self, key: &str) -> Option<u64> {
+        self.get_setting(key).await.ok().flatten()?.trim().parse().ok()
+    }
+
+    /// Saves `text` as `name`, replacing a saved query of the same name.
+    pub async fn save_query(&self, name: &str, text: &str) -> Result<()> {
+        sqlx::query(
+            r#"
+            INSERT INTO saved_queries (name, query_text) VALUES (?, ?)
+            ON CONFLICT(name) DO UPDATE SET query_text = excluded.query_text
+            "#,
+        )
+        .bind(name)
+        .bind(text)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    pub async fn list_saved_queries(&self) -> Result<Vec<SavedQuery>> {
+        let rows = sqlx::query_as::<_, (i64, String, String)>(
+            "SELECT id, name, query_text FROM saved_queries ORDER BY name COLLATE NOCASE",
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows
+            .into_iter()
+            .map(|(id, name, query_text)| SavedQuery { id, name, query_text })
+            .collect())
+    }
+
+    pub async fn delete_saved_query(&self, id: i64) -> Result<()> {
+        sqlx::query("DELETE FROM saved_queries WHERE id = ?")
+            .bind(id)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+
+    /// Settings key under which a connection's working query is kept.
+    pub fn query_key(connection_string: &str) -> String {
+        Self::connection_key("query", connection_string)
+    }
+
+    pub fn read_only_key(connection_string: &str) -> String {
+        Self::connection_key("read_only", connection_string)
+    }
+
+    /// Per-connection settings are keyed by the URL without its password,
+    /// so no password ends up in `crux.db` through them.
+    fn connection_key(prefix: &str, connection_string: &str) -> String {
+        let url = crate::db::split_password(connection_string)
+            .map_or_else(|| connection_string.to_string(), |(url, _)| url);
+        format!("{prefix}:{url}")
+    }
+
+    /// Keys written before they were stripped of passwords.
+    async fn strip_passwords_from_setting_keys(&self) -> Result<()> {
+        let keys: Vec<String> =
+            sqlx::query_scalar("SELECT key FROM settings WHERE key LIKE 'query:%' OR key LIKE 'read_only:%'")
+                .fetch_all(&self.pool)
+                .await?;
+        for key in keys {
+            let Some((prefix, connection_string)) = key.split_once(':') else {
+                continue;
+            };
+            let stripped = Self::connection_key(prefix, connection_string);
+            if stripped == key {
+                continue;
+            }
+            // A value already kept under the stripped key wins.
+            sqlx::query("UPDATE OR IGNORE settings SET key = ? WHERE key = ?")
+                .bind(&stripped)
+                .bind(&key)
+                .execute(&self.pool)
+                .await?;
+            sqlx::query("DELETE FROM settings WHERE key = ?")
+                .bind(&key)
+                .execute(&self.pool)
+                .await?;
+        }
+        Ok(())
+    }
     
     pub async fn add_connection(&self, connection_string: &str) -> Result<()> {
         let display_name = Self::generate_display_name(connection_string);
-        
+        let stored = match self.stash_password(connection_string).await {
+            Some(stripped) => {
+                sqlx::query("DELETE FROM recent_connections WHERE connection_string = ?")
+                    .bind(connection_string)
+                    .execute(&self.pool)
+                    .await?;
+                stripped
+            }
+            None => connection_string.to_string(),
+        };
+
         sqlx::query(
             r#"
             INSERT INTO recent_connections (connection_string, display_name, last_used)
@@ -63,7 +272,7 @@ impl Storage {
             ON CONFLICT(connection_string) DO UPDATE SET last_used = CURRENT_TIMESTAMP
             "#,
         )
-        .bind(connection_string)
+        .bind(&stored)
         .bind(&display_name)
         .execute(&self.pool)
         .await?;
@@ -71,31 +280,80 @@ impl Storage {
         Ok(())
     }
     
+    /// Every grouped connection, and the `limit` most recent ungrouped ones.
     pub async fn get_recent_connections(&self, limit: i32) -> Result<Vec<RecentConnection>> {
-        let rows = sqlx::query_as::<_, (i64, String, String, String)>(
+        let rows = sqlx::query_as::<_, (i64, String, String, String, bool, Option<String>)>(
             r#"
-            SELECT id, connection_string, display_name, datetime(last_used) as last_used
+            SELECT id, connection_string, display_name, datetime(last_used) as last_used, pinned, group_name
             FROM recent_connections
-            ORDER BY last_used DESC
-            LIMIT ?
+            WHERE group_name IS NOT NULL
+                OR id IN (
+                    SELECT id FROM recent_connections
+                    WHERE group_name IS NULL
+                    ORDER BY pinned DESC, last_used DESC
+                    LIMIT ?
+                )
+            ORDER BY pinned DESC, last_used DESC
             "#,
         )
         .bind(limit)
         .fetch_all(&self.pool)
         .await?;
         
-        Ok(rows
-            .into_iter()
-            .map(|(id, connection_string, display_name, last_used)| RecentConnection {
+        let mut connections = Vec::with_capacity(rows.len());
+        for (id, connection_string, display_name, last_used, pinned, group) in rows {
+            connections.push(RecentConnection {
                 id,
-                connection_string,
+                connection_string: self.restore_password(connection_string).await,
                 display_name,
                 last_used,
-            })
-            .collect())
+                pinned,
+                group,
+            });
+        }
+        Ok(connections)
     }
     
+    pub async fn toggle_pin(&self, id: i64) -> Result<()> {
+        sqlx::query("UPDATE recent_connections SET pinned = NOT pinned WHERE id = ?")
+            .bind(id)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    /// Files a connection under `group`, or back under the default header.
+    pub async fn set_connection_group(&self, id: i64, group: Option<&str>) -> Result<()> {
+        sqlx::query("UPDATE recent_connections SET group_name = ? WHERE id = ?")
+            .bind(group)
+            .bind(id)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    /// Replaces the generated label of a recent connection; it is kept
+    /// when the connection is used again.
+    pub async fn rename_connection(&self, id: i64, name: &str) -> Result<()> {
+        sqlx::query("UPDATE recent_connections SET display_name = ? WHERE id = ?")
+            .bind(name)
+            .bind(id)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
     pub async fn delete_connection(&self, id: i64) -> Result<()> {
+        if self.use_keyring {
+            let stored: Option<String> = sqlx::query_scalar("SELECT connection_string FROM recent_connections WHERE id = ?")
+                .bind(id)
+                .fetch_optional(&self.pool)
+                .await?;
+            if let Some(stored) = stored {
+                let _ = keyring_call(stored, |entry| entry.delete_credential()).await;
+            }
+        }
+
         sqlx::query("DELETE FROM recent_connections WHERE id = ?")
             .bind(id)
             .execute(&self.pool)
@@ -104,18 +362,44 @@ impl Storage {
         Ok(())
     }
     
-    fn generate_display_name(connection_string: &str) -> String {
+    /// Moves the password into the keyring, returning the connection string
+    /// to store without it. `None` keeps the full string: keyring mode is
+    /// off, there is no password, or the keyring is unavailable.
+    async fn stash_password(&self, connection_string: &str) -> Option<String> {
+        if !self.use_keyring {
+            return None;
+        }
+        let (stripped, password) = crate::db::split_password(connection_string)?;
+        keyring_call(stripped.clone(), move |entry| entry.set_password(&password)).await?;
+        Some(stripped)
+    }
+
+    /// Puts back a password kept in the keyring by `stash_password`.
+    async fn restore_password(&self, stored: String) -> String {
+        if !self.use_keyring || crate::db::split_password(&stored).is_some() {
+            return stored;
+        }
+        match keyring_call(stored.clone(), |entry| entry.get_password()).await {
+            Some(password) => crate::db::with_password(&stored, &password).unwrap_or(stored),
+            None => stored,
+        }
+    }
+
+    /// A short label for a connection string, without its credentials.
+    pub fn generate_display_name(connection_string: &str) -> String {
         if connection_string.starts_with("postgres://") || connection_string.starts_with("postgresql://") {
             Self::parse_url_display_name(connection_string, "PostgreSQL")
         } else if connection_string.starts_with("mysql://") {
             Self::parse_url_display_name(connection_string, "MySQL")
+        } else if connection_string.starts_with("mssql://") || connection_string.starts_with("sqlserver://") {
+            Self::parse_url_display_name(connection_string, "SQL Server")
         } else if connection_string.starts_with("sqlite://") {
             let path = connection_string.strip_prefix("sqlite://").unwrap_or(connection_string);
-            format!("SQLite: {}", path.split('/').last().unwrap_or(path))
+            format!("SQLite: {}", path.split('/').next_back().unwrap_or(path))
         } else if connection_string.ends_with(".db") {
-            format!("SQLite: {}", connection_string.split('/').last().unwrap_or(connection_string))
+            format!("SQLite: {}", connection_string.split('/').next_back().unwrap_or(connection_string))
         } else {
-            connection_string.chars().take(40).collect()
+            crate::db::mask_password(connection_string).chars().take(40).collect()
         }
     }
     
@@ -142,3 +426,15 @@ impl Storage {
         }
     }
 }
+
+/// Runs `f` on the keyring entry for a stored connection string, off the
+/// async runtime since the platform keyrings block. `None` on any failure.
+async fn keyring_call<T: Send + 'static>(
+    stored: String,
+    f: impl FnOnce(&keyring::Entry) -> keyring::Result<T> + Send + 'static,
+) -> Option<T> {
+    tokio::task::spawn_blocking(move || f(&keyring::Entry::new(KEYRING_SERVICE, &stored)?))
+        .await
+        .ok()?
+        .ok()
+}
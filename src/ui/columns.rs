@@ -0,0 +1,57 @@
+use std::collections::HashSet;
+
+use ratatui::{
+    layout::{Constraint, Layout},
+    text::{Line, Span},
+    widgets::{Clear, Paragraph},
+    Frame,
+};
+
+use super::popup::{centered_rect, popup_block};
+use super::theme::Theme;
+
+/// Every column of the result with a checkbox; unchecked ones are hidden
+/// from the grid and from exports.
+pub fn render_column_chooser(
+    frame: &mut Frame,
+    columns: &[String],
+    hidden: &HashSet<usize>,
+    selected: usize,
+    theme: &Theme,
+) {
+    let area = centered_rect(frame.area(), 50, 20);
+    frame.render_widget(Clear, area);
+
+    let shown = columns.len() - hidden.len();
+    let block = popup_block(format!(" Columns ({shown} of {} shown) ", columns.len()), theme);
+    let inner = block.inner(area);
+    frame.render_widget(block, area);
+
+    let chunks = Layout::vertical([Constraint::Min(1), Constraint::Length(1)]).split(inner);
+
+    let height = chunks[0].height as usize;
+    let offset = (selected + 1).saturating_sub(height);
+    let lines: Vec<Line> = columns
+        .iter()
+        .enumerate()
+        .skip(offset)
+        .take(height)
+        .map(|(i, column)| {
+            let checkbox = if hidden.contains(&i) { "[ ]" } else { "[x]" };
+            let style = if i == selected {
+                theme.selected_style()
+            } else if hidden.contains(&i) {
+                theme.muted_style()
+            } else {
+                theme.text_style()
+            };
+            Line::from(vec![Span::styled(format!(" {checkbox} {column}"), style)])
+        })
+        .collect();
+    frame.render_widget(Paragraph::new(lines), chunks[0]);
+
+    frame.render_widget(
+        Paragraph::new("Space: show / hide  |  a: show all  |  Esc: close").style(theme.muted_style()),
+        chunks[1],
+    );
+}
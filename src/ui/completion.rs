@@ -0,0 +1,86 @@
+use ratatui::{
+    layout::Rect,
+    style::Style,
+    text::Line,
+    widgets::{Block, Borders, Clear, Paragraph},
+    Frame,
+};
+use tui_textarea::TextArea;
+use unicode_width::UnicodeWidthStr;
+
+use super::query::editor_cursor_position;
+use super::theme::Theme;
+
+const MAX_VISIBLE: usize = 8;
+
+/// Identifiers offered for the word before the editor's cursor.
+pub struct Completion {
+    pub items: Vec<String>,
+    pub selected: usize,
+    /// Characters of the word before the cursor that an item replaces.
+    pub prefix_len: usize,
+}
+
+impl Completion {
+    pub fn select_next(&mut self) {
+        self.selected = (self.selected + 1) % self.items.len().max(1);
+    }
+
+    pub fn select_prev(&mut self) {
+        self.selected = self.selected.checked_sub(1).unwrap_or(self.items.len().saturating_sub(1));
+    }
+
+    pub fn selected_item(&self) -> Option<&str> {
+        self.items.get(self.selected).map(String::as_str)
+    }
+}
+
+/// Draws the suggestions under the cursor of the editor in `editor_area`,
+/// or above it when there is no room below.
+pub fn render_completion(frame: &mut Frame, editor_area: Rect, textarea: &TextArea, completion: &Completion, theme: &Theme) {
+    let (cursor_x, cursor_y) = editor_cursor_position(editor_area, textarea);
+    let screen = frame.area();
+
+    let visible = completion.items.len().min(MAX_VISIBLE);
+    let height = visible as u16 + 2;
+    let width = (completion.items.iter().map(|item| item.width()).max().unwrap_or(0) as u16 + 4)
+        .clamp(16, 48)
+        .min(screen.width);
+    let x = cursor_x
+        .saturating_sub(completion.prefix_len as u16)
+        .min(screen.right().saturating_sub(width));
+    let y = if cursor_y + 1 + height <= screen.bottom() {
+        cursor_y + 1
+    } else {
+        cursor_y.saturating_sub(height)
+    };
+    let area = Rect::new(x, y, width, height).intersection(screen);
+
+    let offset = (completion.selected + 1).saturating_sub(visible);
+    let lines: Vec<Line> = completion
+        .items
+        .iter()
+        .enumerate()
+        .skip(offset)
+        .take(visible)
+        .map(|(i, item)| {
+            let style = if i == completion.selected {
+                Style::default().bg(theme.bg_selected).fg(theme.text)
+            } else {
+                theme.text_style()
+            };
+            Line::styled(format!(" {item} "), style)
+        })
+        .collect();
+
+    frame.render_widget(Clear, area);
+    frame.render_widget(
+        Paragraph::new(lines).block(
+            Block::default()
+                .borders(Borders::ALL)
+                .border_style(theme.border_focused_style())
+                .style(Style::default().bg(theme.bg_secondary)),
+        ),
+        area,
+    );
+}
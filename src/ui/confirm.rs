@@ -0,0 +1,90 @@
+use ratatui::{
+    layout::{Alignment, Constraint, Layout},
+    style::{Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, Clear, Paragraph, Wrap},
+    Frame,
+};
+
+use super::popup::centered_rect;
+use super::query::highlight_sql_line;
+use super::theme::Theme;
+
+pub fn render_confirm(frame: &mut Frame, title: &str, message: &str, query: &str, theme: &Theme) {
+    let area = centered_rect(frame.area(), 70, 12);
+    frame.render_widget(Clear, area);
+
+    let block = Block::default()
+        .title(format!(" ⚠ {} ", title))
+        .title_alignment(Alignment::Center)
+        .borders(Borders::ALL)
+        .border_style(theme.error_style())
+        .style(Style::default().bg(theme.bg_secondary));
+    let inner = block.inner(area);
+    frame.render_widget(block, area);
+
+    let chunks = Layout::vertical([
+        Constraint::Length(2),
+        Constraint::Min(1),
+        Constraint::Length(1),
+    ])
+    .split(inner);
+
+    frame.render_widget(
+        Paragraph::new(message)
+            .style(theme.error_style().add_modifier(Modifier::BOLD))
+            .wrap(Wrap { trim: true }),
+        chunks[0],
+    );
+    frame.render_widget(
+        Paragraph::new(query.lines().map(|line| highlight_sql_line(line, theme)).collect::<Vec<_>>())
+            .wrap(Wrap { trim: false }),
+        chunks[1],
+    );
+    frame.render_widget(
+        Paragraph::new(Line::from("y: run  |  n/Esc: cancel"))
+            .style(theme.muted_style())
+            .alignment(Alignment::Center),
+        chunks[2],
+    );
+}
+
+pub fn render_production_banner(frame: &mut Frame, area: ratatui::layout::Rect, theme: &Theme) {
+    frame.render_widget(
+        Paragraph::new(" ⚠ PRODUCTION CONNECTION — writes require confirmation ")
+            .style(Style::default().bg(theme.error).fg(theme.bg).add_modifier(Modifier::BOLD))
+            .alignment(Alignment::Center),
+        area,
+    );
+}
+
+/// The bottom line of the browser: which database this is.
+pub fn render_status_bar(
+    frame: &mut Frame,
+    area: ratatui::layout::Rect,
+    engine: &str,
+    version: Option<&str>,
+    connection: &str,
+    theme: &Theme,
+) {
+    let name = connection.strip_prefix(&format!("{engine}: ")).unwrap_or(connection);
+    let mut spans = vec![Span::styled(format!(" {engine}"), theme.accent_style().add_modifier(Modifier::BOLD))];
+    if let Some(version) = version {
+        spans.push(Span::styled(format!(" {version}"), theme.text_style()));
+    }
+    spans.push(Span::styled(" │ ", theme.muted_style()));
+    spans.push(Span::styled(name.to_string(), theme.text_style()));
+    frame.render_widget(
+        Paragraph::new(Line::from(spans)).style(Style::default().bg(theme.bg_secondary)),
+        area,
+    );
+}
+
+pub fn render_transaction_banner(frame: &mut Frame, area: ratatui::layout::Rect, theme: &Theme) {
+    frame.render_widget(
+        Paragraph::new(" IN TRANSACTION — F4 commit · F5 rollback ")
+            .style(Style::default().bg(theme.accent).fg(theme.bg).add_modifier(Modifier::BOLD))
+            .alignment(Alignment::Center),
+        area,
+    );
+}
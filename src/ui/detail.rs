@@ -0,0 +1,178 @@
+use ratatui::{
+    style::Style,
+    widgets::{Clear, Paragraph, Wrap},
+    Frame,
+};
+
+use unicode_width::UnicodeWidthStr;
+
+use crate::db::QueryResult;
+use crate::export::RowFormat;
+use super::popup::{centered_rect, popup_block};
+use super::theme::Theme;
+
+/// Full value of one cell, word-wrapped, or a hex dump for binary values.
+/// Clamps `scroll` to the content.
+pub fn render_cell_detail(
+    frame: &mut Frame,
+    result: &QueryResult,
+    row: usize,
+    column: usize,
+    scroll: &mut usize,
+    theme: &Theme,
+) {
+    let Some(value) = result.rows.get(row).and_then(|r| r.get(column)) else {
+        return;
+    };
+    let name = result.columns.get(column).map(String::as_str).unwrap_or("");
+    let bytes = value.as_deref().and_then(decode_hex_cell);
+    let (text, style) = match (value, &bytes) {
+        (_, Some(bytes)) => (format_hex_dump(bytes), theme.text_style()),
+        (Some(value), None) => (cell_detail_text(value), theme.text_style()),
+        (None, None) => ("NULL".to_string(), theme.null_style()),
+    };
+    let title = match &bytes {
+        Some(bytes) => format!(" {} (row {}, {} bytes)  y: copy  Esc: close ", name, row + 1, bytes.len()),
+        None => format!(" {} (row {})  y: copy  Esc: close ", name, row + 1),
+    };
+
+    let width = if bytes.is_some() {
+        frame.area().width.min(HEX_DUMP_WIDTH + 2)
+    } else {
+        (frame.area().width * 4 / 5).max(20)
+    };
+    render_text_popup(frame, title, &text, style, width, scroll, theme);
+}
+
+/// The CREATE statement of `table`, from the sidebar. Clamps `scroll` to
+/// the content.
+pub fn render_ddl(frame: &mut Frame, table: &str, ddl: &str, scroll: &mut usize, theme: &Theme) {
+    let title = format!(" {table}  y: copy  Esc: close ");
+    let width = (frame.area().width * 4 / 5).max(20);
+    render_text_popup(frame, title, ddl, theme.text_style(), width, scroll, theme);
+}
+
+/// Word-wrapped text in a popup `width` columns wide and as tall as the
+/// text, up to the screen.
+fn render_text_popup(
+    frame: &mut Frame,
+    title: String,
+    text: &str,
+    style: Style,
+    width: u16,
+    scroll: &mut usize,
+    theme: &Theme,
+) {
+    let inner_width = width.saturating_sub(2).max(1) as usize;
+    let line_count: usize = text.lines().map(|line| wrapped_height(line, inner_width)).sum();
+
+    let area = centered_rect(frame.area(), width, line_count as u16 + 2);
+    *scroll = (*scroll).min(line_count.saturating_sub(area.height.saturating_sub(2) as usize));
+
+    frame.render_widget(Clear, area);
+    frame.render_widget(
+        Paragraph::new(text)
+            .style(style)
+            .wrap(Wrap { trim: false })
+            .scroll((*scroll as u16, 0))
+            .block(popup_block(title, theme)),
+        area,
+    );
+}
+
+/// Rows a line takes when word-wrapped to `width`, approximating ratatui's wrapping.
+fn wrapped_height(line: &str, width: usize) -> usize {
+    let mut rows = 1;
+    let mut used = 0;
+    for word in line.split_inclusive(' ') {
+        let w = word.width();
+        if used + w > width && used > 0 {
+            rows += 1;
+            used = 0;
+        }
+        rows += w / width.max(1);
+        used += w % width.max(1);
+    }
+    rows
+}
+
+/// Bytes of a blob as the extractors render it: `\\x..` (Postgres), `0x..`
+/// (MySQL) or `X'..'` (SQLite).
+fn decode_hex_cell(value: &str) -> Option<Vec<u8>> {
+    let digits = value
+        .strip_prefix("\\x")
+        .or_else(|| value.strip_prefix("0x"))
+        .or_else(|| value.strip_prefix("X'").and_then(|v| v.strip_suffix('\'')))?;
+    hex::decode(digits).ok()
+}
+
+const HEX_DUMP_WIDTH: u16 = 78;
+
+/// `hexdump -C` style: offset, 16 bytes in hex and the printable ASCII.
+fn format_hex_dump(bytes: &[u8]) -> String {
+    if bytes.is_empty() {
+        return "(empty)".to_string();
+    }
+    bytes
+        .chunks(16)
+        .enumerate()
+        .map(|(i, chunk)| {
+            let mut hex = String::with_capacity(49);
+            for (j, b) in chunk.iter().enumerate() {
+                if j == 8 {
+                    hex.push(' ');
+                }
+                hex.push_str(&format!("{b:02x} "));
+            }
+            let ascii: String = chunk
+                .iter()
+                .map(|&b| if b.is_ascii_graphic() || b == b' ' { b as char } else { '.' })
+                .collect();
+            format!("{:08x}  {:<49} |{}|", i * 16, hex, ascii)
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// JSON objects and arrays are pretty-printed; anything else is shown as is.
+fn cell_detail_text(value: &str) -> String {
+    try_pretty_json(value).unwrap_or_else(|| value.to_string())
+}
+
+/// `s` indented two spaces per level when it is a JSON object or array,
+/// keeping key order.
+fn try_pretty_json(s: &str) -> Option<String> {
+    let trimmed = s.trim_start();
+    if !trimmed.starts_with('{') && !trimmed.starts_with('[') {
+        return None;
+    }
+    let json = serde_json::from_str::<serde_json::Value>(s).ok()?;
+    serde_json::to_string_pretty(&json).ok()
+}
+
+pub fn render_row_detail(
+    frame: &mut Frame,
+    result: &QueryResult,
+    row: usize,
+    format: RowFormat,
+    scroll: usize,
+    theme: &Theme,
+) {
+    let text = format.render(result, row);
+    let line_count = text.lines().count();
+
+    let area = centered_rect(frame.area(), 90, line_count as u16 + 2);
+    let max_scroll = line_count.saturating_sub(area.height.saturating_sub(2) as usize);
+
+    frame.render_widget(Clear, area);
+    frame.render_widget(
+        Paragraph::new(text)
+            .style(theme.text_style())
+            .scroll((scroll.min(max_scroll) as u16, 0))
+            .block(popup_block(
+                format!(" Row {} as {} (f: format  y: copy  Esc: close) ", row + 1, format.label()),
+                theme,
+            )),
+        area,
+    );
+}
@@ -0,0 +1,56 @@
+use ratatui::{
+    layout::Rect,
+    style::{Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, Paragraph, Wrap},
+    Frame,
+};
+
+use crate::app::StatusMessage;
+use super::results::status_line;
+use super::theme::Theme;
+
+/// Labels that start their own line in a formatted database error.
+const SECTIONS: [&str; 5] = ["DETAIL:", "HINT:", "WHERE:", "POSITION:", "CODE:"];
+
+pub fn render_error_panel(
+    frame: &mut Frame,
+    area: Rect,
+    error: &str,
+    scroll: usize,
+    focused: bool,
+    status: Option<&StatusMessage>,
+    theme: &Theme,
+) {
+    let lines: Vec<Line> = error
+        .lines()
+        .enumerate()
+        .map(|(i, line)| {
+            if i == 0 {
+                return Line::styled(line, theme.error_style().add_modifier(Modifier::BOLD));
+            }
+            match SECTIONS.iter().find(|s| line.starts_with(*s)) {
+                Some(label) => Line::from(vec![
+                    Span::styled(*label, theme.accent_style()),
+                    Span::styled(&line[label.len()..], theme.text_style()),
+                ]),
+                None => Line::styled(line, theme.text_style()),
+            }
+        })
+        .collect();
+
+    frame.render_widget(
+        Paragraph::new(lines)
+            .wrap(Wrap { trim: false })
+            .scroll((scroll.min(u16::MAX as usize) as u16, 0))
+            .block(
+                Block::default()
+                    .title(" Error (y: copy  Esc: dismiss) ")
+                    .title(status_line(status, theme))
+                    .borders(Borders::ALL)
+                    .border_style(theme.block_style(focused))
+                    .style(Style::default().bg(theme.bg_secondary)),
+            ),
+        area,
+    );
+}
@@ -0,0 +1,155 @@
+use ratatui::{
+    layout::{Constraint, Layout},
+    style::{Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, Clear, Paragraph},
+    Frame,
+};
+use tui_textarea::TextArea;
+
+use crate::export::{ExportDestination, ExportFormat};
+use super::popup::{centered_rect, popup_block};
+use super::theme::Theme;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportStep {
+    Format,
+    Destination,
+    Path,
+}
+
+#[derive(Debug, Clone)]
+pub struct ExportDialog<'a> {
+    pub step: ExportStep,
+    pub format_idx: usize,
+    pub destination_idx: usize,
+    pub path_input: TextArea<'a>,
+}
+
+impl ExportDialog<'_> {
+    pub fn new() -> Self {
+        Self {
+            step: ExportStep::Format,
+            format_idx: 0,
+            destination_idx: 0,
+            path_input: TextArea::default(),
+        }
+    }
+
+    /// Starts at the destination step with `format` already chosen.
+    pub fn with_format(format: ExportFormat) -> Self {
+        Self {
+            step: ExportStep::Destination,
+            format_idx: ExportFormat::ALL.iter().position(|f| *f == format).unwrap_or(0),
+            ..Self::new()
+        }
+    }
+
+    pub fn format(&self) -> ExportFormat {
+        ExportFormat::ALL[self.format_idx]
+    }
+
+    pub fn destination(&self) -> ExportDestination {
+        ExportDestination::ALL[self.destination_idx]
+    }
+
+    pub fn select_next(&mut self) {
+        match self.step {
+            ExportStep::Format => self.format_idx = (self.format_idx + 1) % ExportFormat::ALL.len(),
+            ExportStep::Destination => {
+                self.destination_idx = (self.destination_idx + 1) % ExportDestination::ALL.len()
+            }
+            ExportStep::Path => {}
+        }
+    }
+
+    pub fn select_prev(&mut self) {
+        match self.step {
+            ExportStep::Format => {
+                self.format_idx = (self.format_idx + ExportFormat::ALL.len() - 1) % ExportFormat::ALL.len()
+            }
+            ExportStep::Destination => {
+                self.destination_idx =
+                    (self.destination_idx + ExportDestination::ALL.len() - 1) % ExportDestination::ALL.len()
+            }
+            ExportStep::Path => {}
+        }
+    }
+
+    pub fn enter_path_step(&mut self) {
+        self.step = ExportStep::Path;
+        let mut input = TextArea::from(vec![format!("results.{}", self.format().extension())]);
+        input.set_cursor_line_style(Style::default());
+        input.move_cursor(tui_textarea::CursorMove::End);
+        self.path_input = input;
+    }
+
+    pub fn path(&self) -> String {
+        self.path_input.lines().join("")
+    }
+}
+
+pub fn render_export_dialog(frame: &mut Frame, dialog: &ExportDialog, theme: &Theme) {
+    let area = centered_rect(frame.area(), 50, 9);
+    frame.render_widget(Clear, area);
+
+    let title = match dialog.step {
+        ExportStep::Format => " Export: format ",
+        ExportStep::Destination => " Export: destination ",
+        ExportStep::Path => " Export: file path ",
+    };
+    let block = popup_block(title.to_string(), theme);
+    let inner = block.inner(area);
+    frame.render_widget(block, area);
+
+    let chunks = Layout::vertical([Constraint::Min(1), Constraint::Length(1)]).split(inner);
+
+    match dialog.step {
+        ExportStep::Format => {
+            let labels = ExportFormat::ALL.iter().map(|f| f.label());
+            frame.render_widget(Paragraph::new(option_lines(labels, dialog.format_idx, theme)), chunks[0]);
+        }
+        ExportStep::Destination => {
+            let labels = ExportDestination::ALL.iter().map(|d| d.label());
+            frame.render_widget(
+                Paragraph::new(option_lines(labels, dialog.destination_idx, theme)),
+                chunks[0],
+            );
+        }
+        ExportStep::Path => {
+            let mut ta = dialog.path_input.clone();
+            ta.set_block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .border_style(theme.border_focused_style())
+                    .style(Style::default().bg(theme.bg)),
+            );
+            ta.set_style(theme.text_style());
+            ta.set_cursor_style(Style::default().add_modifier(Modifier::REVERSED).bg(theme.accent));
+            let input_area = Layout::vertical([Constraint::Length(3), Constraint::Min(0)]).split(chunks[0])[0];
+            frame.render_widget(&ta, input_area);
+        }
+    }
+
+    let help = match dialog.step {
+        ExportStep::Path => "Enter: write file  |  Esc: cancel",
+        _ => "↑/↓: choose  |  Enter: next  |  Esc: cancel",
+    };
+    frame.render_widget(Paragraph::new(help).style(theme.muted_style()), chunks[1]);
+}
+
+fn option_lines<'a>(labels: impl Iterator<Item = &'a str>, selected: usize, theme: &Theme) -> Vec<Line<'a>> {
+    labels
+        .enumerate()
+        .map(|(i, label)| {
+            if i == selected {
+                Line::from(vec![
+                    Span::styled("▶ ", theme.accent_style()),
+                    Span::styled(label, theme.selected_style()),
+                ])
+            } else {
+                Line::from(vec![Span::raw("  "), Span::styled(label, theme.text_style())])
+            }
+        })
+        .collect()
+}
@@ -0,0 +1,43 @@
+use ratatui::{
+    style::Modifier,
+    text::{Line, Span},
+    widgets::{Clear, Paragraph},
+    Frame,
+};
+
+use unicode_width::UnicodeWidthStr;
+
+use crate::keymap::{bindings_for, KeyContext, BINDINGS};
+use super::popup::{centered_rect, popup_block};
+use super::theme::Theme;
+
+pub fn render_help(frame: &mut Frame, theme: &Theme) {
+    let key_width = BINDINGS.iter().map(|b| b.keys.width()).max().unwrap_or(0);
+    let desc_width = BINDINGS.iter().map(|b| b.description.width()).max().unwrap_or(0);
+
+    let mut lines: Vec<Line> = Vec::new();
+    for context in KeyContext::ALL {
+        if !lines.is_empty() {
+            lines.push(Line::raw(""));
+        }
+        lines.push(Line::styled(
+            context.title(),
+            theme.accent_style().add_modifier(Modifier::BOLD),
+        ));
+        for binding in bindings_for(context) {
+            lines.push(Line::from(vec![
+                Span::styled(format!("  {:<width$}  ", binding.keys, width = key_width), theme.text_style()),
+                Span::styled(binding.description, theme.dim_style()),
+            ]));
+        }
+    }
+
+    let height = lines.len() as u16 + 2;
+    let area = centered_rect(frame.area(), (key_width + desc_width + 6) as u16, height);
+
+    frame.render_widget(Clear, area);
+    frame.render_widget(
+        Paragraph::new(lines).block(popup_block(" Keybindings (Esc to close) ".to_string(), theme)),
+        area,
+    );
+}
@@ -0,0 +1,39 @@
+use ratatui::{
+    text::{Line, Span},
+    widgets::{Clear, Paragraph},
+    Frame,
+};
+use unicode_width::UnicodeWidthStr;
+
+use crate::db::QueryResult;
+use super::popup::{centered_rect, popup_block};
+use super::theme::Theme;
+
+pub fn render_types_legend(frame: &mut Frame, result: &QueryResult, scroll: usize, theme: &Theme) {
+    let name_width = result.columns.iter().map(|c| c.width()).max().unwrap_or(0).min(40);
+
+    let lines: Vec<Line> = result
+        .columns
+        .iter()
+        .enumerate()
+        .map(|(i, column)| {
+            let type_name = result.column_types.get(i).map(String::as_str).unwrap_or("?");
+            Line::from(vec![
+                Span::styled(format!(" {:<width$}  ", column, width = name_width), theme.text_style()),
+                Span::styled(type_name, theme.accent_style()),
+            ])
+        })
+        .collect();
+
+    let height = (lines.len() as u16).saturating_add(2);
+    let area = centered_rect(frame.area(), name_width as u16 + 32, height);
+    let max_scroll = lines.len().saturating_sub(area.height.saturating_sub(2) as usize);
+
+    frame.render_widget(Clear, area);
+    frame.render_widget(
+        Paragraph::new(lines)
+            .scroll((scroll.min(max_scroll) as u16, 0))
+            .block(popup_block(" Column Types (t/Esc to close) ".to_string(), theme)),
+        area,
+    );
+}
@@ -1,11 +1,40 @@
+mod columns;
+mod completion;
+mod confirm;
 mod connection;
+mod detail;
+mod error;
+pub mod export;
+mod help;
+mod legend;
+pub mod pagination;
+pub mod palette;
+pub mod params;
+mod popup;
+mod prompt;
 pub mod query;
 mod results;
+pub mod saved;
 mod sidebar;
+mod stats;
 pub mod theme;
 
-pub use connection::render_connection_dialog;
-pub use query::{render_query_panel, QueryButton};
+pub use columns::render_column_chooser;
+pub use completion::{render_completion, Completion};
+pub use confirm::{render_confirm, render_production_banner, render_status_bar, render_transaction_banner};
+pub use connection::{recent_rows, render_connection_dialog, ConnectionForm, FormField, RecentRow, UNGROUPED};
+pub use detail::{render_cell_detail, render_ddl, render_row_detail};
+pub use error::render_error_panel;
+pub use export::render_export_dialog;
+pub use help::render_help;
+pub use legend::render_types_legend;
+pub use pagination::render_pagination;
+pub use palette::render_palette;
+pub use params::render_param_form;
+pub use prompt::render_prompt;
+pub use query::{render_query_panel, QueryButton, VimMode};
 pub use results::{render_results, ResultsState};
-pub use sidebar::{render_sidebar, TreeState};
+pub use saved::render_saved_queries;
+pub use sidebar::{render_sidebar, TreeSnapshot, TreeState};
+pub use stats::{column_stats, render_column_stats, ColumnStats};
 pub use theme::Theme;
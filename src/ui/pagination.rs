@@ -0,0 +1,86 @@
+use ratatui::{
+    layout::Rect,
+    style::Style,
+    text::{Line, Span},
+    widgets::Paragraph,
+    Frame,
+};
+
+use super::theme::Theme;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PageButton {
+    None,
+    First,
+    Prev,
+    Next,
+    Last,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct PaginationRegion {
+    buttons: Vec<(PageButton, Rect)>,
+}
+
+impl PaginationRegion {
+    pub fn hit_test(&self, x: u16, y: u16) -> PageButton {
+        self.buttons
+            .iter()
+            .find(|(_, r)| r.x <= x && x < r.x + r.width && r.y <= y && y < r.y + r.height)
+            .map(|(b, _)| *b)
+            .unwrap_or(PageButton::None)
+    }
+}
+
+pub fn render_pagination(
+    frame: &mut Frame,
+    area: Rect,
+    page: usize,
+    total_pages: Option<usize>,
+    hovered: PageButton,
+    theme: &Theme,
+) -> PaginationRegion {
+    let label = match total_pages {
+        Some(total) => format!(" Page {} of {} ", page + 1, total.max(1)),
+        None => format!(" Page {} ", page + 1),
+    };
+
+    let buttons = [
+        (PageButton::First, " ◀◀ "),
+        (PageButton::Prev, " ◀ "),
+        (PageButton::Next, " ▶ "),
+        (PageButton::Last, " ▶▶ "),
+    ];
+
+    let total_width: u16 = buttons.iter().map(|(_, t)| t.chars().count() as u16 + 1).sum::<u16>()
+        + label.chars().count() as u16;
+    let mut x = area.x + area.width.saturating_sub(total_width) / 2;
+
+    let mut region = PaginationRegion::default();
+    let mut spans = Vec::new();
+    for (i, (button, text)) in buttons.iter().enumerate() {
+        if i == 2 {
+            spans.push(Span::styled(label.clone(), theme.dim_style()));
+            x += label.chars().count() as u16;
+        }
+        let width = text.chars().count() as u16;
+        region.buttons.push((*button, Rect::new(x, area.y, width, 1).intersection(area)));
+        let style = if *button == hovered {
+            theme.button_hover_style()
+        } else {
+            theme.button_style()
+        };
+        spans.push(Span::styled(*text, style));
+        spans.push(Span::raw(" "));
+        x += width + 1;
+    }
+
+    let offset = area.width.saturating_sub(total_width) / 2;
+    let line_area = Rect::new(area.x + offset, area.y, area.width.saturating_sub(offset), 1);
+    frame.render_widget(
+        Paragraph::new(Line::from(spans)).style(Style::default().bg(theme.bg)),
+        line_area,
+    );
+
+    region
+}
@@ -0,0 +1,167 @@
+use ratatui::{
+    layout::{Constraint, Layout},
+    style::{Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, Clear, Paragraph},
+    Frame,
+};
+use tui_textarea::TextArea;
+
+use super::popup::{centered_rect, popup_block};
+use super::theme::Theme;
+use crate::db::TableInfo;
+
+/// The Ctrl+P overlay: a filter and the tables that match it.
+#[derive(Debug, Clone)]
+pub struct Palette<'a> {
+    pub input: TextArea<'a>,
+    pub selected: usize,
+}
+
+/// A table matching the palette's filter.
+pub struct PaletteMatch<'t> {
+    pub table: &'t TableInfo,
+    pub label: String,
+    /// Character indices of `label` matched by the filter.
+    pub positions: Vec<usize>,
+    score: i64,
+}
+
+impl Palette<'_> {
+    pub fn new() -> Self {
+        let mut input = TextArea::default();
+        input.set_cursor_line_style(Style::default());
+        Self { input, selected: 0 }
+    }
+
+    pub fn filter(&self) -> String {
+        self.input.lines().join("")
+    }
+
+    /// Tables matching the filter, best first.
+    pub fn matches<'t>(&self, tables: &'t [TableInfo]) -> Vec<PaletteMatch<'t>> {
+        let filter = self.filter();
+        let mut matches: Vec<PaletteMatch> = tables
+            .iter()
+            .filter_map(|table| {
+                let label = format!("{}.{}", table.schema, table.name);
+                let (score, positions) = fuzzy_match(&filter, &label)?;
+                Some(PaletteMatch {
+                    table,
+                    label,
+                    positions,
+                    score,
+                })
+            })
+            .collect();
+        matches.sort_by(|a, b| {
+            b.score
+                .cmp(&a.score)
+                .then(a.label.len().cmp(&b.label.len()))
+                .then_with(|| a.label.cmp(&b.label))
+        });
+        matches
+    }
+}
+
+/// Scores `text` against `pattern` when every character of the pattern
+/// appears in order, case-insensitively. Runs of consecutive characters and
+/// matches at the start of a word score higher. Returns the score and the
+/// matched character indices.
+pub fn fuzzy_match(pattern: &str, text: &str) -> Option<(i64, Vec<usize>)> {
+    let pattern: Vec<char> = pattern.chars().filter(|c| !c.is_whitespace()).collect();
+    let text: Vec<char> = text.chars().collect();
+    let Some(&first) = pattern.first() else {
+        return Some((0, Vec::new()));
+    };
+    // Greedy from every place the first character occurs, keeping the best.
+    (0..text.len())
+        .filter(|&i| same_letter(text[i], first))
+        .filter_map(|start| match_from(&pattern, &text, start))
+        .max_by_key(|(score, _)| *score)
+}
+
+fn match_from(pattern: &[char], text: &[char], start: usize) -> Option<(i64, Vec<usize>)> {
+    let mut positions: Vec<usize> = Vec::with_capacity(pattern.len());
+    let mut score = 0i64;
+    let mut next = start;
+    for &p in pattern {
+        let idx = (next..text.len()).find(|&i| same_letter(text[i], p))?;
+        score += 1;
+        if positions.last().is_some_and(|&last| last + 1 == idx) {
+            score += 5;
+        }
+        if idx == 0 || matches!(text[idx - 1], '.' | '_' | '-' | ' ') {
+            score += 3;
+        }
+        score -= (idx - next) as i64 / 4;
+        positions.push(idx);
+        next = idx + 1;
+    }
+    Some((score, positions))
+}
+
+fn same_letter(a: char, b: char) -> bool {
+    a.to_lowercase().eq(b.to_lowercase())
+}
+
+/// `partial` notes that schemas never opened in the sidebar are not listed.
+pub fn render_palette(frame: &mut Frame, palette: &Palette, tables: &[TableInfo], partial: bool, theme: &Theme) {
+    let area = centered_rect(frame.area(), 70, 20);
+    frame.render_widget(Clear, area);
+
+    let block = popup_block(" Open Table ".to_string(), theme);
+    let inner = block.inner(area);
+    frame.render_widget(block, area);
+
+    let chunks = Layout::vertical([
+        Constraint::Length(3),
+        Constraint::Min(1),
+        Constraint::Length(1),
+    ])
+    .split(inner);
+
+    let mut input = palette.input.clone();
+    input.set_block(
+        Block::default()
+            .borders(Borders::ALL)
+            .border_style(theme.border_focused_style())
+            .style(Style::default().bg(theme.bg)),
+    );
+    input.set_style(theme.text_style());
+    input.set_cursor_style(Style::default().add_modifier(Modifier::REVERSED).bg(theme.accent));
+    frame.render_widget(&input, chunks[0]);
+
+    let matches = palette.matches(tables);
+    let height = chunks[1].height as usize;
+    let selected = palette.selected.min(matches.len().saturating_sub(1));
+    let offset = (selected + 1).saturating_sub(height);
+    let highlight = Style::default().fg(theme.accent).add_modifier(Modifier::BOLD);
+    let lines: Vec<Line> = matches
+        .iter()
+        .enumerate()
+        .skip(offset)
+        .take(height)
+        .map(|(i, m)| {
+            let base = if i == selected { theme.selected_style() } else { theme.text_style() };
+            let mut spans = vec![Span::styled(" ", base)];
+            spans.extend(m.label.chars().enumerate().map(|(ci, c)| {
+                let style = if m.positions.contains(&ci) { base.patch(highlight) } else { base };
+                Span::styled(c.to_string(), style)
+            }));
+            Line::from(spans)
+        })
+        .collect();
+    if lines.is_empty() {
+        frame.render_widget(Paragraph::new(" No matching tables").style(theme.muted_style()), chunks[1]);
+    } else {
+        frame.render_widget(Paragraph::new(lines), chunks[1]);
+    }
+
+    let hint = if partial {
+        "Enter: open  |  Esc: close  |  only schemas opened in the sidebar are listed"
+    } else {
+        "Enter: open  |  Esc: close"
+    };
+    frame.render_widget(Paragraph::new(hint).style(theme.muted_style()), chunks[2]);
+}
@@ -0,0 +1,110 @@
+use ratatui::{
+    layout::{Constraint, Layout},
+    style::{Modifier, Style},
+    text::Span,
+    widgets::{Clear, Paragraph},
+    Frame,
+};
+use tui_textarea::TextArea;
+
+use super::popup::{centered_rect, popup_block};
+use super::theme::Theme;
+
+/// Values for the bind parameters of a query about to run.
+#[derive(Debug, Clone)]
+pub struct ParamForm<'a> {
+    pub query: String,
+    pub labels: Vec<String>,
+    pub inputs: Vec<TextArea<'a>>,
+    pub selected: usize,
+}
+
+impl<'a> ParamForm<'a> {
+    /// One field per label, filled with `values` where given.
+    pub fn new(query: String, labels: Vec<String>, values: &[String]) -> Self {
+        let inputs = (0..labels.len())
+            .map(|i| {
+                let mut input = TextArea::default();
+                input.set_cursor_line_style(Style::default());
+                if let Some(value) = values.get(i) {
+                    input.insert_str(value);
+                }
+                input
+            })
+            .collect();
+        Self {
+            query,
+            labels,
+            inputs,
+            selected: 0,
+        }
+    }
+
+    pub fn select_next(&mut self) {
+        self.selected = (self.selected + 1) % self.inputs.len();
+    }
+
+    pub fn select_prev(&mut self) {
+        self.selected = (self.selected + self.inputs.len() - 1) % self.inputs.len();
+    }
+
+    pub fn input_mut(&mut self) -> &mut TextArea<'a> {
+        &mut self.inputs[self.selected]
+    }
+
+    /// The entered text of each field.
+    pub fn values(&self) -> Vec<String> {
+        self.inputs.iter().map(|input| input.lines().join("")).collect()
+    }
+}
+
+pub fn render_param_form(frame: &mut Frame, form: &ParamForm, theme: &Theme) {
+    let height = (form.inputs.len() as u16 + 4).min(frame.area().height.saturating_sub(2));
+    let area = centered_rect(frame.area(), 64, height);
+    frame.render_widget(Clear, area);
+
+    let block = popup_block(" Query parameters ".to_string(), theme);
+    let inner = block.inner(area);
+    frame.render_widget(block, area);
+
+    let chunks = Layout::vertical([
+        Constraint::Length(1),
+        Constraint::Min(1),
+        Constraint::Length(1),
+    ])
+    .split(inner);
+
+    frame.render_widget(
+        Paragraph::new("Values are typed as is; NULL binds NULL").style(theme.dim_style()),
+        chunks[0],
+    );
+
+    let label_width = form.labels.iter().map(|l| l.chars().count()).max().unwrap_or(0) as u16 + 2;
+    let visible = chunks[1].height as usize;
+    let offset = (form.selected + 1).saturating_sub(visible);
+    for (row, i) in (offset..form.inputs.len()).take(visible).enumerate() {
+        let line = ratatui::layout::Rect {
+            y: chunks[1].y + row as u16,
+            height: 1,
+            ..chunks[1]
+        };
+        let [label_area, input_area] =
+            Layout::horizontal([Constraint::Length(label_width), Constraint::Min(1)]).areas(line);
+        let style = if i == form.selected { theme.selected_style() } else { theme.text_style() };
+        frame.render_widget(Span::styled(format!(" {}", form.labels[i]), style), label_area);
+
+        let mut input = form.inputs[i].clone();
+        input.set_style(theme.text_style());
+        if i == form.selected {
+            input.set_cursor_style(Style::default().add_modifier(Modifier::REVERSED).bg(theme.accent));
+        } else {
+            input.set_cursor_style(theme.text_style());
+        }
+        frame.render_widget(&input, input_area);
+    }
+
+    frame.render_widget(
+        Paragraph::new("Enter: run  |  Tab/↑↓: next field  |  Esc: cancel").style(theme.muted_style()),
+        chunks[2],
+    );
+}
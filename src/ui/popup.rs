@@ -0,0 +1,27 @@
+use ratatui::{
+    layout::{Alignment, Rect},
+    style::Style,
+    widgets::{Block, Borders},
+};
+
+use super::theme::Theme;
+
+pub fn centered_rect(area: Rect, width: u16, height: u16) -> Rect {
+    let width = width.min(area.width);
+    let height = height.min(area.height);
+    Rect::new(
+        area.x + (area.width - width) / 2,
+        area.y + (area.height - height) / 2,
+        width,
+        height,
+    )
+}
+
+pub fn popup_block<'a>(title: String, theme: &Theme) -> Block<'a> {
+    Block::default()
+        .title(title)
+        .title_alignment(Alignment::Center)
+        .borders(Borders::ALL)
+        .border_style(theme.border_focused_style())
+        .style(Style::default().bg(theme.bg_secondary))
+}
@@ -0,0 +1,44 @@
+use ratatui::{
+    layout::{Constraint, Layout},
+    style::{Modifier, Style},
+    widgets::{Block, Borders, Clear, Paragraph},
+    Frame,
+};
+use tui_textarea::TextArea;
+
+use super::popup::{centered_rect, popup_block};
+use super::theme::Theme;
+
+pub fn render_prompt(frame: &mut Frame, title: &str, hint: &str, input: &TextArea, theme: &Theme) {
+    let area = centered_rect(frame.area(), 64, 7);
+    frame.render_widget(Clear, area);
+
+    let block = popup_block(format!(" {} ", title), theme);
+    let inner = block.inner(area);
+    frame.render_widget(block, area);
+
+    let chunks = Layout::vertical([
+        Constraint::Length(1),
+        Constraint::Length(3),
+        Constraint::Length(1),
+    ])
+    .split(inner);
+
+    frame.render_widget(Paragraph::new(hint).style(theme.dim_style()), chunks[0]);
+
+    let mut ta = input.clone();
+    ta.set_block(
+        Block::default()
+            .borders(Borders::ALL)
+            .border_style(theme.border_focused_style())
+            .style(Style::default().bg(theme.bg)),
+    );
+    ta.set_style(theme.text_style());
+    ta.set_cursor_style(Style::default().add_modifier(Modifier::REVERSED).bg(theme.accent));
+    frame.render_widget(&ta, chunks[1]);
+
+    frame.render_widget(
+        Paragraph::new("Enter: confirm  |  Esc: cancel").style(theme.muted_style()),
+        chunks[2],
+    );
+}
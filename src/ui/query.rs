@@ -1,25 +1,49 @@
 use ratatui::{
-    layout::{Constraint, Direction, Layout, Rect},
+    layout::Rect,
     style::{Modifier, Style},
+    text::{Line, Span},
     widgets::{Block, Borders, Paragraph},
     Frame,
 };
 use tui_textarea::TextArea;
+use unicode_width::UnicodeWidthChar;
 
 use super::theme::{icons, Theme};
+use crate::db::SQL_KEYWORDS;
+
+/// Editing mode of the SQL editor when `vim_mode` is on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum VimMode {
+    #[default]
+    Normal,
+    Insert,
+}
+
+impl VimMode {
+    fn label(self) -> &'static str {
+        match self {
+            VimMode::Normal => "NORMAL",
+            VimMode::Insert => "INSERT",
+        }
+    }
+}
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum QueryButton {
     None,
     Run,
+    Format,
     Clear,
     Copy,
+    Export,
 }
 
 pub struct ButtonRegion {
     pub run: Rect,
+    pub format: Rect,
     pub clear: Rect,
     pub copy: Rect,
+    pub export: Rect,
 }
 
 impl ButtonRegion {
@@ -27,16 +51,23 @@ impl ButtonRegion {
         if self.run.x <= x && x < self.run.x + self.run.width && self.run.y <= y && y < self.run.y + self.run.height {
             return QueryButton::Run;
         }
+        if self.format.x <= x && x < self.format.x + self.format.width && self.format.y <= y && y < self.format.y + self.format.height {
+            return QueryButton::Format;
+        }
         if self.clear.x <= x && x < self.clear.x + self.clear.width && self.clear.y <= y && y < self.clear.y + self.clear.height {
             return QueryButton::Clear;
         }
         if self.copy.x <= x && x < self.copy.x + self.copy.width && self.copy.y <= y && y < self.copy.y + self.copy.height {
             return QueryButton::Copy;
         }
+        if self.export.x <= x && x < self.export.x + self.export.width && self.export.y <= y && y < self.export.y + self.export.height {
+            return QueryButton::Export;
+        }
         QueryButton::None
     }
 }
 
+#[allow(clippy::too_many_arguments)]
 pub fn render_query_panel(
     frame: &mut Frame,
     area: Rect,
@@ -44,27 +75,43 @@ pub fn render_query_panel(
     focused: bool,
     selected_button: QueryButton,
     hovered_button: QueryButton,
+    read_only: bool,
+    vim_mode: Option<VimMode>,
     theme: &Theme,
 ) -> ButtonRegion {
     let editor_area = area;
 
     let run_width = 10u16;
+    let format_width = 12u16;
     let clear_width = 11u16;
     let copy_width = 10u16;
+    let export_width = 12u16;
     let spacing = 1u16;
-    let total_buttons_width = run_width + clear_width + copy_width + spacing * 2;
+    let total_buttons_width = run_width + format_width + clear_width + copy_width + export_width + spacing * 4;
 
     let buttons_x = area.x + area.width.saturating_sub(total_buttons_width + 2);
     let buttons_y = area.y;
 
-    let run_rect = Rect::new(buttons_x, buttons_y, run_width, 1);
-    let clear_rect = Rect::new(buttons_x + run_width + spacing, buttons_y, clear_width, 1);
-    let copy_rect = Rect::new(buttons_x + run_width + spacing + clear_width + spacing, buttons_y, copy_width, 1);
+    // Clip to the panel so a narrow terminal never renders outside the buffer.
+    let run_rect = Rect::new(buttons_x, buttons_y, run_width, 1).intersection(area);
+    let format_rect = Rect::new(buttons_x + run_width + spacing, buttons_y, format_width, 1).intersection(area);
+    let clear_x = buttons_x + run_width + spacing + format_width + spacing;
+    let clear_rect = Rect::new(clear_x, buttons_y, clear_width, 1).intersection(area);
+    let copy_rect = Rect::new(clear_x + clear_width + spacing, buttons_y, copy_width, 1).intersection(area);
+    let export_x = clear_x + clear_width + spacing + copy_width + spacing;
+    let export_rect = Rect::new(export_x, buttons_y, export_width, 1).intersection(area);
 
     let mut ta = textarea.clone();
+    let mut title = vec![Span::raw(" SQL ")];
+    if read_only {
+        title.push(Span::styled("READ-ONLY ", theme.error_style().add_modifier(Modifier::BOLD)));
+    }
+    if let Some(mode) = vim_mode {
+        title.push(Span::styled(format!("{} ", mode.label()), theme.accent_style().add_modifier(Modifier::BOLD)));
+    }
     ta.set_block(
         Block::default()
-            .title(" SQL ")
+            .title(Line::from(title))
             .borders(Borders::ALL)
             .border_style(theme.block_style(focused))
             .style(Style::default().bg(theme.bg_secondary)),
@@ -73,23 +120,32 @@ pub fn render_query_panel(
     ta.set_cursor_style(Style::default().add_modifier(Modifier::REVERSED).bg(theme.accent));
 
     frame.render_widget(&ta, editor_area);
+    highlight_editor(frame, Block::default().borders(Borders::ALL).inner(editor_area), textarea, theme);
 
     let run_style = get_button_style(QueryButton::Run, selected_button, hovered_button, theme);
+    let format_style = get_button_style(QueryButton::Format, selected_button, hovered_button, theme);
     let clear_style = get_button_style(QueryButton::Clear, selected_button, hovered_button, theme);
     let copy_style = get_button_style(QueryButton::Copy, selected_button, hovered_button, theme);
+    let export_style = get_button_style(QueryButton::Export, selected_button, hovered_button, theme);
 
     let run_text = format!(" {} Run ", icons::PLAY);
+    let format_text = format!(" {} Format ", icons::FORMAT);
     let clear_text = format!(" {} Clear ", icons::CLEAR);
     let copy_text = format!(" {} Copy ", icons::COPY);
+    let export_text = format!(" {} Export ", icons::EXPORT);
 
     frame.render_widget(Paragraph::new(run_text).style(run_style), run_rect);
+    frame.render_widget(Paragraph::new(format_text).style(format_style), format_rect);
     frame.render_widget(Paragraph::new(clear_text).style(clear_style), clear_rect);
     frame.render_widget(Paragraph::new(copy_text).style(copy_style), copy_rect);
+    frame.render_widget(Paragraph::new(export_text).style(export_style), export_rect);
 
     ButtonRegion {
         run: run_rect,
+        format: format_rect,
         clear: clear_rect,
         copy: copy_rect,
+        export: export_rect,
     }
 }
 
@@ -102,3 +158,189 @@ fn get_button_style(button: QueryButton, selected: QueryButton, hovered: QueryBu
         theme.button_style()
     }
 }
+
+/// Styles the keywords, string literals, numbers and comments of one line
+/// of SQL.
+pub fn highlight_sql_line(line: &str, theme: &Theme) -> Line<'static> {
+    highlight_line(line, theme, &mut false)
+}
+
+/// Like `highlight_sql_line`, carrying an open `/* */` comment over from
+/// the previous line and on to the next one.
+fn highlight_line(line: &str, theme: &Theme, in_comment: &mut bool) -> Line<'static> {
+    let comment = theme.muted_style().add_modifier(Modifier::ITALIC);
+    let chars: Vec<char> = line.chars().collect();
+    let mut spans = Vec::new();
+    let mut i = 0;
+    while i < chars.len() {
+        let start = i;
+        let style = if *in_comment || (chars[i] == '/' && chars.get(i + 1) == Some(&'*')) {
+            if !*in_comment {
+                i += 2;
+                *in_comment = true;
+            }
+            while i < chars.len() && !(chars[i] == '*' && chars.get(i + 1) == Some(&'/')) {
+                i += 1;
+            }
+            if i < chars.len() {
+                i += 2;
+                *in_comment = false;
+            }
+            comment
+        } else if chars[i] == '-' && chars.get(i + 1) == Some(&'-') {
+            i = chars.len();
+            comment
+        } else if chars[i] == '\'' {
+            i += 1;
+            while i < chars.len() {
+                i += 1;
+                if chars[i - 1] == '\'' {
+                    if chars.get(i) == Some(&'\'') {
+                        i += 1;
+                    } else {
+                        break;
+                    }
+                }
+            }
+            Style::default().fg(theme.table)
+        } else if chars[i].is_ascii_digit() {
+            while i < chars.len() && (chars[i].is_ascii_alphanumeric() || chars[i] == '.') {
+                i += 1;
+            }
+            Style::default().fg(theme.schema)
+        } else if chars[i].is_alphabetic() || chars[i] == '_' {
+            while i < chars.len() && (chars[i].is_alphanumeric() || chars[i] == '_') {
+                i += 1;
+            }
+            let word: String = chars[start..i].iter().collect();
+            if SQL_KEYWORDS.contains(&word.to_ascii_uppercase().as_str()) {
+                Style::default().fg(theme.accent).add_modifier(Modifier::BOLD)
+            } else {
+                theme.text_style()
+            }
+        } else {
+            i += 1;
+            theme.text_style()
+        };
+        spans.push(Span::styled(chars[start..i].iter().collect::<String>(), style));
+    }
+    Line::from(spans)
+}
+
+/// First line and column the editor shows. It is rendered from a fresh
+/// copy each frame, so it scrolls just far enough to keep the cursor in view.
+fn editor_scroll(inner: Rect, textarea: &TextArea) -> (usize, usize) {
+    let (cursor_row, cursor_col) = textarea.cursor();
+    (
+        (cursor_row + 1).saturating_sub(inner.height as usize),
+        (cursor_col + 1).saturating_sub(inner.width as usize),
+    )
+}
+
+/// Screen position of the editor's cursor, for an editor drawn in `area`.
+pub fn editor_cursor_position(area: Rect, textarea: &TextArea) -> (u16, u16) {
+    let inner = Block::default().borders(Borders::ALL).inner(area);
+    let (top_row, top_col) = editor_scroll(inner, textarea);
+    let (cursor_row, cursor_col) = textarea.cursor();
+    (
+        inner.x + (cursor_col - top_col) as u16,
+        inner.y + (cursor_row - top_row) as u16,
+    )
+}
+
+/// Recolors the text the editor just rendered with `highlight_sql_line`.
+/// Only foregrounds change, so the cursor and selection stay visible.
+fn highlight_editor(frame: &mut Frame, inner: Rect, textarea: &TextArea, theme: &Theme) {
+    let (top_row, top_col) = editor_scroll(inner, textarea);
+    let tab = textarea.tab_length().max(1) as usize;
+
+    let buf = frame.buffer_mut();
+    let mut in_comment = false;
+    for (row, line) in textarea.lines().iter().enumerate().take(top_row + inner.height as usize) {
+        let highlighted = highlight_line(line, theme, &mut in_comment);
+        if row < top_row {
+            continue;
+        }
+        let y = inner.y + (row - top_row) as u16;
+        let mut col = 0;
+        for span in &highlighted.spans {
+            for c in span.content.chars() {
+                let width = if c == '\t' { tab - col % tab } else { c.width().unwrap_or(0) };
+                if col >= top_col && col - top_col < inner.width as usize {
+                    let cell = &mut buf[(inner.x + (col - top_col) as u16, y)];
+                    if let Some(fg) = span.style.fg {
+                        cell.set_fg(fg);
+                    }
+                    cell.modifier.insert(span.style.add_modifier);
+                }
+                col += width;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Each span's text and style, in order.
+    fn spans(line: &str, theme: &Theme) -> Vec<(String, Style)> {
+        highlight_sql_line(line, theme)
+            .spans
+            .into_iter()
+            .map(|span| (span.content.into_owned(), span.style))
+            .collect()
+    }
+
+    #[test]
+    fn highlights_keywords_in_any_case() {
+        let theme = Theme::default();
+        let keyword = Style::default().fg(theme.accent).add_modifier(Modifier::BOLD);
+        let spans = spans("select id FROM users", &theme);
+        assert_eq!(spans[0], ("select".to_string(), keyword));
+        assert_eq!(spans[4], ("FROM".to_string(), keyword));
+    }
+
+    #[test]
+    fn leaves_identifiers_plain() {
+        let theme = Theme::default();
+        let spans = spans("SELECT user_name, t2 FROM orders", &theme);
+        assert_eq!(spans[2], ("user_name".to_string(), theme.text_style()));
+        assert_eq!(spans[5], ("t2".to_string(), theme.text_style()));
+        assert_eq!(spans[9], ("orders".to_string(), theme.text_style()));
+    }
+
+    #[test]
+    fn highlights_strings_with_escaped_quotes() {
+        let theme = Theme::default();
+        let spans = spans("WHERE name = 'it''s -- not a comment' AND", &theme);
+        assert_eq!(spans[6], ("'it''s -- not a comment'".to_string(), Style::default().fg(theme.table)));
+        assert_eq!(spans.last().unwrap().0, "AND");
+    }
+
+    #[test]
+    fn highlights_comments() {
+        let theme = Theme::default();
+        let comment = theme.muted_style().add_modifier(Modifier::ITALIC);
+        let line = spans("SELECT 1 -- the rest, SELECT included", &theme);
+        assert_eq!(line.last().unwrap(), &("-- the rest, SELECT included".to_string(), comment));
+
+        let line = spans("SELECT /* a, b */ c", &theme);
+        assert_eq!(line[2], ("/* a, b */".to_string(), comment));
+        assert_eq!(line[4], ("c".to_string(), theme.text_style()));
+    }
+
+    #[test]
+    fn carries_block_comments_across_lines() {
+        let theme = Theme::default();
+        let comment = theme.muted_style().add_modifier(Modifier::ITALIC);
+        let mut in_comment = false;
+        let first = highlight_line("SELECT /* open", &theme, &mut in_comment);
+        assert!(in_comment);
+        assert_eq!(first.spans[2].style, comment);
+        let second = highlight_line("still */ FROM", &theme, &mut in_comment);
+        assert!(!in_comment);
+        assert_eq!(second.spans[0].content, "still */");
+        assert_eq!(second.spans[0].style, comment);
+    }
+}
@@ -1,11 +1,17 @@
+use std::collections::HashSet;
+use std::time::Duration;
+
 use ratatui::{
     layout::{Constraint, Rect},
-    style::Style,
-    widgets::{Block, Borders, Cell, Row, Scrollbar, ScrollbarOrientation, ScrollbarState, Table},
+    style::{Modifier, Style},
+    text::{Line, Span, Text},
+    widgets::{Block, Borders, Cell, Clear, Paragraph, Row, Scrollbar, ScrollbarOrientation, ScrollbarState, Table},
     Frame,
 };
-use unicode_width::UnicodeWidthStr;
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
 
+use crate::app::StatusMessage;
+use crate::config::{Config, TruncateMode};
 use crate::db::QueryResult;
 use super::theme::Theme;
 
@@ -21,48 +27,134 @@ pub struct ScrollbarRegion {
 
 impl ScrollbarRegion {
     pub fn hit_test_vertical(&self, x: u16, y: u16) -> Option<f32> {
-        if let Some(rect) = self.vertical {
-            if x >= rect.x && x < rect.x + rect.width && y >= rect.y && y < rect.y + rect.height {
-                let relative_y = y.saturating_sub(rect.y) as f32;
-                let ratio = relative_y / rect.height as f32;
-                return Some(ratio.clamp(0.0, 1.0));
-            }
+        if let Some(rect) = self.vertical
+            && x >= rect.x && x < rect.x + rect.width && y >= rect.y && y < rect.y + rect.height
+        {
+            let relative_y = y.saturating_sub(rect.y) as f32;
+            let ratio = relative_y / rect.height as f32;
+            return Some(ratio.clamp(0.0, 1.0));
         }
         None
     }
 
     pub fn hit_test_horizontal(&self, x: u16, y: u16) -> Option<f32> {
-        if let Some(rect) = self.horizontal {
-            if x >= rect.x && x < rect.x + rect.width && y >= rect.y && y < rect.y + rect.height {
-                let relative_x = x.saturating_sub(rect.x) as f32;
-                let ratio = relative_x / rect.width as f32;
-                return Some(ratio.clamp(0.0, 1.0));
-            }
+        if let Some(rect) = self.horizontal
+            && x >= rect.x && x < rect.x + rect.width && y >= rect.y && y < rect.y + rect.height
+        {
+            let relative_x = x.saturating_sub(rect.x) as f32;
+            let ratio = relative_x / rect.width as f32;
+            return Some(ratio.clamp(0.0, 1.0));
         }
         None
     }
 }
 
+/// Screen span of each rendered header cell, for click-to-sort.
+#[derive(Debug, Clone, Default)]
+pub struct HeaderRegion {
+    pub y: u16,
+    /// (x, width, column index)
+    pub cells: Vec<(u16, u16, usize)>,
+}
+
+impl HeaderRegion {
+    pub fn hit_test(&self, x: u16, y: u16) -> Option<usize> {
+        if y != self.y {
+            return None;
+        }
+        self.cells
+            .iter()
+            .find(|(cx, width, _)| x >= *cx && x < cx + width)
+            .map(|(_, _, col)| *col)
+    }
+}
+
 #[derive(Debug, Default)]
 pub struct ResultsState {
     pub selected_row: usize,
     pub scroll_offset: usize,
+    pub selected_col: usize,
+    /// First rendered column, counted among the visible ones that scroll.
     pub horizontal_scroll: usize,
+    /// The first visible column stays at the left edge while the rest scroll.
+    pub freeze_first_col: bool,
     pub column_widths: Vec<u16>,
     pub scrollbar_region: ScrollbarRegion,
+    /// Client-side row filter typed after `/`.
+    pub search: String,
+    pub searching: bool,
+    /// Client-side sort set by clicking a header.
+    pub sort_col: Option<usize>,
+    pub sort_asc: bool,
+    pub header_region: HeaderRegion,
+    /// Characters of a cell shown before it is truncated; `+`/`-` adjust it.
+    pub max_cell_width: usize,
+    /// A leading `#` column numbers the rows; not part of the data.
+    pub show_row_numbers: bool,
+    /// Columns left out of the grid and of exports.
+    pub hidden_cols: HashSet<usize>,
+    /// A dim second header line gives each column's SQL type.
+    pub show_column_types: bool,
+    /// The selected row is shown one column per line instead of the grid.
+    pub record_view: bool,
+    /// Lines scrolled in the record view.
+    pub record_scroll: usize,
 }
 
+pub const DEFAULT_MAX_CELL_WIDTH: usize = 47;
+const MIN_MAX_CELL_WIDTH: usize = 10;
+const MAX_MAX_CELL_WIDTH: usize = 500;
+const CELL_WIDTH_STEP: usize = 10;
+
 impl ResultsState {
     pub fn new() -> Self {
-        Self::default()
+        Self {
+            max_cell_width: DEFAULT_MAX_CELL_WIDTH,
+            ..Self::default()
+        }
+    }
+
+    pub fn widen_cells(&mut self) {
+        self.max_cell_width = (self.max_cell_width + CELL_WIDTH_STEP).min(MAX_MAX_CELL_WIDTH);
+        self.column_widths.clear();
+    }
+
+    pub fn narrow_cells(&mut self) {
+        self.max_cell_width = self.max_cell_width.saturating_sub(CELL_WIDTH_STEP).max(MIN_MAX_CELL_WIDTH);
+        self.column_widths.clear();
     }
 
     pub fn reset(&mut self) {
         self.selected_row = 0;
         self.scroll_offset = 0;
+        self.selected_col = 0;
         self.horizontal_scroll = 0;
         self.column_widths.clear();
         self.scrollbar_region = ScrollbarRegion::default();
+        self.search.clear();
+        self.searching = false;
+        self.sort_col = None;
+        self.header_region = HeaderRegion::default();
+        self.record_scroll = 0;
+        self.hidden_cols.clear();
+    }
+
+    /// Indices of the rendered columns that aren't hidden.
+    pub fn visible_columns(&self) -> Vec<usize> {
+        (0..self.column_widths.len()).filter(|i| !self.hidden_cols.contains(i)).collect()
+    }
+
+    /// Hides or shows column `col` of `count`. The last visible column can't
+    /// be hidden; returns false then.
+    pub fn toggle_column(&mut self, col: usize, count: usize) -> bool {
+        if self.hidden_cols.remove(&col) {
+            return true;
+        }
+        if (0..count).filter(|i| !self.hidden_cols.contains(i)).count() <= 1 {
+            return false;
+        }
+        self.hidden_cols.insert(col);
+        true
     }
 
     pub fn scroll_to_vertical_ratio(&mut self, ratio: f32, total_rows: usize) {
@@ -77,6 +169,11 @@ impl ResultsState {
     pub fn scroll_to_horizontal_ratio(&mut self, ratio: f32) {
         let max_scroll = self.scrollbar_region.horizontal_content_length;
         self.horizontal_scroll = ((ratio * max_scroll as f32) as usize).min(max_scroll);
+        let visible = self.visible_columns();
+        let frozen = usize::from(self.freeze_first_col && visible.len() > 1);
+        if let Some(&col) = visible.get(frozen + self.horizontal_scroll) {
+            self.selected_col = col;
+        }
     }
 
     pub fn select_next(&mut self, total_rows: usize) {
@@ -97,17 +194,46 @@ impl ResultsState {
         }
     }
 
-    pub fn scroll_left(&mut self) {
-        self.horizontal_scroll = self.horizontal_scroll.saturating_sub(1);
+    pub fn select_first(&mut self) {
+        self.selected_row = 0;
+        self.scroll_offset = 0;
+    }
+
+    pub fn select_last(&mut self, total_rows: usize) {
+        self.selected_row = total_rows.saturating_sub(1);
+        self.scroll_offset = total_rows.saturating_sub(self.scrollbar_region.visible_height.max(1));
+    }
+
+    /// Moves the selection and the view up by `visible_height` rows.
+    pub fn page_up(&mut self, visible_height: usize) {
+        let step = visible_height.max(1);
+        self.selected_row = self.selected_row.saturating_sub(step);
+        self.scroll_offset = self.scroll_offset.saturating_sub(step);
     }
 
-    pub fn scroll_right(&mut self, max_scroll: usize) {
-        if self.horizontal_scroll < max_scroll {
-            self.horizontal_scroll += 1;
+    /// Moves the selection and the view down by `visible_height` rows,
+    /// stopping at the last row.
+    pub fn page_down(&mut self, visible_height: usize, total_rows: usize) {
+        let step = visible_height.max(1);
+        let last = total_rows.saturating_sub(1);
+        self.selected_row = (self.selected_row + step).min(last);
+        self.scroll_offset = (self.scroll_offset + step).min(total_rows.saturating_sub(step));
+    }
+
+    pub fn select_next_col(&mut self) {
+        if let Some(col) = (self.selected_col + 1..self.column_widths.len()).find(|i| !self.hidden_cols.contains(i)) {
+            self.selected_col = col;
+        }
+    }
+
+    pub fn select_prev_col(&mut self) {
+        if let Some(col) = (0..self.selected_col).rev().find(|i| !self.hidden_cols.contains(i)) {
+            self.selected_col = col;
         }
     }
 
-    pub fn calculate_column_widths(&mut self, result: &QueryResult, _max_width: u16) {
+    /// Widths of the first `columns` columns, the ones that get rendered.
+    pub fn calculate_column_widths(&mut self, result: &QueryResult, columns: usize) {
         if result.columns.is_empty() {
             self.column_widths.clear();
             return;
@@ -116,20 +242,22 @@ impl ResultsState {
         let mut widths: Vec<u16> = result
             .columns
             .iter()
-            .map(|h| (h.width() as u16).max(8).min(40))
+            .take(columns)
+            .map(|h| (h.width() as u16).clamp(8, 40))
             .collect();
 
+        // A truncated cell is `max_cell_width` plus its ellipsis.
+        let max_col_width = (self.max_cell_width + 3).min(u16::MAX as usize) as u16;
         for row in &result.rows {
-            for (i, cell) in row.iter().enumerate() {
+            for (i, cell) in row.iter().take(columns).enumerate() {
                 if i < widths.len() {
-                    let cell_width = (cell.width() as u16).max(8).min(50);
+                    let cell_width = (cell.as_deref().unwrap_or("NULL").width() as u16).clamp(8, max_col_width);
                     widths[i] = widths[i].max(cell_width);
                 }
             }
         }
 
         let min_col_width = 12u16;
-        let max_col_width = 50u16;
         
         for w in &mut widths {
             *w = (*w + 2).clamp(min_col_width, max_col_width);
@@ -139,42 +267,159 @@ impl ResultsState {
     }
 }
 
+#[allow(clippy::too_many_arguments)]
 pub fn render_results(
     frame: &mut Frame,
     area: Rect,
     result: &QueryResult,
     state: &mut ResultsState,
     focused: bool,
+    status: Option<&StatusMessage>,
+    config: &Config,
     theme: &Theme,
 ) {
+    if area.is_empty() {
+        return;
+    }
+
+    if state.record_view && !result.rows.is_empty() {
+        render_record(frame, area, result, state, focused, status, theme);
+        return;
+    }
+
     if result.columns.is_empty() {
         let block = Block::default()
             .title(" Results ")
+            .title(status_line(status, theme))
             .borders(Borders::ALL)
             .border_style(theme.block_style(focused))
             .style(Style::default().bg(theme.bg_secondary));
-        frame.render_widget(block, area);
+        if result.is_command {
+            let rows = if result.affected_rows == 1 { "row" } else { "rows" };
+            let mut message = format!("{} {} affected", result.affected_rows, rows);
+            if result.statements > 1 {
+                message = format!("{} statements executed, {}", result.statements, message);
+            }
+            if let Some(elapsed) = result.elapsed {
+                message.push_str(&format!(" in {}", format_elapsed(elapsed)));
+            }
+            let paragraph = Paragraph::new(message).style(theme.accent_style()).block(block);
+            frame.render_widget(paragraph, area);
+        } else {
+            frame.render_widget(block, area);
+        }
         return;
     }
 
-    if state.column_widths.is_empty() || state.column_widths.len() != result.columns.len() {
-        state.calculate_column_widths(result, area.width);
+    // Very wide results are capped so width calculation and rendering stay fast.
+    let total_columns = result.columns.len();
+    let shown_columns = match config.max_columns {
+        0 => total_columns,
+        max => total_columns.min(max),
+    };
+
+    if state.column_widths.is_empty() || state.column_widths.len() != shown_columns {
+        state.calculate_column_widths(result, shown_columns);
     }
 
-    let visible_height = area.height.saturating_sub(4) as usize;
+    let show_search = state.searching || !state.search.is_empty();
+    let row_lines = config.density.row_lines();
+    let header_lines: u16 = if state.show_column_types { 2 } else { 1 };
+    let reserved = if show_search { 4 } else { 3 } + header_lines;
+    let visible_height = (area.height.saturating_sub(reserved) as usize / row_lines).max(1);
 
     if state.selected_row < state.scroll_offset {
         state.scroll_offset = state.selected_row;
     } else if state.selected_row >= state.scroll_offset + visible_height {
-        state.scroll_offset = state.selected_row.saturating_sub(visible_height - 1);
+        state.scroll_offset = state.selected_row.saturating_sub(visible_height.saturating_sub(1));
     }
 
-    let header_cells: Vec<Cell> = result
-        .columns
+    let page_offset = result.page_offset.unwrap_or(0);
+    let number_width = if state.show_row_numbers {
+        (page_offset + result.rows.len()).max(1).to_string().len() as u16
+    } else {
+        0
+    };
+    let number_space = if state.show_row_numbers { number_width + 1 } else { 0 };
+    let content_width = area.width.saturating_sub(3 + number_space);
+
+    // Scrolling works on the visible columns only; the selection moves off
+    // a column that was just hidden.
+    let mut visible_cols = state.visible_columns();
+    if visible_cols.is_empty() {
+        state.hidden_cols.clear();
+        visible_cols = state.visible_columns();
+    }
+    let selected_pos = visible_cols
+        .iter()
+        .position(|&i| i >= state.selected_col)
+        .unwrap_or(visible_cols.len() - 1);
+    state.selected_col = visible_cols[selected_pos];
+
+    // A frozen first column is drawn before the scrolling ones and takes
+    // its width out of the space they share.
+    let frozen = (state.freeze_first_col && visible_cols.len() > 1).then(|| visible_cols[0]);
+    let frozen_width = frozen.map_or(0, |i| state.column_widths[i] + 1);
+    let scroll_cols = &visible_cols[usize::from(frozen.is_some())..];
+    let scroll_widths: Vec<u16> = scroll_cols.iter().map(|&i| state.column_widths[i]).collect();
+    let scroll_width = content_width.saturating_sub(frozen_width);
+    let max_first_column = max_first_column(&scroll_widths, scroll_width);
+    state.horizontal_scroll = state.horizontal_scroll.min(max_first_column);
+    if let Some(selected_pos) = scroll_cols.iter().position(|&i| i == state.selected_col) {
+        state.horizontal_scroll = state.horizontal_scroll.min(selected_pos);
+        // Scroll right until the selected column fits.
+        while state.horizontal_scroll < selected_pos
+            && scroll_widths[state.horizontal_scroll..=selected_pos]
+                .iter()
+                .map(|w| w + 1)
+                .sum::<u16>()
+                > scroll_width + 1
+        {
+            state.horizontal_scroll += 1;
+        }
+    }
+    let first_column = state.horizontal_scroll;
+
+    // Only what fits is rendered, the last column cut short, so the table
+    // never squeezes every column (the frozen one included) to make room.
+    let mut rendered_cols: Vec<usize> = frozen.into_iter().collect();
+    let mut rendered_widths: Vec<u16> = frozen.map(|i| state.column_widths[i]).into_iter().collect();
+    let mut remaining = scroll_width + 1;
+    for (&i, &w) in scroll_cols[first_column..].iter().zip(&scroll_widths[first_column..]) {
+        if remaining <= 1 {
+            break;
+        }
+        rendered_cols.push(i);
+        rendered_widths.push(w.min(remaining - 1));
+        remaining = remaining.saturating_sub(w + 1);
+    }
+
+    let truncate_modes: Vec<TruncateMode> = rendered_cols
         .iter()
-        .map(|h| Cell::from(h.clone()).style(theme.header_style()))
+        .map(|&i| config.truncate_mode_for(&result.columns[i]))
         .collect();
-    let header = Row::new(header_cells).height(1);
+
+    let mut header_cells: Vec<Cell> = rendered_cols
+        .iter()
+        .map(|&i| (i, &result.columns[i]))
+        .map(|(i, h)| {
+            let text = match state.sort_col {
+                Some(col) if col == i && state.sort_asc => format!("{} ▲", h),
+                Some(col) if col == i => format!("{} ▼", h),
+                _ => h.clone(),
+            };
+            let mut lines = vec![Line::styled(text, theme.header_style())];
+            if state.show_column_types {
+                let type_name = result.column_types.get(i).map_or("", String::as_str);
+                lines.push(Line::styled(type_name.to_string(), theme.dim_style()));
+            }
+            Cell::from(Text::from(lines))
+        })
+        .collect();
+    if state.show_row_numbers {
+        header_cells.insert(0, Cell::from(format!("{:>1$}", "#", number_width as usize)).style(theme.dim_style()));
+    }
+    let header = Row::new(header_cells).height(header_lines);
 
     let rows: Vec<Row> = result
         .rows
@@ -192,34 +437,71 @@ pub fn render_results(
                 Style::default().bg(theme.bg)
             };
 
-            let cells: Vec<Cell> = row
+            let mut cells: Vec<Cell> = rendered_cols
                 .iter()
-                .map(|c| {
-                    let display = if c.len() > 47 {
-                        format!("{}...", &c[..47])
+                .zip(&truncate_modes)
+                .map(|(&col, &mode)| {
+                    let (c, column) = (&row[col], &result.columns[col]);
+                    let selected = is_selected && col == state.selected_col;
+                    let Some(c) = c else {
+                        let style = if selected { theme.selected_cell_style() } else { theme.null_style() };
+                        return Cell::from("NULL").style(style);
+                    };
+                    let style = if selected {
+                        theme.selected_cell_style()
                     } else {
-                        c.clone()
+                        cell_style(column, c, config, theme)
                     };
-                    Cell::from(display).style(theme.text_style())
+                    Cell::from(truncate_cell(c, mode, state.max_cell_width)).style(style)
                 })
                 .collect();
-            Row::new(cells).height(1).style(row_style)
+            if state.show_row_numbers {
+                let number = format!("{:>1$}", page_offset + idx + 1, number_width as usize);
+                cells.insert(0, Cell::from(number).style(theme.dim_style()));
+            }
+            Row::new(cells)
+                .height(1)
+                .bottom_margin(row_lines as u16 - 1)
+                .style(row_style)
         })
         .collect();
 
     let widths: Vec<Constraint> = state
-        .column_widths
-        .iter()
-        .map(|&w| Constraint::Length(w))
+        .show_row_numbers
+        .then_some(Constraint::Length(number_width))
+        .into_iter()
+        .chain(rendered_widths.iter().map(|&w| Constraint::Length(w)))
         .collect();
 
-    let title = format!(" Results ({} rows) ", result.rows.len());
+    let mut count = match result.page_offset {
+        Some(offset) if !result.rows.is_empty() => {
+            format!("rows {}–{}", offset + 1, offset + result.rows.len())
+        }
+        _ => format!("{} rows", result.rows.len()),
+    };
+    if let Some(elapsed) = result.elapsed {
+        count.push_str(&format!(" in {}", format_elapsed(elapsed)));
+    }
+    let mut title = match &result.source_table {
+        Some(table) if config.qualified_table_names => {
+            format!(" Results: {}.{} ({}) ", table.schema, table.name, count)
+        }
+        Some(table) => format!(" Results: {} ({}) ", table.name, count),
+        None => format!(" Results ({}) ", count),
+    };
+    if shown_columns < total_columns {
+        title.push_str(&format!("· showing {} of {} columns (max_columns) ", shown_columns, total_columns));
+    }
+    if !state.hidden_cols.is_empty() {
+        title.push_str(&format!("· {} hidden (V) ", state.hidden_cols.len()));
+    }
 
     let table = Table::new(rows, widths)
         .header(header)
         .block(
             Block::default()
                 .title(title)
+                .title(status_line(status, theme))
                 .borders(Borders::ALL)
                 .border_style(theme.block_style(focused))
                 .style(Style::default().bg(theme.bg_secondary)),
@@ -229,12 +511,40 @@ pub fn render_results(
 
     frame.render_widget(table, area);
 
+    if show_search && area.height > 3 {
+        let cursor = if state.searching { "█" } else { "  (n/N, Esc clears)" };
+        let search_area = Rect::new(area.x + 1, area.y + area.height - 2, area.width.saturating_sub(2), 1);
+        frame.render_widget(Clear, search_area);
+        frame.render_widget(
+            Paragraph::new(Line::from(vec![
+                Span::styled("/", theme.accent_style()),
+                Span::styled(state.search.as_str(), theme.text_style()),
+                Span::styled(cursor, theme.muted_style()),
+            ]))
+            .style(Style::default().bg(theme.bg_secondary)),
+            search_area,
+        );
+    }
+
+    let mut x = area.x + 1 + number_space;
+    let right = area.right().saturating_sub(1);
+    state.header_region = HeaderRegion { y: area.y + 1, cells: Vec::new() };
+    for (&i, &w) in rendered_cols.iter().zip(&rendered_widths) {
+        if x >= right {
+            break;
+        }
+        state.header_region.cells.push((x, w.min(right - x), i));
+        x = x.saturating_add(w + 1);
+    }
+
     state.scrollbar_region.visible_height = visible_height;
     state.scrollbar_region.visible_width = area.width.saturating_sub(3);
     state.scrollbar_region.vertical = None;
     state.scrollbar_region.horizontal = None;
+    state.scrollbar_region.horizontal_content_length = max_first_column;
 
-    if result.rows.len() > visible_height {
+    // Scrollbars sit inside the border, so skip them when there's no room.
+    if result.rows.len() > visible_height && area.width > 2 && area.height > 3 {
         let scrollbar = Scrollbar::new(ScrollbarOrientation::VerticalRight)
             .begin_symbol(Some("▲"))
             .end_symbol(Some("▼"))
@@ -246,9 +556,9 @@ pub fn render_results(
 
         let scrollbar_area = Rect::new(
             area.x + area.width - 1,
-            area.y + 2,
+            area.y + 1 + header_lines,
             1,
-            area.height.saturating_sub(3),
+            area.height.saturating_sub(2 + header_lines),
         );
 
         state.scrollbar_region.vertical = Some(scrollbar_area);
@@ -257,18 +567,15 @@ pub fn render_results(
         frame.render_stateful_widget(scrollbar, scrollbar_area, &mut scrollbar_state);
     }
 
-    let total_width: u16 = state.column_widths.iter().sum::<u16>() + state.column_widths.len() as u16;
-    let content_width = area.width.saturating_sub(3);
-    if total_width > content_width {
+    if max_first_column > 0 && area.width > 2 && area.height > 2 {
         let scrollbar = Scrollbar::new(ScrollbarOrientation::HorizontalBottom)
             .begin_symbol(Some("◀"))
             .end_symbol(Some("▶"))
             .track_symbol(Some("─"))
             .thumb_symbol("█");
 
-        let max_h_scroll = (total_width.saturating_sub(content_width)) as usize;
-        let mut scrollbar_state = ScrollbarState::new(max_h_scroll)
-            .position(state.horizontal_scroll);
+        let mut scrollbar_state = ScrollbarState::new(max_first_column)
+            .position(first_column);
 
         let scrollbar_area = Rect::new(
             area.x + 1,
@@ -278,8 +585,181 @@ pub fn render_results(
         );
 
         state.scrollbar_region.horizontal = Some(scrollbar_area);
-        state.scrollbar_region.horizontal_content_length = max_h_scroll;
 
         frame.render_stateful_widget(scrollbar, scrollbar_area, &mut scrollbar_state);
     }
 }
+
+/// Largest first column that still fills `available` cells, so scrolling
+/// stops once the last column is in view.
+fn max_first_column(widths: &[u16], available: u16) -> usize {
+    let mut used = 0u16;
+    for (i, &w) in widths.iter().enumerate().rev() {
+        used = used.saturating_add(w + 1);
+        if used > available.saturating_add(1) {
+            return (i + 1).min(widths.len().saturating_sub(1));
+        }
+    }
+    0
+}
+
+/// The selected row, one `column: value` per line with values in full.
+fn render_record(
+    frame: &mut Frame,
+    area: Rect,
+    result: &QueryResult,
+    state: &mut ResultsState,
+    focused: bool,
+    status: Option<&StatusMessage>,
+    theme: &Theme,
+) {
+    state.selected_row = state.selected_row.min(result.rows.len() - 1);
+    let row = &result.rows[state.selected_row];
+    let name_width = result.columns.iter().map(|c| c.width()).max().unwrap_or(0).min(30);
+    let indent = " ".repeat(name_width + 3);
+
+    let mut lines: Vec<Line> = Vec::new();
+    for (column, cell) in result.columns.iter().zip(row) {
+        let name = Span::styled(format!(" {:<name_width$}  ", column), theme.header_style());
+        let Some(value) = cell else {
+            lines.push(Line::from(vec![name, Span::styled("NULL", theme.null_style())]));
+            continue;
+        };
+        let mut value_lines = value.lines();
+        let first = value_lines.next().unwrap_or("");
+        lines.push(Line::from(vec![name, Span::styled(first.to_string(), theme.text_style())]));
+        for line in value_lines {
+            lines.push(Line::styled(format!("{indent}{line}"), theme.text_style()));
+        }
+    }
+
+    let visible = area.height.saturating_sub(2) as usize;
+    let width = area.width.saturating_sub(2).max(1) as usize;
+    let wrapped: usize = lines.iter().map(|line| line.width().max(1).div_ceil(width)).sum();
+    state.record_scroll = state.record_scroll.min(wrapped.saturating_sub(visible));
+    state.header_region = HeaderRegion::default();
+    state.scrollbar_region.vertical = None;
+    state.scrollbar_region.horizontal = None;
+    state.scrollbar_region.visible_height = 1;
+
+    let offset = result.page_offset.unwrap_or(0);
+    let title = format!(
+        " Record {} of {} (r: back to the grid) ",
+        offset + state.selected_row + 1,
+        offset + result.rows.len()
+    );
+    frame.render_widget(
+        Paragraph::new(lines)
+            .wrap(ratatui::widgets::Wrap { trim: false })
+            .scroll((state.record_scroll as u16, 0))
+            .block(
+                Block::default()
+                    .title(title)
+                    .title(status_line(status, theme))
+                    .borders(Borders::ALL)
+                    .border_style(theme.block_style(focused))
+                    .style(Style::default().bg(theme.bg_secondary)),
+            ),
+        area,
+    );
+}
+
+pub fn status_line<'a>(status: Option<&'a StatusMessage>, theme: &Theme) -> Line<'a> {
+    match status {
+        Some(s) => {
+            let style = if s.is_error { theme.error_style() } else { theme.accent_style() };
+            Line::styled(format!(" {} ", s.text), style).right_aligned()
+        }
+        None => Line::default(),
+    }
+}
+
+fn format_elapsed(elapsed: Duration) -> String {
+    if elapsed.as_millis() < 1000 {
+        format!("{}ms", elapsed.as_millis())
+    } else {
+        format!("{:.2}s", elapsed.as_secs_f64())
+    }
+}
+
+fn cell_style(column: &str, value: &str, config: &Config, theme: &Theme) -> Style {
+    // Lossily decoded text always stands out so it isn't mistaken for real data.
+    if value.contains(char::REPLACEMENT_CHARACTER) {
+        return theme.error_style().add_modifier(Modifier::ITALIC);
+    }
+
+    // User rules take precedence over the built-in ones; the last match wins.
+    if let Some(rule) = config.format_rules.iter().rev().find(|r| r.matches(column, value)) {
+        return Style::default().fg(theme.color(rule.color));
+    }
+
+    if config.conditional_formatting {
+        match value {
+            "true" => return theme.table_style(),
+            "false" => return theme.error_style(),
+            v if v.starts_with('-') && v.parse::<f64>().is_ok() => return theme.error_style(),
+            _ => {}
+        }
+    }
+
+    theme.text_style()
+}
+
+/// Cuts `value` to `max_width` display columns plus an ellipsis, never
+/// splitting a character.
+fn truncate_cell(value: &str, mode: TruncateMode, max_width: usize) -> String {
+    if value.width() <= max_width + 3 {
+        return value.to_string();
+    }
+    match mode {
+        TruncateMode::End => format!("{}...", take_width(value.chars(), max_width)),
+        TruncateMode::Middle => {
+            // Keep both ends visible so IDs and paths stay distinguishable.
+            let head = take_width(value.chars(), (max_width + 2) / 2);
+            let tail: String = take_width(value.chars().rev(), (max_width + 3) / 2)
+                .chars()
+                .rev()
+                .collect();
+            format!("{}…{}", head, tail)
+        }
+    }
+}
+
+/// Leading characters of `chars` that fit in `width` display columns.
+fn take_width(chars: impl Iterator<Item = char>, width: usize) -> String {
+    let mut used = 0;
+    chars
+        .take_while(|c| {
+            used += c.width().unwrap_or(0);
+            used <= width
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn take_width_never_splits_a_wide_character() {
+        assert_eq!(take_width("東京都".chars(), 5), "東京");
+        assert_eq!(take_width("東京都".chars(), 1), "");
+        assert_eq!(take_width("a😀b".chars(), 2), "a");
+        assert_eq!(take_width("a😀b".chars(), 3), "a😀");
+    }
+
+    #[test]
+    fn truncates_cjk_and_emoji_at_the_end() {
+        assert_eq!(truncate_cell("東京都渋谷区神南", TruncateMode::End, 5), "東京...");
+        assert_eq!(truncate_cell("😀😃😄😁😆😅", TruncateMode::End, 4), "😀😃...");
+        assert_eq!(truncate_cell("東京", TruncateMode::End, 1), "東京");
+    }
+
+    #[test]
+    fn truncates_cjk_and_emoji_in_the_middle() {
+        let cell = truncate_cell("東京都渋谷区神南一丁目", TruncateMode::Middle, 8);
+        assert_eq!(cell, "東京…丁目");
+        assert!(cell.width() <= 8 + 3);
+        assert_eq!(truncate_cell("😀😃😄😁😆😅😂", TruncateMode::Middle, 6), "😀😃…😅😂");
+    }
+}
@@ -0,0 +1,83 @@
+use ratatui::{
+    layout::{Constraint, Layout},
+    text::{Line, Span},
+    widgets::{Clear, Paragraph},
+    Frame,
+};
+
+use super::popup::{centered_rect, popup_block};
+use super::theme::Theme;
+use crate::storage::SavedQuery;
+
+/// The saved queries overlay: every named query, one selected.
+#[derive(Debug, Clone)]
+pub struct SavedQueryList {
+    pub items: Vec<SavedQuery>,
+    pub selected: usize,
+}
+
+impl SavedQueryList {
+    pub fn new(items: Vec<SavedQuery>) -> Self {
+        Self { items, selected: 0 }
+    }
+
+    pub fn select_next(&mut self) {
+        self.selected = (self.selected + 1).min(self.items.len().saturating_sub(1));
+    }
+
+    pub fn select_prev(&mut self) {
+        self.selected = self.selected.saturating_sub(1);
+    }
+
+    pub fn selected_item(&self) -> Option<&SavedQuery> {
+        self.items.get(self.selected)
+    }
+}
+
+pub fn render_saved_queries(frame: &mut Frame, list: &SavedQueryList, theme: &Theme) {
+    let area = centered_rect(frame.area(), 80, 20);
+    frame.render_widget(Clear, area);
+
+    let block = popup_block(" Saved Queries ".to_string(), theme);
+    let inner = block.inner(area);
+    frame.render_widget(block, area);
+
+    let chunks = Layout::vertical([Constraint::Min(1), Constraint::Length(1)]).split(inner);
+
+    let height = chunks[0].height as usize;
+    let offset = (list.selected + 1).saturating_sub(height);
+    let name_width = list.items.iter().map(|q| q.name.chars().count()).max().unwrap_or(0).min(30);
+    let lines: Vec<Line> = list
+        .items
+        .iter()
+        .enumerate()
+        .skip(offset)
+        .take(height)
+        .map(|(i, query)| {
+            let (name_style, text_style) = if i == list.selected {
+                (theme.selected_style(), theme.selected_style())
+            } else {
+                (theme.text_style(), theme.muted_style())
+            };
+            // Only the first line of the query, as a reminder of what it does.
+            let first_line = query.query_text.lines().find(|l| !l.trim().is_empty()).unwrap_or("").trim();
+            Line::from(vec![
+                Span::styled(format!(" {:<name_width$}  ", query.name), name_style),
+                Span::styled(first_line.to_string(), text_style),
+            ])
+        })
+        .collect();
+    if lines.is_empty() {
+        frame.render_widget(
+            Paragraph::new(" No saved queries yet; Ctrl+S in the editor saves one").style(theme.muted_style()),
+            chunks[0],
+        );
+    } else {
+        frame.render_widget(Paragraph::new(lines), chunks[0]);
+    }
+
+    frame.render_widget(
+        Paragraph::new("Enter: load into the editor  |  d: delete  |  Esc: close").style(theme.muted_style()),
+        chunks[1],
+    );
+}
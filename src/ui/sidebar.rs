@@ -1,19 +1,33 @@
-use std::collections::BTreeMap;
 use ratatui::{
     layout::Rect,
     style::Style,
     text::{Line, Span},
-    widgets::{Block, Borders, List, ListItem, Scrollbar, ScrollbarOrientation, ScrollbarState},
+    widgets::{Block, Borders, List, ListItem, Paragraph, Scrollbar, ScrollbarOrientation, ScrollbarState},
     Frame,
 };
 
-use crate::db::TableInfo;
+use crate::db::{ColumnInfo, TableInfo, TableKind};
 use super::theme::{icons, Theme};
 
 #[derive(Debug, Clone)]
 pub enum TreeNode {
-    Schema { name: String, expanded: bool },
-    Table { schema: String, name: String },
+    /// `loaded` is false until the schema's tables have been fetched.
+    Schema { name: String, expanded: bool, loaded: bool },
+    /// `loaded` is false until the table's columns have been fetched.
+    Table { schema: String, name: String, expanded: bool, loaded: bool, row_count: Option<u64> },
+    /// Listed after the tables of its schema; opens and expands like a table.
+    View { schema: String, name: String, expanded: bool, loaded: bool },
+    Column { schema: String, table: String, name: String, data_type: String },
+}
+
+/// What a reload of the tree keeps: the expanded schemas and tables, the
+/// selected node and the filter.
+#[derive(Debug)]
+pub struct TreeSnapshot {
+    pub schemas: Vec<String>,
+    pub tables: Vec<(String, String)>,
+    selected: Option<TreeNode>,
+    filter: String,
 }
 
 #[derive(Debug, Default)]
@@ -21,48 +35,210 @@ pub struct TreeState {
     pub nodes: Vec<TreeNode>,
     pub selected: usize,
     pub scroll_offset: usize,
+    pub search: String,
+    pub searching: bool,
+    /// Only nodes whose names contain this are listed, with their schemas.
+    pub filter: String,
+    pub filtering: bool,
+    /// Schema whose tables should be fetched after the next frame is drawn.
+    pub loading: Option<String>,
+    /// Table (schema, name) whose columns should be fetched likewise.
+    pub loading_columns: Option<(String, String)>,
 }
 
 impl TreeState {
-    pub fn from_tables(tables: &[TableInfo]) -> Self {
-        let mut grouped: BTreeMap<&str, Vec<&str>> = BTreeMap::new();
-        for t in tables {
-            grouped.entry(&t.schema).or_default().push(&t.name);
-        }
-
-        let mut nodes = Vec::new();
-        for (schema, table_names) in grouped {
-            nodes.push(TreeNode::Schema {
-                name: schema.to_string(),
-                expanded: true,
-            });
-            for name in table_names {
-                nodes.push(TreeNode::Table {
-                    schema: schema.to_string(),
-                    name: name.to_string(),
-                });
+    pub fn from_schemas(schemas: &[String]) -> Self {
+        let nodes = schemas
+            .iter()
+            .map(|name| TreeNode::Schema {
+                name: name.clone(),
+                expanded: false,
+                loaded: false,
+            })
+            .collect();
+
+        let mut state = Self {
+            nodes,
+            ..Self::default()
+        };
+        // Nothing to choose between, so open the only schema straight away.
+        if schemas.len() == 1 {
+            state.toggle_selected();
+        }
+        state
+    }
+
+    pub fn snapshot(&self) -> TreeSnapshot {
+        let mut snapshot = TreeSnapshot {
+            schemas: Vec::new(),
+            tables: Vec::new(),
+            selected: self.nodes.get(self.selected).cloned(),
+            filter: self.filter.clone(),
+        };
+        for node in &self.nodes {
+            match node {
+                TreeNode::Schema { name, expanded: true, loaded: true } => snapshot.schemas.push(name.clone()),
+                TreeNode::Table { schema, name, expanded: true, loaded: true, .. }
+                | TreeNode::View { schema, name, expanded: true, loaded: true } => {
+                    snapshot.tables.push((schema.clone(), name.clone()))
+                }
+                _ => {}
             }
         }
+        snapshot
+    }
+
+    /// Reselects the node selected when `snapshot` was taken, if it is still
+    /// there, and reapplies the filter.
+    pub fn restore_selection(&mut self, snapshot: &TreeSnapshot) {
+        self.filter = snapshot.filter.clone();
+        if let Some(selected) = &snapshot.selected
+            && let Some(idx) = self.nodes.iter().position(|n| same_node(n, selected))
+        {
+            self.selected = idx;
+        }
+        self.select_visible();
+    }
 
-        Self {
-            nodes,
-            selected: 0,
-            scroll_offset: 0,
+    /// Inserts the fetched tables under their schema and expands it.
+    pub fn set_schema_tables(&mut self, schema: &str, tables: &[TableInfo]) {
+        self.loading = None;
+        let Some(idx) = self
+            .nodes
+            .iter()
+            .position(|n| matches!(n, TreeNode::Schema { name, .. } if name == schema))
+        else {
+            return;
+        };
+        if let TreeNode::Schema { expanded, loaded, .. } = &mut self.nodes[idx] {
+            *expanded = true;
+            *loaded = true;
+        }
+        let table_nodes = tables.iter().map(|t| match t.kind {
+            TableKind::Table => TreeNode::Table {
+                schema: t.schema.clone(),
+                name: t.name.clone(),
+                expanded: false,
+                loaded: false,
+                row_count: t.row_count,
+            },
+            TableKind::View => TreeNode::View {
+                schema: t.schema.clone(),
+                name: t.name.clone(),
+                expanded: false,
+                loaded: false,
+            },
+        });
+        self.nodes.splice(idx + 1..idx + 1, table_nodes);
+        if self.selected > idx {
+            self.selected += tables.len();
+        }
+    }
+
+    pub fn set_row_counts(&mut self, schema: &str, counts: &[(String, u64)]) {
+        for node in &mut self.nodes {
+            if let TreeNode::Table { schema: s, name, row_count, .. } = node
+                && s == schema
+                && let Some((_, count)) = counts.iter().find(|(table, _)| table == name)
+            {
+                *row_count = Some(*count);
+            }
+        }
+    }
+
+    /// Inserts the fetched columns under their table and expands it.
+    pub fn set_table_columns(&mut self, schema: &str, table: &str, columns: &[ColumnInfo]) {
+        self.loading_columns = None;
+        let Some(idx) = self.nodes.iter().position(
+            |n| matches!(n, TreeNode::Table { schema: s, name, .. } | TreeNode::View { schema: s, name, .. } if s == schema && name == table),
+        ) else {
+            return;
+        };
+        if let TreeNode::Table { expanded, loaded, .. } | TreeNode::View { expanded, loaded, .. } = &mut self.nodes[idx] {
+            *expanded = true;
+            *loaded = true;
+        }
+        let column_nodes = columns.iter().map(|c| TreeNode::Column {
+            schema: schema.to_string(),
+            table: table.to_string(),
+            name: c.name.clone(),
+            data_type: c.data_type.clone(),
+        });
+        self.nodes.splice(idx + 1..idx + 1, column_nodes);
+        if self.selected > idx {
+            self.selected += columns.len();
+        }
+    }
+
+    /// Collapses a table whose columns could not be fetched.
+    pub fn cancel_loading_columns(&mut self) {
+        if let Some((schema, table)) = self.loading_columns.take() {
+            for node in &mut self.nodes {
+                if let TreeNode::Table { schema: s, name, expanded, .. } | TreeNode::View { schema: s, name, expanded, .. } = node
+                    && *s == schema
+                    && *name == table
+                {
+                    *expanded = false;
+                }
+            }
+        }
+    }
+
+    /// Collapses a schema whose tables could not be fetched.
+    pub fn cancel_loading(&mut self) {
+        if let Some(schema) = self.loading.take() {
+            for node in &mut self.nodes {
+                if let TreeNode::Schema { name, expanded, .. } = node
+                    && *name == schema
+                {
+                    *expanded = false;
+                }
+            }
         }
     }
 
     fn visible_indices(&self) -> Vec<usize> {
+        let filter = self.filter.to_lowercase();
+        let matches = |name: &str| name.to_lowercase().contains(&filter);
         let mut visible = Vec::new();
-        let mut current_schema_expanded = true;
+        // Tables shown under the current schema: all, none, or (while
+        // filtering) only the matching ones, whether or not it is expanded.
+        let mut show_tables = Some(true);
+        let mut current_table_expanded = true;
+        let mut table_visible = true;
 
         for (idx, node) in self.nodes.iter().enumerate() {
             match node {
-                TreeNode::Schema { expanded, .. } => {
-                    visible.push(idx);
-                    current_schema_expanded = *expanded;
+                TreeNode::Schema { name, expanded, .. } => {
+                    let (shown, tables) = if filter.is_empty() || (*expanded && matches(name)) {
+                        (true, expanded.then_some(true))
+                    } else if self.nodes[idx + 1..]
+                        .iter()
+                        .take_while(|n| !matches!(n, TreeNode::Schema { .. }))
+                        .any(|n| matches!(n, TreeNode::Table { name, .. } | TreeNode::View { name, .. } if matches(name)))
+                    {
+                        (true, Some(false))
+                    } else {
+                        (matches(name), None)
+                    };
+                    show_tables = tables;
+                    if shown {
+                        visible.push(idx);
+                    }
+                }
+                TreeNode::Table { name, expanded, .. } | TreeNode::View { name, expanded, .. } => {
+                    table_visible = match show_tables {
+                        Some(true) => true,
+                        Some(false) => matches(name),
+                        None => false,
+                    };
+                    if table_visible {
+                        visible.push(idx);
+                    }
+                    current_table_expanded = *expanded;
                 }
-                TreeNode::Table { .. } => {
-                    if current_schema_expanded {
+                TreeNode::Column { .. } => {
+                    if table_visible && current_table_expanded {
                         visible.push(idx);
                     }
                 }
@@ -71,6 +247,19 @@ impl TreeState {
         visible
     }
 
+    /// Moves the selection onto a listed node after the filter changed.
+    pub fn select_visible(&mut self) {
+        let visible = self.visible_indices();
+        if !visible.contains(&self.selected) {
+            let first_table = visible
+                .iter()
+                .find(|&&idx| matches!(self.nodes[idx], TreeNode::Table { .. } | TreeNode::View { .. }));
+            if let Some(&idx) = first_table.or(visible.first()) {
+                self.selected = idx;
+            }
+        }
+    }
+
     pub fn visible_nodes(&self) -> Vec<(usize, &TreeNode)> {
         self.visible_indices()
             .into_iter()
@@ -113,26 +302,152 @@ impl TreeState {
     }
 
     pub fn toggle_selected(&mut self) {
-        if let Some(TreeNode::Schema { expanded, .. }) = self.nodes.get_mut(self.selected) {
-            *expanded = !*expanded;
+        match self.nodes.get_mut(self.selected) {
+            Some(TreeNode::Schema { name, expanded, loaded }) => {
+                *expanded = !*expanded;
+                if *expanded && !*loaded {
+                    self.loading = Some(name.clone());
+                }
+            }
+            Some(
+                TreeNode::Table { schema, name, expanded, loaded, .. } | TreeNode::View { schema, name, expanded, loaded },
+            ) => {
+                *expanded = !*expanded;
+                if *expanded && !*loaded {
+                    self.loading_columns = Some((schema.clone(), name.clone()));
+                }
+            }
+            _ => {}
+        }
+    }
+
+    pub fn expand_selected(&mut self) {
+        if let Some(
+            TreeNode::Schema { expanded: false, .. }
+            | TreeNode::Table { expanded: false, .. }
+            | TreeNode::View { expanded: false, .. },
+        ) = self.nodes.get(self.selected)
+        {
+            self.toggle_selected();
+        }
+    }
+
+    /// Collapses the selected node, or moves to its parent when there is
+    /// nothing to collapse.
+    pub fn collapse_selected(&mut self) {
+        let parent = match self.nodes.get(self.selected) {
+            Some(TreeNode::Schema { .. } | TreeNode::Table { expanded: true, .. } | TreeNode::View { expanded: true, .. }) => {
+                Some(self.selected)
+            }
+            Some(TreeNode::Table { .. } | TreeNode::View { .. }) => self.nodes[..self.selected]
+                .iter()
+                .rposition(|n| matches!(n, TreeNode::Schema { .. })),
+            Some(TreeNode::Column { .. }) => self.nodes[..self.selected]
+                .iter()
+                .rposition(|n| matches!(n, TreeNode::Table { .. } | TreeNode::View { .. })),
+            None => None,
+        };
+        let Some(idx) = parent else {
+            return;
+        };
+        self.selected = idx;
+        if let Some(TreeNode::Schema { expanded, .. } | TreeNode::Table { expanded, .. } | TreeNode::View { expanded, .. }) =
+            self.nodes.get_mut(idx)
+        {
+            *expanded = false;
         }
     }
 
+    /// The selected table or view, or the one of the selected column.
     pub fn get_selected_table(&self) -> Option<(&str, &str)> {
         match self.nodes.get(self.selected) {
-            Some(TreeNode::Table { schema, name }) => Some((schema.as_str(), name.as_str())),
+            Some(TreeNode::Table { schema, name, .. } | TreeNode::View { schema, name, .. }) => {
+                Some((schema.as_str(), name.as_str()))
+            }
+            Some(TreeNode::Column { schema, table, .. }) => Some((schema.as_str(), table.as_str())),
             _ => None,
         }
     }
 
+    pub fn schema_names(&self) -> impl Iterator<Item = &str> {
+        self.nodes.iter().filter_map(|n| match n {
+            TreeNode::Schema { name, .. } => Some(name.as_str()),
+            _ => None,
+        })
+    }
+
+    /// Whether the tables of every schema have been fetched.
+    pub fn all_schemas_loaded(&self) -> bool {
+        !self.nodes.iter().any(|n| matches!(n, TreeNode::Schema { loaded: false, .. }))
+    }
+
+    pub fn is_selected_table(&self) -> bool {
+        matches!(self.nodes.get(self.selected), Some(TreeNode::Table { .. } | TreeNode::View { .. }))
+    }
+
     pub fn is_selected_schema(&self) -> bool {
         matches!(self.nodes.get(self.selected), Some(TreeNode::Schema { .. }))
     }
 
-    pub fn select_by_click(&mut self, visible_index: usize) {
+    pub fn select_by_click(&mut self, visible_index: usize) -> bool {
         let visible = self.visible_indices();
         if let Some(&real_idx) = visible.get(visible_index) {
             self.selected = real_idx;
+            return true;
+        }
+        false
+    }
+
+    pub fn node_matches_search(&self, node: &TreeNode) -> bool {
+        if self.search.is_empty() {
+            return false;
+        }
+        let name = match node {
+            TreeNode::Schema { name, .. } | TreeNode::Table { name, .. } | TreeNode::View { name, .. } => name,
+            TreeNode::Column { .. } => return false,
+        };
+        name.to_lowercase().contains(&self.search.to_lowercase())
+    }
+
+    fn search_matches(&self) -> Vec<usize> {
+        self.nodes
+            .iter()
+            .enumerate()
+            .filter(|(_, node)| self.node_matches_search(node))
+            .map(|(idx, _)| idx)
+            .collect()
+    }
+
+    /// Moves the selection to the next (or previous) node matching the
+    /// search term, expanding its schema so the whole tree stays in view.
+    /// `include_current` lets the live search keep a still-matching node.
+    pub fn jump_to_match(&mut self, forward: bool, include_current: bool) {
+        let matches = self.search_matches();
+        if matches.is_empty() {
+            return;
+        }
+
+        let target = if forward {
+            matches
+                .iter()
+                .find(|&&idx| idx > self.selected || (include_current && idx == self.selected))
+                .or(matches.first())
+        } else {
+            matches.iter().rev().find(|&&idx| idx < self.selected).or(matches.last())
+        };
+
+        if let Some(&idx) = target {
+            self.expand_parent(idx);
+            self.selected = idx;
+        }
+    }
+
+    fn expand_parent(&mut self, idx: usize) {
+        let parent = self.nodes[..=idx]
+            .iter()
+            .rposition(|node| matches!(node, TreeNode::Schema { .. }));
+        if let Some(TreeNode::Schema { expanded, .. }) = parent.and_then(|p| self.nodes.get_mut(p)) {
+            *expanded = true;
         }
     }
 
@@ -146,11 +461,37 @@ impl TreeState {
         if selected_visible_idx < self.scroll_offset {
             self.scroll_offset = selected_visible_idx;
         } else if selected_visible_idx >= self.scroll_offset + visible_height {
-            self.scroll_offset = selected_visible_idx.saturating_sub(visible_height - 1);
+            self.scroll_offset = selected_visible_idx.saturating_sub(visible_height.saturating_sub(1));
         }
     }
 }
 
+/// `1234` → `1.2k`, `5600000` → `5.6M`.
+fn compact_count(n: u64) -> String {
+    match n {
+        0..1_000 => n.to_string(),
+        1_000..1_000_000 => format!("{:.1}k", n as f64 / 1e3),
+        1_000_000..1_000_000_000 => format!("{:.1}M", n as f64 / 1e6),
+        _ => format!("{:.1}B", n as f64 / 1e9),
+    }
+}
+
+/// Whether two nodes name the same schema, table or column.
+fn same_node(a: &TreeNode, b: &TreeNode) -> bool {
+    match (a, b) {
+        (TreeNode::Schema { name: a, .. }, TreeNode::Schema { name: b, .. }) => a == b,
+        (
+            TreeNode::Table { schema: sa, name: a, .. } | TreeNode::View { schema: sa, name: a, .. },
+            TreeNode::Table { schema: sb, name: b, .. } | TreeNode::View { schema: sb, name: b, .. },
+        ) => sa == sb && a == b,
+        (
+            TreeNode::Column { schema: sa, table: ta, name: a, .. },
+            TreeNode::Column { schema: sb, table: tb, name: b, .. },
+        ) => sa == sb && ta == tb && a == b,
+        _ => false,
+    }
+}
+
 pub fn render_sidebar(
     frame: &mut Frame,
     area: Rect,
@@ -158,7 +499,12 @@ pub fn render_sidebar(
     focused: bool,
     theme: &Theme,
 ) -> Rect {
-    let visible_height = area.height.saturating_sub(2) as usize;
+    if area.is_empty() {
+        return area;
+    }
+
+    let show_search = tree_state.searching || !tree_state.search.is_empty();
+    let visible_height = area.height.saturating_sub(if show_search { 3 } else { 2 }) as usize;
 
     tree_state.update_scroll(visible_height);
 
@@ -173,39 +519,95 @@ pub fn render_sidebar(
         .take(visible_height)
         .map(|(idx, node)| {
             let is_selected = *idx == selected;
+            let is_match = tree_state.node_matches_search(node);
             match node {
-                TreeNode::Schema { name, expanded } => {
+                TreeNode::Schema { name, expanded, loaded } => {
                     let icon = if *expanded { icons::COLLAPSE } else { icons::EXPAND };
+                    let marker = if tree_state.loading.as_deref() == Some(name.as_str()) {
+                        " loading…"
+                    } else if !*loaded {
+                        " …"
+                    } else {
+                        ""
+                    };
                     let style = if is_selected {
                         theme.selected_style()
+                    } else if is_match {
+                        theme.search_match_style()
                     } else {
                         theme.schema_style()
                     };
                     ListItem::new(Line::from(vec![
                         Span::styled(format!(" {} ", icon), theme.dim_style()),
                         Span::styled(name.as_str(), style),
+                        Span::styled(marker, theme.muted_style()),
                     ]))
                 }
-                TreeNode::Table { name, .. } => {
+                TreeNode::Table { schema, name, expanded, row_count, .. } => {
                     let style = if is_selected {
                         theme.selected_style()
+                    } else if is_match {
+                        theme.search_match_style()
                     } else {
                         theme.table_style()
                     };
+                    let icon = if *expanded { icons::COLLAPSE } else { icons::EXPAND };
+                    let loading = tree_state
+                        .loading_columns
+                        .as_ref()
+                        .is_some_and(|(s, t)| s == schema && t == name);
                     ListItem::new(Line::from(vec![
-                        Span::raw("    "),
+                        Span::styled(format!("   {} ", icon), theme.dim_style()),
                         Span::styled(icons::TABLE, theme.dim_style()),
                         Span::raw(" "),
                         Span::styled(name.as_str(), style),
+                        Span::styled(
+                            row_count.map(|n| format!(" ({})", compact_count(n))).unwrap_or_default(),
+                            theme.dim_style(),
+                        ),
+                        Span::styled(if loading { " loading…" } else { "" }, theme.muted_style()),
+                    ]))
+                }
+                TreeNode::View { schema, name, expanded, .. } => {
+                    let style = if is_selected {
+                        theme.selected_style()
+                    } else if is_match {
+                        theme.search_match_style()
+                    } else {
+                        theme.view_style()
+                    };
+                    let icon = if *expanded { icons::COLLAPSE } else { icons::EXPAND };
+                    let loading = tree_state
+                        .loading_columns
+                        .as_ref()
+                        .is_some_and(|(s, t)| s == schema && t == name);
+                    ListItem::new(Line::from(vec![
+                        Span::styled(format!("   {} ", icon), theme.dim_style()),
+                        Span::styled(icons::VIEW, theme.dim_style()),
+                        Span::raw(" "),
+                        Span::styled(name.as_str(), style),
+                        Span::styled(if loading { " loading…" } else { "" }, theme.muted_style()),
+                    ]))
+                }
+                TreeNode::Column { name, data_type, .. } => {
+                    let style = if is_selected { theme.selected_style() } else { theme.text_style() };
+                    ListItem::new(Line::from(vec![
+                        Span::styled(format!("       {} ", icons::COLUMN), theme.dim_style()),
+                        Span::styled(name.as_str(), style),
+                        Span::styled(format!(" {}", data_type), theme.dim_style()),
                     ]))
                 }
             }
         })
         .collect();
 
-    let title = format!(" {} Database ", icons::DATABASE);
+    let mut title = vec![Span::raw(format!(" {} Database ", icons::DATABASE))];
+    if tree_state.filtering || !tree_state.filter.is_empty() {
+        title.push(Span::styled(format!("filter: {}", tree_state.filter), theme.accent_style()));
+        title.push(Span::styled(if tree_state.filtering { "█ " } else { " " }, theme.muted_style()));
+    }
     let block = Block::default()
-        .title(title)
+        .title(Line::from(title))
         .borders(Borders::ALL)
         .border_style(theme.block_style(focused))
         .style(Style::default().bg(theme.bg_secondary));
@@ -214,7 +616,20 @@ pub fn render_sidebar(
 
     frame.render_widget(list, area);
 
-    if total_visible > visible_height {
+    if show_search && area.height > 2 {
+        let cursor = if tree_state.searching { "█" } else { "  (n/N)" };
+        let search_area = Rect::new(area.x + 1, area.y + area.height - 2, area.width.saturating_sub(2), 1);
+        frame.render_widget(
+            Paragraph::new(Line::from(vec![
+                Span::styled("/", theme.accent_style()),
+                Span::styled(tree_state.search.as_str(), theme.text_style()),
+                Span::styled(cursor, theme.muted_style()),
+            ])),
+            search_area,
+        );
+    }
+
+    if total_visible > visible_height && area.width > 2 && area.height > 2 {
         let scrollbar = Scrollbar::new(ScrollbarOrientation::VerticalRight)
             .begin_symbol(Some("▲"))
             .end_symbol(Some("▼"))
@@ -0,0 +1,138 @@
+use ratatui::{
+    text::{Line, Span},
+    widgets::{Clear, Paragraph},
+    Frame,
+};
+
+use super::popup::{centered_rect, popup_block};
+use super::theme::Theme;
+
+/// Summary of one column over the rows loaded in the grid.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ColumnStats {
+    /// Cells that parsed as numbers.
+    pub numeric: usize,
+    pub nulls: usize,
+    /// Non-NULL cells that are not numbers; left out of the aggregates.
+    pub non_numeric: usize,
+    pub min: Option<f64>,
+    pub max: Option<f64>,
+    pub sum: f64,
+}
+
+impl ColumnStats {
+    pub fn mean(&self) -> Option<f64> {
+        (self.numeric > 0).then(|| self.sum / self.numeric as f64)
+    }
+}
+
+/// Aggregates column `col` of `rows`, skipping NULLs and cells that are not
+/// finite numbers.
+pub fn column_stats(rows: &[Vec<Option<String>>], col: usize) -> ColumnStats {
+    let mut stats = ColumnStats::default();
+    for cell in rows.iter().map(|row| row.get(col).cloned().flatten()) {
+        let Some(cell) = cell else {
+            stats.nulls += 1;
+            continue;
+        };
+        match cell.trim().parse::<f64>() {
+            Ok(v) if v.is_finite() => {
+                stats.numeric += 1;
+                stats.sum += v;
+                stats.min = Some(stats.min.map_or(v, |m| m.min(v)));
+                stats.max = Some(stats.max.map_or(v, |m| m.max(v)));
+            }
+            _ => stats.non_numeric += 1,
+        }
+    }
+    stats
+}
+
+/// Whole numbers without a fraction, others to six decimal places at most.
+fn format_number(v: f64) -> String {
+    if v.fract() == 0.0 && v.abs() < 1e15 {
+        format!("{v:.0}")
+    } else {
+        let text = format!("{v:.6}");
+        text.trim_end_matches('0').trim_end_matches('.').to_string()
+    }
+}
+
+pub fn render_column_stats(frame: &mut Frame, column: &str, rows: usize, stats: &ColumnStats, theme: &Theme) {
+    let value = |v: Option<f64>| v.map_or_else(|| "—".to_string(), format_number);
+    let entries = [
+        ("min", value(stats.min)),
+        ("max", value(stats.max)),
+        ("sum", value((stats.numeric > 0).then_some(stats.sum))),
+        ("mean", value(stats.mean())),
+        ("numbers", stats.numeric.to_string()),
+        ("nulls", stats.nulls.to_string()),
+        ("other", stats.non_numeric.to_string()),
+    ];
+    let mut lines: Vec<Line> = entries
+        .into_iter()
+        .map(|(label, value)| {
+            Line::from(vec![
+                Span::styled(format!(" {label:<8} "), theme.muted_style()),
+                Span::styled(value, theme.text_style()),
+            ])
+        })
+        .collect();
+    lines.push(Line::from(""));
+    lines.push(Line::styled(
+        format!(" Over the {rows} loaded rows only, not the whole table"),
+        theme.dim_style(),
+    ));
+
+    let area = centered_rect(frame.area(), 56, lines.len() as u16 + 2);
+    frame.render_widget(Clear, area);
+    frame.render_widget(
+        Paragraph::new(lines).block(popup_block(format!(" {column} (s/Esc to close) "), theme)),
+        area,
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rows(cells: &[Option<&str>]) -> Vec<Vec<Option<String>>> {
+        cells.iter().map(|cell| vec![cell.map(str::to_string)]).collect()
+    }
+
+    #[test]
+    fn empty_input_has_no_aggregates() {
+        let stats = column_stats(&[], 0);
+        assert_eq!((stats.numeric, stats.nulls, stats.non_numeric), (0, 0, 0));
+        assert_eq!((stats.min, stats.max, stats.mean()), (None, None, None));
+    }
+
+    #[test]
+    fn all_null_column_counts_only_nulls() {
+        let stats = column_stats(&rows(&[None, None, None]), 0);
+        assert_eq!((stats.numeric, stats.nulls, stats.non_numeric), (0, 3, 0));
+        assert_eq!(stats.mean(), None);
+    }
+
+    #[test]
+    fn skips_non_numeric_cells() {
+        let stats = column_stats(&rows(&[Some("4"), Some("abc"), None, Some("NaN"), Some("inf"), Some(" 6 ")]), 0);
+        assert_eq!((stats.numeric, stats.nulls, stats.non_numeric), (2, 1, 3));
+        assert_eq!((stats.min, stats.max), (Some(4.0), Some(6.0)));
+        assert_eq!(stats.mean(), Some(5.0));
+    }
+
+    #[test]
+    fn aggregates_numbers() {
+        let stats = column_stats(&rows(&[Some("1.5"), Some("-2"), Some("10"), Some("0.5")]), 0);
+        assert_eq!(stats.numeric, 4);
+        assert_eq!((stats.min, stats.max, stats.sum), (Some(-2.0), Some(10.0), 10.0));
+        assert_eq!(stats.mean(), Some(2.5));
+    }
+
+    #[test]
+    fn missing_column_counts_as_null() {
+        let stats = column_stats(&rows(&[Some("1")]), 3);
+        assert_eq!((stats.numeric, stats.nulls), (0, 1));
+    }
+}
@@ -1,4 +1,9 @@
+use std::path::Path;
+use std::str::FromStr;
+
+use anyhow::Result;
 use ratatui::style::{Color, Modifier, Style};
+use serde::Deserialize;
 
 #[derive(Debug, Clone)]
 pub struct Theme {
@@ -15,6 +20,155 @@ pub struct Theme {
     pub border_focused: Color,
     pub schema: Color,
     pub table: Color,
+    /// Colors the terminal can show; see `downgraded`.
+    pub level: ColorLevel,
+}
+
+/// How many colors the terminal can display.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ColorLevel {
+    TrueColor,
+    Ansi256,
+    Ansi16,
+}
+
+impl ColorLevel {
+    /// `CRUX_COLORS` (`truecolor`, `256` or `16`) when set, otherwise a guess
+    /// from `COLORTERM` and `TERM`. `COLORTERM` is often not forwarded over
+    /// SSH, so a `*-256color` terminal without it gets the 256-color palette.
+    pub fn detect() -> Self {
+        let var = |name| std::env::var(name).unwrap_or_default().to_ascii_lowercase();
+        if let Some(level) = Self::parse(&var("CRUX_COLORS")) {
+            return level;
+        }
+        let term = var("TERM");
+        if matches!(var("COLORTERM").as_str(), "truecolor" | "24bit") || term.ends_with("-direct") {
+            ColorLevel::TrueColor
+        } else if term.contains("256") {
+            ColorLevel::Ansi256
+        } else if term.is_empty() && cfg!(windows) {
+            // Windows Terminal and recent consoles set no TERM but do 24-bit color.
+            ColorLevel::TrueColor
+        } else {
+            ColorLevel::Ansi16
+        }
+    }
+
+    fn parse(value: &str) -> Option<Self> {
+        match value {
+            "truecolor" | "24bit" => Some(ColorLevel::TrueColor),
+            "256" => Some(ColorLevel::Ansi256),
+            "16" => Some(ColorLevel::Ansi16),
+            _ => None,
+        }
+    }
+
+    /// The nearest color this level can show.
+    pub fn map(self, color: Color) -> Color {
+        match (self, color) {
+            (ColorLevel::TrueColor, _) => color,
+            (ColorLevel::Ansi256, Color::Rgb(r, g, b)) => Color::Indexed(nearest_256(r, g, b)),
+            (ColorLevel::Ansi16, Color::Rgb(r, g, b)) => nearest_16(r, g, b),
+            (ColorLevel::Ansi16, Color::Indexed(i)) if i >= 16 => {
+                let (r, g, b) = indexed_rgb(i);
+                nearest_16(r, g, b)
+            }
+            _ => color,
+        }
+    }
+}
+
+/// Channel values of the xterm 6×6×6 color cube (indices 16–231).
+const CUBE_LEVELS: [u8; 6] = [0, 95, 135, 175, 215, 255];
+
+/// Approximate RGB of the 16 basic colors, as xterm draws them.
+const ANSI_16: [(Color, (u8, u8, u8)); 16] = [
+    (Color::Black, (0, 0, 0)),
+    (Color::Red, (205, 0, 0)),
+    (Color::Green, (0, 205, 0)),
+    (Color::Yellow, (205, 205, 0)),
+    (Color::Blue, (0, 0, 238)),
+    (Color::Magenta, (205, 0, 205)),
+    (Color::Cyan, (0, 205, 205)),
+    (Color::Gray, (229, 229, 229)),
+    (Color::DarkGray, (127, 127, 127)),
+    (Color::LightRed, (255, 0, 0)),
+    (Color::LightGreen, (0, 255, 0)),
+    (Color::LightYellow, (255, 255, 0)),
+    (Color::LightBlue, (92, 92, 255)),
+    (Color::LightMagenta, (255, 0, 255)),
+    (Color::LightCyan, (0, 255, 255)),
+    (Color::White, (255, 255, 255)),
+];
+
+fn distance((r1, g1, b1): (u8, u8, u8), (r2, g2, b2): (u8, u8, u8)) -> u32 {
+    let d = |a: u8, b: u8| (a as i32 - b as i32).pow(2) as u32;
+    d(r1, r2) + d(g1, g2) + d(b1, b2)
+}
+
+/// The closer of the nearest cube entry and the nearest gray ramp entry.
+fn nearest_256(r: u8, g: u8, b: u8) -> u8 {
+    let cube_index = |v: u8| {
+        (0..CUBE_LEVELS.len())
+            .min_by_key(|&i| (CUBE_LEVELS[i] as i32 - v as i32).abs())
+            .unwrap_or(0) as u8
+    };
+    let (ri, gi, bi) = (cube_index(r), cube_index(g), cube_index(b));
+    let cube = 16 + 36 * ri + 6 * gi + bi;
+    let average = (r as u32 + g as u32 + b as u32) / 3;
+    let gray = 232 + ((average.saturating_sub(3)) / 10).min(23) as u8;
+    if distance((r, g, b), indexed_rgb(gray)) < distance((r, g, b), indexed_rgb(cube)) {
+        gray
+    } else {
+        cube
+    }
+}
+
+/// Grays go to the nearest of the four grays. Anything with a clear hue
+/// keeps it, since plain distance would turn pastel accents gray: the hue
+/// picks one of the six colors and brightness picks its light variant.
+fn nearest_16(r: u8, g: u8, b: u8) -> Color {
+    let (max, min) = (r.max(g).max(b), r.min(g).min(b));
+    if max - min < 64 {
+        return [Color::Black, Color::DarkGray, Color::Gray, Color::White]
+            .into_iter()
+            .zip([0u8, 127, 229, 255])
+            .min_by_key(|&(_, v)| distance((r, g, b), (v, v, v)))
+            .map_or(Color::Reset, |(color, _)| color);
+    }
+    let (rf, gf, bf, chroma) = (r as f32, g as f32, b as f32, (max - min) as f32);
+    let hue = if max == r {
+        60.0 * ((gf - bf) / chroma).rem_euclid(6.0)
+    } else if max == g {
+        60.0 * ((bf - rf) / chroma + 2.0)
+    } else {
+        60.0 * ((rf - gf) / chroma + 4.0)
+    };
+    let sector = (hue / 60.0).round() as usize % 6;
+    let (normal, light) = [
+        (Color::Red, Color::LightRed),
+        (Color::Yellow, Color::LightYellow),
+        (Color::Green, Color::LightGreen),
+        (Color::Cyan, Color::LightCyan),
+        (Color::Blue, Color::LightBlue),
+        (Color::Magenta, Color::LightMagenta),
+    ][sector];
+    if max >= 230 { light } else { normal }
+}
+
+/// RGB of a 256-color palette index.
+fn indexed_rgb(i: u8) -> (u8, u8, u8) {
+    match i {
+        0..16 => ANSI_16[i as usize].1,
+        16..232 => {
+            let i = i - 16;
+            (CUBE_LEVELS[(i / 36) as usize], CUBE_LEVELS[(i / 6 % 6) as usize], CUBE_LEVELS[(i % 6) as usize])
+        }
+        _ => {
+            let v = 8 + 10 * (i - 232);
+            (v, v, v)
+        }
+    }
 }
 
 impl Default for Theme {
@@ -23,7 +177,80 @@ impl Default for Theme {
     }
 }
 
+/// A color in `theme.toml`: `[r, g, b]` or anything ratatui parses,
+/// such as `"#ff79c6"` or `"magenta"`.
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum ThemeColor {
+    Rgb([u8; 3]),
+    Text(String),
+}
+
+impl ThemeColor {
+    fn to_color(&self) -> Result<Color> {
+        match self {
+            ThemeColor::Rgb([r, g, b]) => Ok(Color::Rgb(*r, *g, *b)),
+            ThemeColor::Text(text) => {
+                Color::from_str(text).map_err(|_| anyhow::anyhow!("invalid color: {text}"))
+            }
+        }
+    }
+}
+
+/// Overrides for any subset of the `Theme` fields.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct ThemeFile {
+    bg: Option<ThemeColor>,
+    bg_secondary: Option<ThemeColor>,
+    bg_highlight: Option<ThemeColor>,
+    bg_selected: Option<ThemeColor>,
+    text: Option<ThemeColor>,
+    text_dim: Option<ThemeColor>,
+    text_muted: Option<ThemeColor>,
+    accent: Option<ThemeColor>,
+    error: Option<ThemeColor>,
+    border: Option<ThemeColor>,
+    border_focused: Option<ThemeColor>,
+    schema: Option<ThemeColor>,
+    table: Option<ThemeColor>,
+}
+
 impl Theme {
+    /// `~/.crux/theme.toml` over the dark theme; the dark theme alone if
+    /// the file is missing or invalid.
+    pub fn load() -> Self {
+        crate::config::config_dir()
+            .map(|dir| dir.join("theme.toml"))
+            .and_then(|path| Self::from_file(&path).ok())
+            .unwrap_or_else(Self::dark)
+    }
+
+    pub fn from_file(path: &Path) -> Result<Self> {
+        let file: ThemeFile = toml::from_str(&std::fs::read_to_string(path)?)?;
+        let mut theme = Self::dark();
+        for (slot, value) in [
+            (&mut theme.bg, &file.bg),
+            (&mut theme.bg_secondary, &file.bg_secondary),
+            (&mut theme.bg_highlight, &file.bg_highlight),
+            (&mut theme.bg_selected, &file.bg_selected),
+            (&mut theme.text, &file.text),
+            (&mut theme.text_dim, &file.text_dim),
+            (&mut theme.text_muted, &file.text_muted),
+            (&mut theme.accent, &file.accent),
+            (&mut theme.error, &file.error),
+            (&mut theme.border, &file.border),
+            (&mut theme.border_focused, &file.border_focused),
+            (&mut theme.schema, &file.schema),
+            (&mut theme.table, &file.table),
+        ] {
+            if let Some(value) = value {
+                *slot = value.to_color()?;
+            }
+        }
+        Ok(theme)
+    }
+
     pub fn dark() -> Self {
         Self {
             bg: Color::Rgb(22, 22, 30),
@@ -39,9 +266,37 @@ impl Theme {
             border_focused: Color::Rgb(139, 233, 253),
             schema: Color::Rgb(255, 184, 108),
             table: Color::Rgb(80, 250, 123),
+            level: ColorLevel::TrueColor,
+        }
+    }
+
+    /// This theme with every color mapped to the nearest one `level` can show.
+    pub fn downgraded(&self, level: ColorLevel) -> Self {
+        let map = |color| level.map(color);
+        Self {
+            bg: map(self.bg),
+            bg_secondary: map(self.bg_secondary),
+            bg_highlight: map(self.bg_highlight),
+            bg_selected: map(self.bg_selected),
+            text: map(self.text),
+            text_dim: map(self.text_dim),
+            text_muted: map(self.text_muted),
+            accent: map(self.accent),
+            error: map(self.error),
+            border: map(self.border),
+            border_focused: map(self.border_focused),
+            schema: map(self.schema),
+            table: map(self.table),
+            level,
         }
     }
 
+    /// A color from outside the theme, such as a format rule's, mapped to
+    /// what the terminal can show.
+    pub fn color(&self, color: Color) -> Color {
+        self.level.map(color)
+    }
+
     pub fn text_style(&self) -> Style {
         Style::default().fg(self.text)
     }
@@ -54,6 +309,11 @@ impl Theme {
         Style::default().fg(self.text_muted)
     }
 
+    /// SQL NULL, kept apart from a cell holding the text "NULL".
+    pub fn null_style(&self) -> Style {
+        self.muted_style().add_modifier(Modifier::ITALIC)
+    }
+
     pub fn border_style(&self) -> Style {
         Style::default().fg(self.border)
     }
@@ -96,6 +356,14 @@ impl Theme {
             .add_modifier(Modifier::BOLD)
     }
 
+    /// The cell under the cursor, set apart from the rest of its row.
+    pub fn selected_cell_style(&self) -> Style {
+        Style::default()
+            .fg(self.bg)
+            .bg(self.accent)
+            .add_modifier(Modifier::BOLD)
+    }
+
     pub fn schema_style(&self) -> Style {
         Style::default()
             .fg(self.schema)
@@ -106,6 +374,16 @@ impl Theme {
         Style::default().fg(self.table)
     }
 
+    pub fn view_style(&self) -> Style {
+        Style::default().fg(self.accent)
+    }
+
+    pub fn search_match_style(&self) -> Style {
+        Style::default()
+            .fg(self.accent)
+            .add_modifier(Modifier::UNDERLINED)
+    }
+
     pub fn header_style(&self) -> Style {
         Style::default()
             .fg(self.accent)
@@ -123,12 +401,17 @@ impl Theme {
 
 pub mod icons {
     pub const FOLDER_OPEN: &str = "";
+    pub const VIEW: &str = "";
     pub const TABLE: &str = "";
     pub const DATABASE: &str = "";
     pub const PLAY: &str = "";
     pub const COPY: &str = "";
+    pub const FORMAT: &str = "";
     pub const CLEAR: &str = "";
+    pub const EXPORT: &str = "";
+    pub const COLUMN: &str = "◦";
     pub const EXPAND: &str = "▶";
     pub const COLLAPSE: &str = "▼";
     pub const CONNECTION: &str = "◆";
+    pub const PINNED: &str = "★";
 }